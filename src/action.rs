@@ -1,8 +1,22 @@
-use crate::{app::Focus};
+use crate::{
+    app::Focus,
+    components::{prompt::PromptKind, viewer::Column},
+    config::{bookmarks::Bookmark, saved_searches::Definition, theme::ThemeName},
+    storage::{cloud_uri::CloudUri, ObjectMetadata},
+    transfer::{
+        diff::Diff,
+        sync::{SyncEntry, SyncRequest},
+        JobRequest,
+    },
+};
 
 #[derive(Debug, Clone)]
 pub enum Action {
     Quit,
+    /// Housekeeping between events, sent by the input thread at the tick rate
+    Tick,
+    /// Draw a frame if anything changed, sent by the input thread at the frame rate
+    Render,
     // ListConfiguration(Vec<u8>),
     ConnectionList(Vec<String>),
     ViewerList(Vec<String>),
@@ -14,5 +28,49 @@ pub enum Action {
     // ActivateConfig(CloudProviderConfig),
     Activate(Vec<String>),
     SelectFilteredItem(String, Focus),
+    Download(Vec<String>),
+    Delete(Vec<String>),
+    Upload(Vec<String>),
+    Verify(Vec<String>),
+    Yank(Vec<String>),
+    Cut(Vec<String>),
+    Paste(Vec<String>),
+    Sync(Vec<String>),
+    Compare(Vec<String>),
+    Search(Vec<String>),
+    Reveal(String),
+    GoTo(Vec<String>),
+    Navigate(String),
+    SaveSearch(Definition),
+    SavedSearches,
+    ApplySaved(Definition),
+    /// The differences between two prefixes, worked out on a background thread
+    Compared(Box<(CloudUri, CloudUri)>, Result<Box<Diff>, String>),
+    PlanSync(Box<SyncRequest>),
+    /// The plan for a sync, worked out on a background thread
+    SyncPlanned(Box<SyncRequest>, Result<Vec<SyncEntry>, String>),
+    RunSync(Box<SyncRequest>, Vec<SyncEntry>),
+    RetryTransfer(usize),
+    CancelTransfer(usize),
+    SubmitPrompt(PromptKind, String),
+    Help,
+    Palette,
+    SetTheme(ThemeName),
+    /// Shows or hides a column in the Viewer
+    ToggleColumn(Column),
+    /// Jobs for a download, delete or paste whose prefixes were listed on a background thread, and
+    /// the objects left out of them
+    Expanded(Result<(Vec<JobRequest>, Vec<CloudUri>), String>),
+    /// Metadata of the objects under a listed prefix for the Viewer's columns or filter, fetched on a background thread
+    ListedMetadata(String, Result<Vec<(String, ObjectMetadata)>, String>),
+    Refresh(Vec<String>),
+    Bookmark(Vec<String>),
+    Bookmarks,
+    OpenBookmark(Bookmark),
+    ForgetBookmark(String),
+    /// Lists and opens a prefix that was open when the last session ended
+    RestoreExpanded(String),
+    /// Selects what was selected when the last session ended and turns its listing to the saved page
+    RestoreSelection(String, usize),
     Error(String),
 }
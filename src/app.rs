@@ -1,17 +1,42 @@
+use std::collections::VecDeque;
 use std::result::Result;
-use std::time::Duration;
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::thread;
 
-use crossterm::event::{Event, KeyEvent, MouseEvent};
-use tracing::info;
+use clap::ValueEnum;
+use crossterm::event::{KeyEvent, MouseEvent};
+use tracing::{error, info};
 
-use super::components::connections::Connections;
-use super::components::viewer::Viewer;
+use super::components::compare::Compare;
+use super::components::connections::{Connections, CONNECTIONS_ROOT};
+use super::components::viewer::{Column, Viewer};
 use crate::action::Action;
+use crate::cli::StartAt;
+use crate::components::bookmarks::BookmarkPicker;
 use crate::components::error::ErrorComponent;
+use crate::components::filter::MatchOptions;
 use crate::components::footer::Footer;
+use crate::components::help::{self, Help};
+use crate::components::palette::{CommandPalette, PaletteEntry};
+use crate::components::prompt::{Prompt, PromptKind};
+use crate::components::saved_searches::SavedSearchPicker;
+use crate::components::search::Search;
+use crate::components::sync_review::SyncReview;
+use crate::components::transfers::Transfers;
 use crate::components::{Component as Comp, TreeComponent};
+use crate::config::bookmarks::{self, Bookmark};
+use crate::config::saved_searches::{self, Definition};
+use crate::config::session::{self, Session};
+use crate::config::cloud_provider_config::cloud_provider_kind::CloudProviderKind;
+use crate::config::key_sequences::KeySequences;
+use crate::config::theme::{self, Theme, ThemeName};
 use crate::config::Config;
-use crate::tui::Tui;
+use crate::key::Key;
+use crate::storage::{self, cloud_uri::CloudUri, search::{RunningSearch, SearchPattern}};
+use crate::transfer::diff::{self, Diff};
+use crate::transfer::sync::{self, SyncDirection, SyncEntry, SyncRequest};
+use crate::transfer::{self, Clipboard, Endpoint, JobRequest, TransferKind};
+use crate::tui::{self, Event, Tui};
 
 #[derive(Debug, Clone, Copy)]
 pub enum Focus {
@@ -21,6 +46,16 @@ pub enum Focus {
     ViewerFilter,
     ConnectionFilterResults,
     ViewerFilterResults,
+    DiffFilter,
+    DiffFilterResults,
+    Transfers,
+    SyncReview,
+    Search,
+    SavedSearches,
+    Bookmarks,
+    Prompt,
+    Palette,
+    Help,
     Error,
 }
 
@@ -31,49 +66,129 @@ pub struct App {
     // pub error_component: ErrorComponent,
     pub focus: Focus,
     pub config: Config,
+    /// Connection and URI to open at startup, from the command line
+    pub start_at: StartAt,
+    /// Bindings from the config file that were ignored, shown once the TUI is up
+    pub key_problems: Vec<String>,
+    /// What the previous run had open, restored when nothing else is asked for on the command line
+    pub session: Option<Session>,
+    /// Counts and multi-key sequences typed in the trees
+    pub key_sequences: KeySequences,
+    /// Actions to run before reading the next event, e.g. one picked in the command palette
+    pub queued_actions: VecDeque<Action>,
+    /// Handed to every component to queue actions of its own
+    action_sender: Sender<Action>,
+    action_receiver: Receiver<Action>,
+    /// Housekeeping rounds and frames per second
+    pub tick_rate: f64,
+    pub frame_rate: f64,
+    /// Something changed since the last frame was drawn
+    needs_render: bool,
 }
 
 impl App {
     pub fn new() -> Self {
+        let (action_sender, action_receiver) = mpsc::channel();
         Self {
             _should_quit: false,
             components: vec![
                 Box::new(Connections::default()),
                 Box::new(Viewer::default()),
                 Box::new(Footer::default()),
+                Box::new(Transfers::default()),
+                Box::new(SyncReview::default()),
+                Box::new(Compare::default()),
+                Box::new(Search::default()),
+                Box::new(SavedSearchPicker::default()),
+                Box::new(BookmarkPicker::default()),
+                Box::new(Prompt::default()),
+                Box::new(CommandPalette::default()),
+                Box::new(Help::default()),
                 Box::new(ErrorComponent::default()),
             ],
             // error_component: ErrorComponent::default(),
             focus: Focus::Connections,
             config: Config::default(),
-
+            start_at: StartAt::default(),
+            key_problems: vec![],
+            session: None,
+            key_sequences: KeySequences::default(),
+            queued_actions: VecDeque::new(),
+            action_sender,
+            action_receiver,
+            tick_rate: tui::DEFAULT_TICK_RATE,
+            frame_rate: tui::DEFAULT_FRAME_RATE,
+            needs_render: true,
         }
     }
 
     pub fn run(&mut self) -> Result<(), String> {
         // start the TUI
         info!("Cloud Storage Viewer TUI started");
-        let mut tui = Tui::new()?;
+        let mut tui = Tui::new(self.tick_rate, self.frame_rate)?;
         tui.enter()?;
         tui.clear()?;
 
+        // pick up transfers a previous run did not finish, unless they might write to a bucket
+        if !self.config.read_only {
+            self.config.transfers.restore();
+        }
+
         for component in self.components.iter_mut() {
             component.register_config(&self.config, self.focus)?;
+            component.register_action_sender(self.action_sender.clone());
             component.init(&self.config)?;
 
             let component_name = &component.name();
             info!("Initialized and registerd default config for component {component_name:?}");
         }
 
+        if !self.key_problems.is_empty() {
+            let message = format!("Ignored key bindings:\n{}", self.key_problems.join("\n"));
+            error!("{message}");
+            self.report_error(Action::Error(message))?;
+        }
+
+        if !self.start_at.is_empty() {
+            if let Err(e) = self.start(self.start_at.clone()) {
+                self.report_error(e)?;
+            }
+        } else if let Some(session) = self.session.take() {
+            if let Err(e) = self.restore(session) {
+                self.report_error(e)?;
+            }
+        }
+
         // time to work
         loop {
-            // draw terminal
-            self.render(&mut tui)?;
+            self.queued_actions.extend(self.action_receiver.try_iter());
+            let act = match self.queued_actions.pop_front() {
+                Some(act) => {
+                    // show what the previous queued action changed, e.g. a prefix restored from the session
+                    if self.needs_render {
+                        self.render(&mut tui)?;
+                    }
+                    Ok(act)
+                }
+                None => self.handle_events(&mut tui),
+            };
+            // input events mark the frame themselves; of the rest only actions that change something need one
+            if !matches!(act, Ok(Action::Render | Action::Tick | Action::Nothing | Action::Skip)) {
+                self.needs_render = true;
+            }
 
-            // after drawing, handle terminal events
-            match self.handle_events() {
+            match act.and_then(|act| self.refuse_when_read_only(act)) {
                 Ok(act) => match act {
                     Action::Quit => break,
+                    // nothing changed since the last frame otherwise
+                    Action::Render if self.needs_render => self.render(&mut tui)?,
+                    Action::Tick => match self.tick() {
+                        Ok(changed) => self.needs_render |= changed,
+                        Err(e) => {
+                            self.needs_render = true;
+                            self.report_error(e)?;
+                        }
+                    },
                     Action::ChangeFocus(focus) => self.change_focus(focus),
                     // Action::ListCloudProvider(cloud_provider_config) => {
                     //     self.config.cloud_provider_config = cloud_provider_config;
@@ -96,25 +211,15 @@ impl App {
                                 for component in self.components.iter_mut() {
                                     component.register_config(&self.config, self.focus)?;
                                 }
+                                if let Some(value) = self.config.app_selection.last().filter(|value| value.ends_with('/')).cloned() {
+                                    self.visit(&value);
+                                }
                             }
                         }
                     }
                     Action::ConnectionList(connection_selection) => {
-                        self.config.app_selection = connection_selection.clone();
-                        match self.ls(connection_selection, self.focus) {
-                            Err(e) => if let Action::Error(e) = e {
-                                self.change_focus(Focus::Error);
-                                for component in self.components.iter_mut() {
-                                    component.report_error(&e)?;
-                                }
-                            }
-                            Ok(focus) => {
-                                self.change_focus(focus);
-                                for component in self.components.iter_mut() {
-                                    component.register_config(&self.config, self.focus)?;
-                                }
-                                
-                            }
+                        if let Err(e) = self.connection_list(connection_selection) {
+                            self.report_error(e)?;
                         }
                         // let gcp = self.config.cloud_provider_config.gcs.clone();
                         // info!("{gcp:?}");
@@ -192,6 +297,332 @@ impl App {
                             }
                         }
                     }
+                    Action::Download(selection) => {
+                        if let Err(e) = self.download(selection) {
+                            self.report_error(e)?;
+                        }
+                    }
+                    Action::Delete(selection) => {
+                        match self.targets(selection) {
+                            Err(e) => self.report_error(e)?,
+                            Ok(targets) if targets.is_empty() => {
+                                self.report_error(Action::Error("Nothing selected to delete".to_string()))?
+                            }
+                            Ok(targets) => {
+                                if let Some(prompt) = self.prompt() {
+                                    prompt.open(PromptKind::Delete(targets), "", Focus::Viewer);
+                                }
+                                self.change_focus(Focus::Prompt);
+                            }
+                        }
+                    }
+                    Action::Upload(selection) => {
+                        match self.destination_prefix(selection) {
+                            Err(e) => self.report_error(e)?,
+                            Ok(destination) => {
+                                if let Some(prompt) = self.prompt() {
+                                    prompt.open(PromptKind::Upload(destination.to_string()), "", Focus::Viewer);
+                                }
+                                self.change_focus(Focus::Prompt);
+                            }
+                        }
+                    }
+                    Action::Verify(selection) => {
+                        match selection.last().map(|selected| CloudUri::parse(selected)) {
+                            Some(Ok(object)) if !object.is_prefix() => {
+                                let local = transfer::default_download_dir().join(object.file_name());
+                                if let Some(prompt) = self.prompt() {
+                                    prompt.open(
+                                        PromptKind::Verify(object.to_string()),
+                                        &local.to_string_lossy(),
+                                        Focus::Viewer,
+                                    );
+                                }
+                                self.change_focus(Focus::Prompt);
+                            }
+                            Some(Err(e)) => self.report_error(e)?,
+                            _ => self.report_error(Action::Error("Select an object to verify".to_string()))?,
+                        }
+                    }
+                    Action::Yank(selection) => {
+                        if let Err(e) = self.yank(TransferKind::Copy, selection) {
+                            self.report_error(e)?;
+                        }
+                    }
+                    Action::Cut(selection) => {
+                        if let Err(e) = self.yank(TransferKind::Move, selection) {
+                            self.report_error(e)?;
+                        }
+                    }
+                    Action::Paste(selection) => {
+                        let clipboard = match &self.config.clipboard {
+                            Some(clipboard) => clipboard.clone(),
+                            None => {
+                                self.report_error(Action::Error(
+                                    "Nothing to paste, copy [y] or move [x] something first".to_string(),
+                                ))?;
+                                continue;
+                            }
+                        };
+                        match self.destination_prefix(selection) {
+                            Err(e) => self.report_error(e)?,
+                            Ok(destination) => {
+                                if let Some(prompt) = self.prompt() {
+                                    prompt.open(
+                                        PromptKind::Paste { kind: clipboard.kind, count: clipboard.sources.len() },
+                                        &destination.to_string(),
+                                        Focus::Viewer,
+                                    );
+                                }
+                                self.change_focus(Focus::Prompt);
+                            }
+                        }
+                    }
+                    Action::Sync(selection) => {
+                        match self.destination_prefix(selection) {
+                            Err(e) => self.report_error(e)?,
+                            Ok(prefix) => {
+                                let local = transfer::default_download_dir().join(prefix.file_name());
+                                if let Some(prompt) = self.prompt() {
+                                    prompt.open(PromptKind::Sync(prefix.to_string()), &local.to_string_lossy(), Focus::Viewer);
+                                }
+                                self.change_focus(Focus::Prompt);
+                            }
+                        }
+                    }
+                    Action::Compare(selection) => {
+                        match self.destination_prefix(selection) {
+                            Err(e) => self.report_error(e)?,
+                            Ok(left) => {
+                                if let Some(prompt) = self.prompt() {
+                                    prompt.open(PromptKind::Compare(left.to_string()), &left.to_string(), Focus::Viewer);
+                                }
+                                self.change_focus(Focus::Prompt);
+                            }
+                        }
+                    }
+                    Action::Search(selection) => {
+                        if self.search().is_some_and(|search| search.is_open()) {
+                            self.change_focus(Focus::Search);
+                            continue;
+                        }
+                        let initial = match self.destination_prefix(selection) {
+                            Ok(prefix) => format!("{prefix}**"),
+                            Err(_) => String::new(),
+                        };
+                        if let Some(prompt) = self.prompt() {
+                            prompt.open(PromptKind::Search, &initial, Focus::Viewer);
+                        }
+                        self.change_focus(Focus::Prompt);
+                    }
+                    Action::GoTo(selection) => {
+                        let initial = match self.destination_prefix(selection) {
+                            Ok(prefix) => prefix.to_string(),
+                            Err(_) => String::new(),
+                        };
+                        if let Some(prompt) = self.prompt() {
+                            prompt.open(PromptKind::GoTo, &initial, Focus::Viewer);
+                        }
+                        self.change_focus(Focus::Prompt);
+                    }
+                    Action::Navigate(uri) => {
+                        if let Err(e) = self.go_to(&uri) {
+                            self.report_error(e)?;
+                        }
+                    }
+                    Action::Reveal(uri) => {
+                        if let Err(e) = self.reveal(&uri) {
+                            self.report_error(e)?;
+                        }
+                    }
+                    Action::SaveSearch(definition) => {
+                        let return_focus = self.focus;
+                        if let Some(prompt) = self.prompt() {
+                            prompt.open(PromptKind::SaveSearch(definition), "", return_focus);
+                        }
+                        self.change_focus(Focus::Prompt);
+                    }
+                    Action::SavedSearches => {
+                        let saved = saved_searches::load();
+                        if saved.is_empty() {
+                            self.report_error(Action::Error(
+                                format!(
+                                    "Nothing saved yet, save a filter or search with [{}]",
+                                    help::key_label(self.config.key_config.save_search)
+                                ),
+                            ))?;
+                            continue;
+                        }
+                        if let Some(picker) = self
+                            .components
+                            .iter_mut()
+                            .find_map(|component| component.as_any_mut().downcast_mut::<SavedSearchPicker>())
+                        {
+                            picker.open(saved);
+                        }
+                        self.change_focus(Focus::SavedSearches);
+                    }
+                    Action::ApplySaved(definition) => {
+                        if let Err(e) = self.apply_saved(definition) {
+                            self.report_error(e)?;
+                        }
+                    }
+                    Action::PlanSync(request) => {
+                        if let Err(e) = self.review_sync(*request) {
+                            self.report_error(e)?;
+                        }
+                    }
+                    Action::SetTheme(name) => self.set_theme(name),
+                    Action::ToggleColumn(column) => {
+                        if let Some(viewer) = self.viewer() {
+                            viewer.toggle_column(column);
+                        }
+                        self.fetch_metadata();
+                    }
+                    Action::Expanded(expanded) => match expanded {
+                        Ok((requests, skipped)) => {
+                            self.config.transfers.enqueue_all(requests);
+                            if let Err(e) = skipped_error(&skipped) {
+                                self.report_error(e)?;
+                            }
+                        }
+                        Err(e) => self.report_error(Action::Error(e))?,
+                    },
+                    Action::ListedMetadata(prefix, listing) => {
+                        // a prefix that could not be fetched counts as empty, so a filter stops waiting on it
+                        let (listing, error) = match listing {
+                            Ok(listing) => (listing, None),
+                            Err(e) => (Vec::new(), Some(Action::Error(e))),
+                        };
+                        if let Some(viewer) = self.viewer() {
+                            if let Err(e) = viewer.add_metadata(prefix, listing) {
+                                self.report_error(e)?;
+                            }
+                        }
+                        if let Some(e) = error {
+                            self.report_error(e)?;
+                        }
+                    }
+                    Action::SyncPlanned(request, planned) => self.show_sync_plan(*request, planned)?,
+                    Action::Compared(prefixes, diff) => {
+                        let (left, right) = *prefixes;
+                        self.show_diff(left, right, diff)?
+                    }
+                    Action::RunSync(request, entries) => {
+                        self.config.transfers.enqueue_all(sync::jobs(&request, &entries));
+                        self.change_focus(Focus::Transfers);
+                    }
+                    Action::RetryTransfer(id) => {
+                        if let Err(e) = self.config.transfers.retry(id) {
+                            self.report_error(e)?;
+                        }
+                    }
+                    Action::CancelTransfer(id) => {
+                        if let Err(e) = self.config.transfers.cancel(id) {
+                            self.report_error(e)?;
+                        }
+                    }
+                    Action::SubmitPrompt(kind, input) => {
+                        let return_focus = match self.prompt() {
+                            Some(prompt) => prompt.return_focus,
+                            None => Focus::Viewer,
+                        };
+                        self.change_focus(return_focus);
+                        if let Err(e) = self.submit_prompt(kind, input) {
+                            self.report_error(e)?;
+                        }
+                    }
+                    Action::Help => {
+                        let listed = self.focus;
+                        if let Some(help) = self
+                            .components
+                            .iter_mut()
+                            .find_map(|component| component.as_any_mut().downcast_mut::<Help>())
+                        {
+                            help.open(listed);
+                        }
+                        self.change_focus(Focus::Help);
+                    }
+                    Action::Palette => {
+                        let opened_from = self.focus;
+                        let entries = self.palette_entries();
+                        if let Some(palette) = self
+                            .components
+                            .iter_mut()
+                            .find_map(|component| component.as_any_mut().downcast_mut::<CommandPalette>())
+                        {
+                            palette.open(entries, opened_from);
+                        }
+                        self.change_focus(Focus::Palette);
+                    }
+                    Action::Bookmark(selection) => {
+                        let Some(uri) = selection.last().cloned() else {
+                            self.report_error(Action::Error("Select something in the Viewer to bookmark".to_string()))?;
+                            continue;
+                        };
+                        let return_focus = self.focus;
+                        if let Some(prompt) = self.prompt() {
+                            prompt.open(PromptKind::Bookmark(uri), "", return_focus);
+                        }
+                        self.change_focus(Focus::Prompt);
+                    }
+                    Action::Bookmarks => {
+                        let saved = bookmarks::load();
+                        if saved.is_empty() {
+                            self.report_error(Action::Error(format!(
+                                "No bookmarks yet, bookmark the selection in the Viewer with [{}]",
+                                help::key_label(self.config.key_config.bookmark)
+                            )))?;
+                            continue;
+                        }
+                        let return_focus = self.focus;
+                        if let Some(picker) = self.bookmark_picker() {
+                            picker.open(saved, return_focus);
+                        }
+                        self.change_focus(Focus::Bookmarks);
+                    }
+                    Action::OpenBookmark(bookmark) => {
+                        self.change_focus(Focus::Viewer);
+                        if let Err(e) = self.open_bookmark(bookmark) {
+                            self.report_error(e)?;
+                        }
+                    }
+                    Action::ForgetBookmark(name) => {
+                        if let Err(e) = bookmarks::remove(&name) {
+                            self.report_error(e)?;
+                            continue;
+                        }
+                        let remaining = self.pin_bookmarks();
+                        let return_focus = match self.bookmark_picker() {
+                            Some(picker) => {
+                                picker.set_bookmarks(remaining.clone());
+                                picker.return_focus
+                            }
+                            None => Focus::Viewer,
+                        };
+                        if remaining.is_empty() {
+                            self.change_focus(return_focus);
+                        }
+                    }
+                    Action::Refresh(selection) => {
+                        if let Err(e) = self.refresh(selection) {
+                            self.report_error(e)?;
+                        }
+                    }
+                    // whatever was deleted since the last session is left closed
+                    Action::RestoreExpanded(value) => {
+                        if let Err(e) = self.restore_expanded(&value) {
+                            info!("Not restoring {value}: {e:?}");
+                        }
+                    }
+                    Action::RestoreSelection(value, page) => {
+                        if let Err(e) = self.reveal(&value) {
+                            info!("Not restoring the selection {value}: {e:?}");
+                        }
+                        if let Some(viewer) = self.viewer() {
+                            viewer.restore_page(page);
+                        }
+                    }
                     _ => (),
                 },
                 Err(act) => match act {
@@ -207,33 +638,95 @@ impl App {
             };
         }
 
+        let session = self.current_session();
+        session::save(&session);
         tui.exit()?;
         Ok(())
     }
 
-    fn handle_events(&mut self) -> Result<Action, Action> {
-        match crossterm::event::poll(Duration::from_millis(250)) {
-            Ok(_) => match crossterm::event::read() {
-                Ok(event) => match event {
-                    Event::Key(key) => self.handle_key_events(key),
-                    Event::Mouse(mouse) => self.handle_mouse_events(mouse),
-                    Event::Resize(_, _) => Ok(Action::Nothing),
-                    _ => Ok(Action::Quit),
-                },
-                Err(_) => {
-                    let message = "Error reading events".to_string();
-                    Err(Action::Error(message))
-                }
-            },
-            Err(_) => {
-                let message = "Error polling for events".to_string();
-                Err(Action::Error(message))
+    /// Waits for the input thread and turns what it sends into an action
+    fn handle_events(&mut self, tui: &mut Tui) -> Result<Action, Action> {
+        if self.key_sequences.has_ready() {
+            self.needs_render = true;
+            return self.send_ready_keys();
+        }
+
+        let event = tui.next_event();
+        // whatever the user did may have changed something on screen, ticks say so themselves
+        if !matches!(event, Some(Event::Tick | Event::Render)) {
+            self.needs_render = true;
+        }
+        match event {
+            Some(Event::Tick) => Ok(Action::Tick),
+            Some(Event::Render) => Ok(Action::Render),
+            Some(Event::Key(key)) => self.handle_key_events(key),
+            Some(Event::Mouse(mouse)) => self.handle_mouse_events(mouse),
+            // the next frame is drawn at the new size
+            Some(Event::Resize(columns, rows)) => {
+                info!("Terminal resized to {columns}x{rows}");
+                Ok(Action::Nothing)
+            }
+            // other programs may have drawn over the terminal in the meantime, so draw all of it again
+            Some(Event::FocusGained) => {
+                tui.clear().map_err(Action::Error)?;
+                Ok(Action::Nothing)
+            }
+            Some(Event::Paste(text)) => self.handle_paste_event(&text),
+            Some(Event::Error(message)) => Err(Action::Error(message)),
+            None => {
+                error!("The input thread stopped");
+                Err(Action::Quit)
             }
         }
     }
 
+    /// Housekeeping between events: transfers, changed prefixes, key sequences that timed out and
+    /// background work the components show; whether any of it changed something on screen
+    fn tick(&mut self) -> Result<bool, Action> {
+        // pick up any transfers waiting in the queue
+        self.config.transfers.start_queued();
+
+        // a sequence that waited too long means what its keys mean on their own
+        let mut changed = self.key_sequences.expire(&self.config.key_config);
+
+        // prefixes listed since the last tick need metadata if the Viewer shows columns or a filter waits on it
+        self.fetch_metadata();
+
+        for component in self.components.iter_mut() {
+            changed |= component.tick(self.focus, &self.config);
+        }
+
+        // re-list whatever finished copies and moves changed
+        let prefixes = self.config.transfers.take_changed_prefixes();
+        if prefixes.is_empty() {
+            return Ok(changed);
+        }
+        self.refresh_prefixes(prefixes)?;
+        Ok(true)
+    }
+
     fn handle_key_events(&mut self, key_event: KeyEvent) -> Result<Action, Action> {
-        // convert key event into Key
+        // only the trees take counts and sequences, everywhere else keys may be typed as text
+        if !matches!(self.focus, Focus::Connections | Focus::Viewer) {
+            self.key_sequences.clear();
+            return self.send_key_event(key_event);
+        }
+        self.key_sequences.push(key_event.into(), &self.config.key_config);
+        self.send_ready_keys()
+    }
+
+    /// Sends resolved keys until one of them does something the app has to handle, the rest wait for the next round
+    fn send_ready_keys(&mut self) -> Result<Action, Action> {
+        while let Some(key) = self.key_sequences.next_ready() {
+            let act = self.send_key_event(key.into())?;
+            if !matches!(act, Action::Nothing) {
+                return Ok(act);
+            }
+        }
+        Ok(Action::Nothing)
+    }
+
+    fn send_key_event(&mut self, key_event: KeyEvent) -> Result<Action, Action> {
         let mut act = Action::Nothing;
 
         // handle event for components
@@ -248,6 +741,19 @@ impl App {
         Ok(act)
     }
 
+    /// Pasted text goes to the focused text input as one line; the trees ignore it
+    fn handle_paste_event(&mut self, text: &str) -> Result<Action, Action> {
+        let text = text.lines().collect::<Vec<_>>().join(" ");
+        let mut act = Action::Nothing;
+        for component in self.components.iter_mut() {
+            let res = component.handle_paste(&text, self.focus)?;
+            if !matches!(res, Action::Skip) {
+                act = res
+            }
+        }
+        Ok(act)
+    }
+
     fn handle_mouse_events(&mut self, mouse_event: MouseEvent) -> Result<Action, Action> {
         let res = Action::Nothing;
         // handle event for components
@@ -264,6 +770,11 @@ impl App {
     }
 
     pub fn render(&mut self, tui: &mut Tui) -> Result<(), String> {
+        // the footer shows how many items are marked
+        self.config.marked = self.viewer().map_or(0, |viewer| viewer.marks.len());
+        self.config.pending_keys = self.key_sequences.pending();
+        self.needs_render = false;
+
         match tui.terminal.draw(|frame| {
             for component in self.components.iter_mut() {
                 if let Err(err) = component.draw(frame, frame.area(), self.focus, &self.config) {
@@ -279,6 +790,762 @@ impl App {
         }
     }
 
+    fn report_error(&mut self, action: Action) -> Result<(), String> {
+        if let Action::Error(message) = action {
+            self.change_focus(Focus::Error);
+            for component in self.components.iter_mut() {
+                component.report_error(&message)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// With `--read-only`, turns anything that would change a bucket into an error
+    fn refuse_when_read_only(&self, act: Action) -> Result<Action, Action> {
+        if !self.config.read_only {
+            return Ok(act);
+        }
+        let refused = match &act {
+            Action::Upload(_) => Some("uploading"),
+            Action::Cut(_) => Some("moving"),
+            Action::Paste(_) => Some("pasting"),
+            Action::Delete(_) => Some("deleting"),
+            Action::RunSync(request, _) if request.direction == SyncDirection::Up => Some("syncing up"),
+            _ => None,
+        };
+        match refused {
+            Some(what) => Err(Action::Error(format!("Started with --read-only, {what} is disabled"))),
+            None => Ok(act),
+        }
+    }
+
+    /// Lists the connections of a cloud provider, or the buckets of one connection, like `Enter` in Connections
+    fn connection_list(&mut self, selection: Vec<String>) -> Result<(), Action> {
+        self.config.app_selection = selection.clone();
+        let focus = self.ls(selection, Focus::Connections)?;
+        self.change_focus(focus);
+        for component in self.components.iter_mut() {
+            component.register_config(&self.config, self.focus).map_err(Action::Error)?;
+        }
+        Ok(())
+    }
+
+    /// Lists the connection to start with, then expands the Viewer to the URI if one was given
+    fn start(&mut self, start_at: StartAt) -> Result<(), Action> {
+        let uri = start_at.uri.as_deref().map(CloudUri::parse).transpose()?;
+        let kind = match (&uri, start_at.provider) {
+            (Some(uri), _) => uri.kind,
+            (None, Some(kind)) => kind,
+            (None, None) => CloudProviderKind::Gcs,
+        };
+        info!("Starting at {:?} with {kind}", start_at.uri);
+
+        let provider = vec![CONNECTIONS_ROOT.to_string(), kind.to_string()];
+        self.connection_list(provider.clone())?;
+        let active = self
+            .config
+            .cloud_provider_config
+            .active_cloud_connection
+            .as_ref()
+            .map(|connection| connection.name());
+        let connection = match (start_at.connection, active) {
+            (Some(connection), _) => connection,
+            (None, Some(active)) => active,
+            (None, None) => return Err(Action::Error(format!("No active {kind} connection, pick one with --connection"))),
+        };
+
+        self.connection_list([provider, vec![connection.clone()]].concat())?;
+        let activated = self.config.cloud_provider_config.active_cloud_connection.as_ref().map(|c| c.name());
+        if activated.as_deref() != Some(connection.as_str()) {
+            return Err(Action::Error(format!("No {kind} connection named {connection}")));
+        }
+
+        match uri {
+            Some(uri) => self.go_to(&uri.to_string()),
+            None => {
+                self.change_focus(Focus::Connections);
+                Ok(())
+            }
+        }
+    }
+
+    fn prompt(&mut self) -> Option<&mut Prompt> {
+        self.components
+            .iter_mut()
+            .find_map(|component| component.as_any_mut().downcast_mut::<Prompt>())
+    }
+
+    fn search(&mut self) -> Option<&mut Search> {
+        self.components
+            .iter_mut()
+            .find_map(|component| component.as_any_mut().downcast_mut::<Search>())
+    }
+
+    fn bookmark_picker(&mut self) -> Option<&mut BookmarkPicker> {
+        self.components
+            .iter_mut()
+            .find_map(|component| component.as_any_mut().downcast_mut::<BookmarkPicker>())
+    }
+
+    /// Shows the bookmarks on disk in the Connections tree and returns them
+    fn pin_bookmarks(&mut self) -> Vec<Bookmark> {
+        let saved = bookmarks::load();
+        if let Some(connections) = self
+            .components
+            .iter_mut()
+            .find_map(|component| component.as_any_mut().downcast_mut::<Connections>())
+        {
+            connections.pin_bookmarks(saved.clone());
+        }
+        saved
+    }
+
+    /// Activates the bookmark's connection, lists every level down to its URI and selects it
+    fn open_bookmark(&mut self, bookmark: Bookmark) -> Result<(), Action> {
+        info!("Opening bookmark {} at {}", bookmark.name, bookmark.uri);
+        self.start(StartAt {
+            uri: Some(bookmark.uri),
+            provider: None,
+            connection: bookmark.connection,
+        })
+    }
+
+    fn viewer(&mut self) -> Option<&mut Viewer> {
+        self.components
+            .iter_mut()
+            .find_map(|component| component.as_any_mut().downcast_mut::<Viewer>())
+    }
+
+    /// What a batch action applies to: every item marked in the Viewer, or the selection when nothing is marked
+    fn targets(&mut self, selection: Vec<String>) -> Result<Vec<CloudUri>, Action> {
+        let marked = self.viewer().map(|viewer| viewer.marked()).unwrap_or_default();
+        if marked.is_empty() {
+            return match selection.last() {
+                Some(selected) => Ok(vec![CloudUri::parse(selected)?]),
+                None => Ok(vec![]),
+            };
+        }
+        marked.iter().map(|value| CloudUri::parse(value)).collect()
+    }
+
+    /// Marks only last for one batch action
+    fn clear_marks(&mut self) {
+        if let Some(viewer) = self.viewer() {
+            viewer.clear_marks();
+        }
+    }
+
+    /// Queues a download of each target object, and of every object under each target prefix
+    pub fn download(&mut self, selection: Vec<String>) -> Result<(), Action> {
+        let targets = self.targets(selection)?;
+        if targets.is_empty() {
+            return Err(Action::Error("Nothing selected to download".to_string()));
+        }
+        self.clear_marks();
+        self.enqueue_expanded(move || {
+            let mut requests = vec![];
+            let mut skipped = vec![];
+            for selected in targets {
+                let (queued, unsafe_keys) = download_requests(selected)?;
+                requests.extend(queued);
+                skipped.extend(unsafe_keys);
+            }
+            Ok((requests, skipped))
+        });
+        Ok(())
+    }
+
+    /// Works out the jobs with `expand` on a background thread, since listing every object under a
+    /// prefix can take a while on big buckets; they are queued once `Action::Expanded` comes back
+    fn enqueue_expanded<F>(&self, expand: F)
+    where
+        F: FnOnce() -> Result<(Vec<JobRequest>, Vec<CloudUri>), Action> + Send + 'static,
+    {
+        let sender = self.action_sender.clone();
+        thread::spawn(move || {
+            let expanded = expand().map_err(transfer::action_message);
+            let _ = sender.send(Action::Expanded(expanded));
+        });
+    }
+
+    /// The prefix an upload or paste lands in: the selected prefix, or the parent of the selected object
+    fn destination_prefix(&self, selection: Vec<String>) -> Result<CloudUri, Action> {
+        let selected = match selection.last() {
+            Some(selected) => CloudUri::parse(selected)?,
+            None => return Err(Action::Error("Select a bucket or directory to upload into".to_string())),
+        };
+        if selected.is_prefix() {
+            Ok(selected)
+        } else {
+            Ok(selected.parent())
+        }
+    }
+
+    fn submit_prompt(&mut self, kind: PromptKind, input: String) -> Result<(), Action> {
+        match kind {
+            PromptKind::Upload(destination) => {
+                let destination = CloudUri::parse(&destination)?;
+                let source = transfer::expand_local_path(&input);
+
+                if source.is_dir() {
+                    let dir_name = source
+                        .file_name()
+                        .map(|name| name.to_string_lossy().to_string())
+                        .unwrap_or_default();
+                    let mut requests = vec![];
+                    for file in transfer::local_files(&source)? {
+                        let relative = file.strip_prefix(&source).unwrap_or(&file);
+                        let target = destination
+                            .join(&dir_name)
+                            .join(&relative.to_string_lossy());
+                        requests.push((
+                            TransferKind::Upload,
+                            Endpoint::Local(file.clone()),
+                            Endpoint::Remote(target),
+                            None,
+                        ));
+                    }
+                    self.config.transfers.enqueue_all(requests);
+                    Ok(())
+                } else if source.is_file() {
+                    let file_name = source
+                        .file_name()
+                        .map(|name| name.to_string_lossy().to_string())
+                        .unwrap_or_default();
+                    self.config.transfers.enqueue(
+                        TransferKind::Upload,
+                        Endpoint::Local(source),
+                        Endpoint::Remote(destination.join(&file_name)),
+                        None,
+                    );
+                    Ok(())
+                } else {
+                    Err(Action::Error(format!("No such file or directory: {input}")))
+                }
+            }
+            PromptKind::Paste { .. } => self.paste(&input),
+            PromptKind::Delete(targets) => match input.trim() {
+                "y" | "yes" => self.delete(targets),
+                _ => Ok(()),
+            },
+            PromptKind::Compare(left) => {
+                let left = CloudUri::parse(&left)?;
+                let right = CloudUri::parse(&input)?;
+                if !right.is_prefix() {
+                    return Err(Action::Error(format!("{right} is not a prefix, end it with '/'")));
+                }
+                if let Some(compare) = self.compare() {
+                    compare.start(left.clone(), right.clone())?;
+                }
+                self.change_focus(Focus::DiffFilter);
+
+                let sender = self.action_sender.clone();
+                thread::spawn(move || {
+                    // both prefixes are listed in full, which can take a while on big buckets
+                    let diff = diff::compare(&left, &right).map(Box::new).map_err(transfer::action_message);
+                    let _ = sender.send(Action::Compared(Box::new((left, right)), diff));
+                });
+                Ok(())
+            }
+            PromptKind::Search => self.start_search(&input),
+            PromptKind::GoTo => self.go_to(&input),
+            PromptKind::SaveSearch(definition) => saved_searches::add(&input, definition),
+            PromptKind::Bookmark(uri) => {
+                let connection = self
+                    .config
+                    .cloud_provider_config
+                    .active_cloud_connection
+                    .as_ref()
+                    .map(|connection| connection.name());
+                bookmarks::add(&input, uri, connection)?;
+                self.pin_bookmarks();
+                Ok(())
+            }
+            PromptKind::Sync(prefix) => {
+                let request = SyncRequest::new(transfer::expand_local_path(&input), CloudUri::parse(&prefix)?);
+                self.review_sync(request)
+            }
+            PromptKind::Verify(object) => {
+                let object = CloudUri::parse(&object)?;
+                let local = transfer::expand_local_path(&input);
+                if !local.is_file() {
+                    return Err(Action::Error(format!("No such file: {input}")));
+                }
+                self.config.transfers.enqueue(
+                    TransferKind::Verify,
+                    Endpoint::Local(local),
+                    Endpoint::Remote(object),
+                    None,
+                );
+                Ok(())
+            }
+        }
+    }
+
+    fn start_search(&mut self, input: &str) -> Result<(), Action> {
+        let pattern = SearchPattern::parse(input)?;
+        info!("Searching {} for {}", pattern.prefix, pattern.text);
+        if let Some(search) = self.search() {
+            search.open(RunningSearch::start(pattern));
+        }
+        self.change_focus(Focus::Search);
+        Ok(())
+    }
+
+    /// Re-applies a saved filter to what the Viewer has listed, or starts a saved search again
+    fn apply_saved(&mut self, definition: Definition) -> Result<(), Action> {
+        info!("Applying saved {definition}");
+        match definition {
+            Definition::Filter { text, mode, case_sensitive } => {
+                if let Some(viewer) = self.viewer() {
+                    viewer.apply_filter(&text, MatchOptions { mode, case_sensitive })?;
+                }
+                self.change_focus(Focus::ViewerFilter);
+                Ok(())
+            }
+            Definition::Search { pattern } => self.start_search(&pattern),
+        }
+    }
+
+    /// Puts the marked items, or the selection, on the clipboard to be copied or moved by the next paste
+    fn yank(&mut self, kind: TransferKind, selection: Vec<String>) -> Result<(), Action> {
+        let sources = self.targets(selection)?;
+        if sources.is_empty() {
+            return Err(Action::Error("Nothing selected to copy or move".to_string()));
+        }
+        info!("{kind} clipboard: {} items", sources.len());
+        self.config.clipboard = Some(Clipboard { kind, sources });
+        self.clear_marks();
+        Ok(())
+    }
+
+    /// Queues a delete of each target object, and of every object under each target prefix
+    fn delete(&mut self, targets: Vec<CloudUri>) -> Result<(), Action> {
+        self.clear_marks();
+        self.enqueue_expanded(move || {
+            let mut requests = vec![];
+            for target in targets {
+                let objects = if target.is_prefix() {
+                    storage::list_recursive(&target)?.into_iter().map(|entry| entry.uri).collect()
+                } else {
+                    vec![target]
+                };
+                for object in objects {
+                    requests.push((
+                        TransferKind::Delete,
+                        Endpoint::Remote(object.clone()),
+                        Endpoint::Remote(object),
+                        None,
+                    ));
+                }
+            }
+            Ok((requests, vec![]))
+        });
+        Ok(())
+    }
+
+    /// Queues a server-side copy or move of everything on the clipboard into `input`.
+    /// A single object pasted to a full key (no trailing `/`) is renamed.
+    fn paste(&mut self, input: &str) -> Result<(), Action> {
+        let clipboard = match self.config.clipboard.clone() {
+            Some(clipboard) => clipboard,
+            None => return Err(Action::Error("Nothing to paste".to_string())),
+        };
+        let destination = CloudUri::parse(input)?;
+        let rename = !destination.is_prefix() && matches!(clipboard.sources.as_slice(), [source] if !source.is_prefix());
+
+        // where everything lands is checked here, the prefixes are only listed in the background
+        let mut targets = vec![];
+        for source in &clipboard.sources {
+            let target = if source.is_prefix() {
+                destination.join(&format!("{}/", source.file_name()))
+            } else if rename {
+                destination.clone()
+            } else {
+                destination.join(&source.file_name())
+            };
+            if source.is_prefix() && target.relative_to(source).is_some() {
+                return Err(Action::Error(format!("Cannot {} {source} into itself", clipboard.kind.to_string().to_lowercase())));
+            }
+            if &target == source {
+                return Err(Action::Error(format!("{source} is already there")));
+            }
+            targets.push((source.clone(), target));
+        }
+
+        let kind = clipboard.kind;
+        self.enqueue_expanded(move || {
+            let mut requests = vec![];
+            for (source, target) in targets {
+                if !source.is_prefix() {
+                    requests.push((kind, Endpoint::Remote(source), Endpoint::Remote(target), None));
+                    continue;
+                }
+                // every object under the prefix is rewritten, there are no real directories to move
+                for entry in storage::list_recursive(&source)? {
+                    let relative = entry
+                        .uri
+                        .relative_to(&source)
+                        .unwrap_or_else(|| entry.uri.file_name());
+                    if relative.is_empty() || relative.ends_with('/') {
+                        continue;
+                    }
+                    requests.push((kind, Endpoint::Remote(entry.uri), Endpoint::Remote(target.join(&relative)), Some(entry.size)));
+                }
+            }
+            Ok((requests, vec![]))
+        });
+
+        // a cut can only be pasted once, a copy can be pasted again elsewhere
+        if clipboard.kind == TransferKind::Move {
+            self.config.clipboard = None;
+        }
+        Ok(())
+    }
+
+    /// Opens the preview and plans the sync on a background thread; nothing runs until it is confirmed there
+    fn review_sync(&mut self, request: SyncRequest) -> Result<(), Action> {
+        if let Some(review) = self.sync_review() {
+            review.start(request.clone());
+        }
+        self.change_focus(Focus::SyncReview);
+
+        let sender = self.action_sender.clone();
+        thread::spawn(move || {
+            // listing and hashing every file can take a while, the UI keeps running meanwhile
+            let planned = sync::plan(&request).map_err(transfer::action_message);
+            let _ = sender.send(Action::SyncPlanned(Box::new(request), planned));
+        });
+        Ok(())
+    }
+
+    /// Fills the preview with a finished plan, if the preview is still waiting for it
+    fn show_sync_plan(&mut self, request: SyncRequest, planned: Result<Vec<SyncEntry>, String>) -> Result<(), String> {
+        let Some(review) = self.sync_review() else {
+            return Ok(());
+        };
+        if !review.is_planning(&request) {
+            return Ok(());
+        }
+        match planned {
+            Ok(entries) => {
+                info!("Sync {} {} {}: {} entries", request.local.display(), request.direction, request.remote, entries.len());
+                review.open(request, entries);
+                Ok(())
+            }
+            Err(message) => {
+                review.close();
+                self.change_focus(Focus::Viewer);
+                self.report_error(Action::Error(message))
+            }
+        }
+    }
+
+    /// Lists a finished comparison, if the Compare popup is still waiting for it
+    fn show_diff(&mut self, left: CloudUri, right: CloudUri, diff: Result<Box<Diff>, String>) -> Result<(), String> {
+        let Some(compare) = self.compare() else {
+            return Ok(());
+        };
+        if !compare.is_comparing(&left, &right) {
+            return Ok(());
+        }
+        match diff {
+            Ok(diff) => {
+                info!("Compared {left} with {right}: {} differences", diff.entries.len());
+                match compare.open(left, right, *diff) {
+                    Ok(()) => Ok(()),
+                    Err(e) => self.report_error(e),
+                }
+            }
+            Err(message) => {
+                compare.close();
+                self.change_focus(Focus::Viewer);
+                self.report_error(Action::Error(message))
+            }
+        }
+    }
+
+    fn compare(&mut self) -> Option<&mut Compare> {
+        self.components
+            .iter_mut()
+            .find_map(|component| component.as_any_mut().downcast_mut::<Compare>())
+    }
+
+    fn sync_review(&mut self) -> Option<&mut SyncReview> {
+        self.components
+            .iter_mut()
+            .find_map(|component| component.as_any_mut().downcast_mut::<SyncReview>())
+    }
+
+    /// Re-lists the nodes showing `prefixes` so finished copies and moves appear in the Viewer
+    fn refresh_prefixes(&mut self, prefixes: Vec<CloudUri>) -> Result<(), Action> {
+        if self.config.cloud_provider_config.active_cloud_connection.is_none() {
+            return Ok(());
+        }
+        for prefix in prefixes {
+            let node = self.components.iter_mut().find_map(|component| {
+                component
+                    .as_any_mut()
+                    .downcast_mut::<Viewer>()
+                    .and_then(|viewer| viewer.listed_ancestor(&prefix))
+            });
+            let Some(node) = node else {
+                continue;
+            };
+
+            info!("Refreshing {node}");
+            self.config.cloud_provider_config.ls(vec![node.clone()], Focus::Viewer)?;
+            for component in self.components.iter_mut() {
+                if let Some(viewer) = component.as_any_mut().downcast_mut::<Viewer>() {
+                    viewer.refresh_node(&self.config, &node)?;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Lists the selected prefix, or the one the selected object is in, again
+    fn refresh(&mut self, selection: Vec<String>) -> Result<(), Action> {
+        if selection.is_empty() {
+            return Err(Action::Error("Select a bucket or directory to refresh".to_string()));
+        }
+        let prefix = self.destination_prefix(selection)?;
+        self.refresh_prefixes(vec![prefix])
+    }
+
+    /// Fetches the metadata the Viewer's columns or filter need for prefixes listed since the last call, one thread each
+    fn fetch_metadata(&mut self) {
+        let prefixes = self.viewer().map(|viewer| viewer.take_unfetched_prefixes()).unwrap_or_default();
+        for prefix in prefixes {
+            info!("Fetching metadata of {prefix} for the Viewer");
+            let sender = self.action_sender.clone();
+            thread::spawn(move || {
+                let listing = CloudUri::parse(&prefix)
+                    .and_then(|uri| storage::list_metadata(&uri))
+                    .map(|listing| listing.into_iter().map(|(uri, metadata)| (uri.to_string(), metadata)).collect())
+                    .map_err(transfer::action_message);
+                let _ = sender.send(Action::ListedMetadata(prefix, listing));
+            });
+        }
+    }
+
+    /// Switches every component to the theme called `name`, and any created from now on
+    fn set_theme(&mut self, name: ThemeName) {
+        let theme = Theme::named(name);
+        theme::set_theme(theme);
+        self.config.theme = theme;
+        for component in self.components.iter_mut() {
+            component.set_theme(theme);
+        }
+    }
+
+    /// What the command palette offers: the Viewer's actions on its selection, switching panes
+    /// and connections, themes and columns, and the app-wide commands, each with the key that does the same
+    fn palette_entries(&mut self) -> Vec<PaletteEntry> {
+        let keys = self.config.key_config;
+        let selected = self.viewer().map(|viewer| viewer.state.selected().to_vec()).unwrap_or_default();
+        let on_selection = |label: &str, key: Key, action: fn(Vec<String>) -> Action| {
+            PaletteEntry::new(label, Some(key), action(selected.clone()))
+        };
+
+        let mut entries = vec![
+            on_selection("Refresh", keys.refresh, Action::Refresh),
+            on_selection("Download", keys.download, Action::Download),
+            on_selection("Upload", keys.upload, Action::Upload),
+            on_selection("Delete", keys.delete_object, Action::Delete),
+            on_selection("Verify against a local file", keys.verify, Action::Verify),
+            on_selection("Copy", keys.yank, Action::Yank),
+            on_selection("Move", keys.cut, Action::Cut),
+            on_selection("Paste", keys.paste, Action::Paste),
+            on_selection("Sync with a local directory", keys.sync, Action::Sync),
+            on_selection("Compare with another prefix", keys.compare, Action::Compare),
+            on_selection("Search", keys.search, Action::Search),
+            on_selection("Go to URI", keys.go_to, Action::GoTo),
+            on_selection("Bookmark the selection", keys.bookmark, Action::Bookmark),
+            PaletteEntry::new("Bookmarks", Some(keys.bookmarks), Action::Bookmarks),
+            PaletteEntry::new("Saved searches", Some(keys.saved_searches), Action::SavedSearches),
+            PaletteEntry::new("Transfers", Some(keys.transfers), Action::ChangeFocus(Focus::Transfers)),
+            PaletteEntry::new("Switch to Connections", None, Action::ChangeFocus(Focus::Connections)),
+            PaletteEntry::new("Switch to Viewer", None, Action::ChangeFocus(Focus::Viewer)),
+        ];
+
+        let providers = &self.config.cloud_provider_config;
+        let connections = [
+            (CloudProviderKind::Gcs, providers.gcs.iter().map(|c| c.name.clone()).collect::<Vec<_>>()),
+            (CloudProviderKind::S3, providers.s3.iter().map(|c| c.name.clone()).collect()),
+            (CloudProviderKind::Azure, providers.azure.iter().map(|c| c.name.clone()).collect()),
+        ];
+        for (kind, names) in connections {
+            for name in names {
+                let path = vec![CONNECTIONS_ROOT.to_string(), kind.to_string(), name.clone()];
+                entries.push(PaletteEntry::new(
+                    format!("Switch connection to {name} ({kind})"),
+                    None,
+                    Action::ConnectionList(path),
+                ));
+            }
+        }
+
+        for bookmark in bookmarks::load() {
+            entries.push(PaletteEntry::new(
+                format!("Open bookmark {} ({})", bookmark.name, bookmark.uri),
+                None,
+                Action::OpenBookmark(bookmark),
+            ));
+        }
+
+        let current = self.config.theme.name;
+        for name in ThemeName::value_variants().iter().copied().filter(|name| *name != current) {
+            entries.push(PaletteEntry::new(format!("Change theme to {name}"), None, Action::SetTheme(name)));
+        }
+        let shown = self.viewer().map(|viewer| viewer.columns.clone()).unwrap_or_default();
+        for column in Column::ALL {
+            let verb = if shown.contains(&column) { "Hide" } else { "Show" };
+            entries.push(PaletteEntry::new(format!("{verb} the {column} column"), None, Action::ToggleColumn(column)));
+        }
+
+        entries.push(PaletteEntry::new("Help", Some(keys.help), Action::Help));
+        entries.push(PaletteEntry::new("Quit", Some(keys.quit), Action::Quit));
+        entries
+    }
+
+    /// Lists every prefix between the bucket and `target` the Viewer has not listed yet
+    fn list_ancestors(&mut self, target: &CloudUri) -> Result<(), Action> {
+        let mut prefixes = vec![target.parent()];
+        while let Some(prefix) = prefixes.last().filter(|prefix| !prefix.path.is_empty()) {
+            prefixes.push(prefix.parent());
+        }
+
+        for prefix in prefixes.iter().rev() {
+            self.list_node(prefix)?;
+        }
+        Ok(())
+    }
+
+    /// Lists every prefix between the bucket and `target`, then selects `target`
+    fn reveal(&mut self, target: &str) -> Result<(), Action> {
+        let target = CloudUri::parse(target)?;
+        self.list_ancestors(&target)?;
+
+        let value = target.to_string();
+        if let Some(viewer) = self.viewer() {
+            if viewer.tree_path(&value).is_none() {
+                return Err(Action::Error(format!("{value} does not exist")));
+            }
+            viewer.reveal(&value)?;
+        }
+        self.change_focus(Focus::Viewer);
+        Ok(())
+    }
+
+    /// Lists the Viewer node showing `prefix` unless it already is, the same steps as listing it by hand
+    fn list_node(&mut self, prefix: &CloudUri) -> Result<(), Action> {
+        let value = prefix.to_string();
+        let (path, listed) = match self.viewer() {
+            Some(viewer) => (viewer.tree_path(&value), viewer.is_listed(&value)),
+            None => return Ok(()),
+        };
+        let path = match path {
+            Some(path) => path,
+            None if prefix.path.is_empty() => {
+                return Err(Action::Error(format!("{value} is not in the Viewer, list its connection first")))
+            }
+            None => return Err(Action::Error(format!("{value} does not exist"))),
+        };
+        if !listed {
+            self.config.app_selection = path.clone();
+            self.ls(path, Focus::Viewer)?;
+            for component in self.components.iter_mut() {
+                component.register_config(&self.config, Focus::Viewer).map_err(Action::Error)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Lists every level down to `uri` and expands the tree to it; a prefix is listed and opened as well
+    fn go_to(&mut self, uri: &str) -> Result<(), Action> {
+        let target = CloudUri::parse(uri.trim())?;
+        let target = match self.reveal(&target.to_string()) {
+            // a directory typed without its trailing slash
+            Err(_) if !target.is_prefix() => {
+                let directory = CloudUri::parse(&format!("{target}/"))?;
+                self.reveal(&directory.to_string())?;
+                directory
+            }
+            result => result.map(|_| target)?,
+        };
+        let value = target.to_string();
+        if target.is_prefix() {
+            self.list_node(&target)?;
+            if let Some(viewer) = self.viewer() {
+                viewer.expand(&value);
+                viewer.reveal(&value)?;
+            }
+        }
+        self.visit(&value);
+        Ok(())
+    }
+
+    /// Lists the active connection of the last session again; its prefixes are listed one per
+    /// round of the event loop afterwards, so the tree shows up straight away and fills in
+    fn restore(&mut self, session: Session) -> Result<(), Action> {
+        let (Some(provider), Some(connection)) = (session.provider, session.connection) else {
+            return Ok(());
+        };
+        self.start(StartAt {
+            uri: None,
+            provider: Some(provider.into()),
+            connection: Some(connection),
+        })?;
+
+        let selected = session.selected.or_else(|| session.expanded.last().cloned());
+        self.queued_actions.extend(session.expanded.into_iter().map(Action::RestoreExpanded));
+        if let Some(selected) = selected {
+            self.queued_actions.push_back(Action::RestoreSelection(selected, session.page));
+        }
+        Ok(())
+    }
+
+    /// Lists `value` and the prefixes above it unless they already are, and opens it
+    fn restore_expanded(&mut self, value: &str) -> Result<(), Action> {
+        let prefix = CloudUri::parse(value)?;
+        self.list_ancestors(&prefix)?;
+        self.list_node(&prefix)?;
+        if let Some(viewer) = self.viewer() {
+            viewer.expand(value);
+        }
+        Ok(())
+    }
+
+    /// The active connection and what the Viewer has open, to be saved when quitting
+    fn current_session(&mut self) -> Session {
+        let Some(active) = self.config.cloud_provider_config.active_cloud_connection.as_ref() else {
+            return Session::default();
+        };
+        let provider = Some(active.into());
+        let connection = Some(active.name());
+        match self.viewer() {
+            Some(viewer) => {
+                let (expanded, page) = viewer.open_prefixes();
+                Session {
+                    provider,
+                    connection,
+                    expanded,
+                    selected: viewer.state.selected().last().filter(|value| value.contains("://")).cloned(),
+                    page,
+                }
+            }
+            None => Session { provider, connection, ..Session::default() },
+        }
+    }
+
+    /// Records `value` in the Viewer's back/forward history
+    fn visit(&mut self, value: &str) {
+        if let Some(viewer) = self.viewer() {
+            viewer.history.visit(value);
+        }
+    }
+
     pub fn ls(
         &mut self,
         selection: Vec<String>,
@@ -476,3 +1743,52 @@ impl App {
 }
 
 
+
+/// A download of the object, or of every object under the prefix, into the download directory, and the
+/// objects left out because their keys would land outside it
+fn download_requests(selected: CloudUri) -> Result<(Vec<JobRequest>, Vec<CloudUri>), Action> {
+    let download_dir = transfer::default_download_dir();
+    let Some(destination) = transfer::local_destination(&download_dir, &selected.file_name()) else {
+        return Ok((vec![], vec![selected]));
+    };
+
+    if selected.is_prefix() {
+        let mut requests = vec![];
+        let mut skipped = vec![];
+        for entry in storage::list_recursive(&selected)? {
+            let relative = entry
+                .uri
+                .relative_to(&selected)
+                .unwrap_or_else(|| entry.uri.file_name());
+            // skip the placeholder objects some tools create for "directories"
+            if relative.is_empty() || relative.ends_with('/') {
+                continue;
+            }
+            match transfer::local_destination(&destination, &relative) {
+                Some(local) => requests.push((
+                    TransferKind::Download,
+                    Endpoint::Remote(entry.uri),
+                    Endpoint::Local(local),
+                    Some(entry.size),
+                )),
+                None => skipped.push(entry.uri),
+            }
+        }
+        Ok((requests, skipped))
+    } else {
+        Ok((vec![(TransferKind::Download, Endpoint::Remote(selected), Endpoint::Local(destination), None)], vec![]))
+    }
+}
+
+/// Reports objects left out of a download because their keys would put them outside the download directory
+fn skipped_error(skipped: &[CloudUri]) -> Result<(), Action> {
+    if skipped.is_empty() {
+        return Ok(());
+    }
+    let keys: Vec<String> = skipped.iter().map(|uri| uri.to_string()).collect();
+    Err(Action::Error(format!(
+        "Skipped {} objects whose keys would land outside the download directory:\n{}",
+        keys.len(),
+        keys.join("\n")
+    )))
+}
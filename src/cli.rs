@@ -0,0 +1,129 @@
+use std::path::PathBuf;
+
+use clap::{builder::RangedU64ValueParser, Parser, Subcommand, ValueEnum};
+use serde::{Deserialize, Serialize};
+
+use crate::config::cloud_provider_config::cloud_provider_connection::CloudConnection;
+use crate::config::cloud_provider_config::cloud_provider_kind::CloudProviderKind;
+use crate::config::theme::ThemeName;
+
+#[derive(Debug, Parser)]
+#[command(version, about = "Browse cloud object storage from the terminal", args_conflicts_with_subcommands = true)]
+pub struct Cli {
+    #[command(subcommand)]
+    pub command: Option<Command>,
+
+    /// Open the Viewer at this URI, e.g. gs://bucket/path/, instead of starting in Connections
+    pub uri: Option<String>,
+
+    /// Cloud provider to list at startup; taken from the URI when one is given
+    #[arg(long, value_enum)]
+    pub provider: Option<Provider>,
+
+    /// Connection to activate, e.g. a gcloud configuration, instead of the currently active one
+    #[arg(long, short = 'c', value_name = "NAME", global = true)]
+    pub connection: Option<String>,
+
+    /// Config file to read instead of config.toml in the config directory
+    #[arg(long, value_name = "FILE", global = true)]
+    pub config: Option<PathBuf>,
+
+    /// Log level, e.g. debug or trace, or a full tracing filter
+    #[arg(long, value_name = "LEVEL", global = true)]
+    pub log_level: Option<String>,
+
+    /// How many items a listing shows before it is paged
+    #[arg(long, value_name = "N", value_parser = RangedU64ValueParser::<usize>::new().range(1..))]
+    pub results_per_page: Option<usize>,
+
+    /// Refuse anything that changes a bucket: uploads, moves, pastes, deletes and syncs up
+    #[arg(long, global = true)]
+    pub read_only: bool,
+
+    /// Color theme; `monochrome` draws without colors, as does setting NO_COLOR
+    #[arg(long, value_enum, value_name = "NAME")]
+    pub theme: Option<ThemeName>,
+
+    /// Start from the bare Connections tree instead of restoring the last session
+    #[arg(long)]
+    pub fresh: bool,
+}
+
+/// Runs once and prints to stdout instead of starting the TUI
+#[derive(Debug, Subcommand)]
+pub enum Command {
+    /// List the objects and prefixes directly under a URI
+    Ls {
+        uri: String,
+        /// Show size, update time and storage class
+        #[arg(long, short = 'l')]
+        long: bool,
+        /// Print a JSON array instead of text
+        #[arg(long)]
+        json: bool,
+    },
+    /// Show an object's metadata
+    Stat {
+        uri: String,
+        /// Print a JSON object instead of text
+        #[arg(long)]
+        json: bool,
+    },
+    /// Write objects to stdout
+    Cat {
+        #[arg(required = true)]
+        uris: Vec<String>,
+    },
+    /// Copy a local path to a URI, a URI to a local path, or a URI to another URI
+    Cp {
+        source: String,
+        /// Where to copy to; a local directory or a URI ending in '/' receives the source under its own name
+        destination: String,
+        /// Copy everything under a directory or prefix
+        #[arg(long, short = 'r')]
+        recursive: bool,
+    },
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Provider {
+    Gcs,
+    S3,
+    Azure,
+}
+
+impl From<Provider> for CloudProviderKind {
+    fn from(provider: Provider) -> Self {
+        match provider {
+            Provider::Gcs => CloudProviderKind::Gcs,
+            Provider::S3 => CloudProviderKind::S3,
+            Provider::Azure => CloudProviderKind::Azure,
+        }
+    }
+}
+
+impl From<&CloudConnection> for Provider {
+    fn from(connection: &CloudConnection) -> Self {
+        match connection {
+            CloudConnection::Gcs(_) => Provider::Gcs,
+            CloudConnection::S3(_) => Provider::S3,
+            CloudConnection::Azure(_) => Provider::Azure,
+        }
+    }
+}
+
+/// Where the app opens: which connection to list and which URI to expand the Viewer to
+#[derive(Debug, Clone, Default)]
+pub struct StartAt {
+    pub uri: Option<String>,
+    pub provider: Option<CloudProviderKind>,
+    pub connection: Option<String>,
+}
+
+impl StartAt {
+    /// Nothing given: start in Connections as usual
+    pub fn is_empty(&self) -> bool {
+        self.uri.is_none() && self.provider.is_none() && self.connection.is_none()
+    }
+}
@@ -0,0 +1,169 @@
+use std::result::Result;
+
+use crossterm::event::KeyEvent;
+use ratatui::{
+    layout::Constraint,
+    style::{Modifier, Style},
+    widgets::{Block, Clear, Row, Table, TableState},
+};
+
+use crate::{
+    action::Action,
+    app::Focus,
+    config::{
+        bookmarks::{self, Bookmark},
+        Config,
+    },
+    key::Key,
+    util,
+};
+
+use super::Component;
+
+/// Picks a bookmark to jump to
+#[derive(Debug)]
+pub struct BookmarkPicker {
+    pub config: Config,
+    pub state: TableState,
+    pub bookmarks: Vec<Bookmark>,
+    pub return_focus: Focus,
+}
+
+impl Default for BookmarkPicker {
+    fn default() -> Self {
+        Self {
+            config: Config::default(),
+            state: TableState::default(),
+            bookmarks: vec![],
+            return_focus: Focus::Viewer,
+        }
+    }
+}
+
+impl BookmarkPicker {
+    pub fn open(&mut self, bookmarks: Vec<Bookmark>, return_focus: Focus) {
+        self.return_focus = return_focus;
+        self.set_bookmarks(bookmarks);
+    }
+
+    /// Shows `bookmarks`, keeping the selection where it was as far as possible
+    pub fn set_bookmarks(&mut self, bookmarks: Vec<Bookmark>) {
+        let selected = self.state.selected().unwrap_or_default();
+        self.state.select(match bookmarks.len() {
+            0 => None,
+            len => Some(selected.min(len - 1)),
+        });
+        self.bookmarks = bookmarks;
+    }
+}
+
+impl Component for BookmarkPicker {
+    fn name(&self) -> &str {
+        "BookmarkPicker"
+    }
+    fn as_any_mut(&mut self) -> &mut dyn std::any::Any {
+        self
+    }
+
+    fn register_config(&mut self, _config: &Config, _focus: Focus) -> Result<(), String> {
+        Ok(())
+    }
+
+    fn draw(
+        &mut self,
+        frame: &mut ratatui::Frame,
+        area: ratatui::prelude::Rect,
+        focus: Focus,
+        config: &Config,
+    ) -> Result<(), String> {
+        if !matches!(focus, Focus::Bookmarks) {
+            return Ok(());
+        }
+
+        let panel = util::popup_area(area, 70, 50);
+        let rows: Vec<Row> = self
+            .bookmarks
+            .iter()
+            .map(|bookmark| {
+                Row::new(vec![
+                    bookmark.name.clone(),
+                    bookmark.uri.clone(),
+                    bookmark.connection.clone().unwrap_or_default(),
+                ])
+            })
+            .collect();
+        let table = Table::new(
+            rows,
+            [
+                Constraint::Percentage(25),
+                Constraint::Fill(1),
+                Constraint::Length(20),
+            ],
+        )
+        .header(
+            Row::new(vec!["Name", "URI", "Connection"])
+                .style(Style::new().add_modifier(Modifier::BOLD)),
+        )
+        .block(
+            Block::bordered()
+                .title(format!("Bookmarks ({})", bookmarks::bookmarks_path().display()))
+                .border_style(config.theme.border),
+        )
+        .style(config.theme.popup)
+        .row_highlight_style(config.theme.highlight);
+
+        frame.render_widget(Clear, panel);
+        frame.render_stateful_widget(table, panel, &mut self.state);
+        Ok(())
+    }
+
+    fn handle_key_event(&mut self, key_event: KeyEvent, focus: Focus) -> Result<Action, Action> {
+        if !matches!(focus, Focus::Bookmarks) {
+            return Ok(Action::Skip);
+        }
+        let key: Key = key_event.into();
+        let selected = self.state.selected().and_then(|idx| self.bookmarks.get(idx));
+
+        if key == self.config.key_config.exit {
+            Ok(Action::Quit)
+        } else if [
+            self.config.key_config.close_component,
+            self.config.key_config.quit,
+        ]
+        .iter()
+        .any(|kc| kc == &key)
+        {
+            Ok(Action::ChangeFocus(self.return_focus))
+        } else if [
+            self.config.key_config.key_up,
+            self.config.key_config.arrow_up,
+        ]
+        .iter()
+        .any(|kc| kc == &key)
+        {
+            self.state.select_previous();
+            Ok(Action::Nothing)
+        } else if [
+            self.config.key_config.key_down,
+            self.config.key_config.arrow_down,
+        ]
+        .iter()
+        .any(|kc| kc == &key)
+        {
+            self.state.select_next();
+            Ok(Action::Nothing)
+        } else if key == self.config.key_config.delete {
+            match selected {
+                Some(bookmark) => Ok(Action::ForgetBookmark(bookmark.name.clone())),
+                None => Ok(Action::Nothing),
+            }
+        } else if key == self.config.key_config.enter {
+            match selected {
+                Some(bookmark) => Ok(Action::OpenBookmark(bookmark.clone())),
+                None => Ok(Action::Nothing),
+            }
+        } else {
+            Ok(Action::Nothing)
+        }
+    }
+}
@@ -0,0 +1,174 @@
+use std::result::Result;
+
+use crossterm::event::KeyEvent;
+use ratatui::{
+    text::Line,
+    widgets::{Block, Clear},
+};
+
+use crate::{
+    action::Action,
+    app::Focus,
+    config::{theme::Theme, Config},
+    key::Key,
+    storage::cloud_uri::CloudUri,
+    transfer::diff::Diff,
+    util,
+};
+
+use super::filter::{DiffFilter, Filter};
+use super::Component;
+
+/// Filterable list of the objects that differ between two prefixes
+#[derive(Debug)]
+pub struct Compare {
+    pub config: Config,
+    pub prefixes: Option<(CloudUri, CloudUri)>,
+    pub diff: Diff,
+    /// The prefixes are still being listed and compared
+    pub comparing: bool,
+    pub filter: Box<dyn Filter>,
+    /// Bumped whenever the differences are replaced, so the filter matches the new ones
+    generation: u64,
+}
+
+impl Default for Compare {
+    fn default() -> Self {
+        Self {
+            config: Config::default(),
+            prefixes: None,
+            diff: Diff::default(),
+            comparing: false,
+            filter: Box::new(DiffFilter::default()),
+            generation: 0,
+        }
+    }
+}
+
+impl Compare {
+    /// Shows the two prefixes while they are compared in the background
+    pub fn start(&mut self, left: CloudUri, right: CloudUri) -> Result<(), Action> {
+        self.prefixes = Some((left, right));
+        self.diff = Diff::default();
+        self.comparing = true;
+        self.filter = Box::new(DiffFilter::default());
+        self.filter.switch_active_status();
+        self.generation += 1;
+        self.filter.engage_filter(vec![String::new()], self.generation, vec![], None)?;
+        Ok(())
+    }
+
+    /// Lists the differences, unless the comparison was closed or replaced in the meantime
+    pub fn open(&mut self, left: CloudUri, right: CloudUri, diff: Diff) -> Result<(), Action> {
+        if !self.is_comparing(&left, &right) {
+            return Ok(());
+        }
+        self.diff = diff;
+        self.comparing = false;
+        self.generation += 1;
+        // an empty pattern lists every difference
+        self.filter.engage_filter(vec![String::new()], self.generation, self.items(), None)?;
+        Ok(())
+    }
+
+    /// Whether the popup is still waiting for the comparison of `left` and `right`
+    pub fn is_comparing(&self, left: &CloudUri, right: &CloudUri) -> bool {
+        self.comparing && self.prefixes.as_ref() == Some(&(left.clone(), right.clone()))
+    }
+
+    pub fn close(&mut self) -> Action {
+        self.prefixes = None;
+        self.comparing = false;
+        self.filter.switch_active_status();
+        Action::ChangeFocus(Focus::Viewer)
+    }
+
+    fn items(&self) -> Vec<String> {
+        self.diff.entries.iter().map(|entry| entry.to_string()).collect()
+    }
+}
+
+impl Component for Compare {
+    fn name(&self) -> &str {
+        "Compare"
+    }
+    fn as_any_mut(&mut self) -> &mut dyn std::any::Any {
+        self
+    }
+
+    fn tick(&mut self, _focus: Focus, _config: &Config) -> bool {
+        self.filter.poll_matches()
+    }
+
+    fn set_theme(&mut self, theme: Theme) {
+        self.config.theme = theme;
+        self.filter.set_theme(theme);
+    }
+
+    fn register_config(&mut self, config: &Config, focus: Focus) -> Result<(), String> {
+        self.filter.register_config(config, focus)
+    }
+
+    fn draw(
+        &mut self,
+        frame: &mut ratatui::Frame,
+        area: ratatui::prelude::Rect,
+        focus: Focus,
+        config: &Config,
+    ) -> Result<(), String> {
+        let Some((left, right)) = &self.prefixes else {
+            return Ok(());
+        };
+        if !matches!(focus, Focus::DiffFilter | Focus::DiffFilterResults) {
+            return Ok(());
+        }
+
+        let summary = if self.comparing {
+            " comparing... ".to_string()
+        } else {
+            format!(
+                " {} differences, {} identical, {} same size without a common checksum ",
+                self.diff.entries.len(),
+                self.diff.identical,
+                self.diff.unverified,
+            )
+        };
+        let panel = util::popup_area(area, 90, 70);
+        let block = Block::bordered()
+            .title(format!("Compare {left} <-> {right}"))
+            .title_bottom(Line::from(summary))
+            .border_style(config.theme.border)
+            .style(config.theme.popup);
+        let inner = block.inner(panel);
+
+        frame.render_widget(Clear, panel);
+        frame.render_widget(block, panel);
+        self.filter.draw(frame, inner, focus)
+    }
+
+    fn handle_key_event(&mut self, key_event: KeyEvent, focus: Focus) -> Result<Action, Action> {
+        if !matches!(focus, Focus::DiffFilter | Focus::DiffFilterResults) {
+            return Ok(Action::Skip);
+        }
+        let key: Key = key_event.into();
+
+        if key == self.config.key_config.close_component {
+            Ok(self.close())
+        } else if matches!(focus, Focus::DiffFilter) {
+            match self.filter.handle_key_event(key_event, focus)? {
+                Action::Filter(txt) => self.filter.engage_filter(txt, self.generation, self.items(), None),
+                action => Ok(action),
+            }
+        } else {
+            self.filter.filter_results_handle_key_event(key_event, focus)
+        }
+    }
+
+    fn handle_paste(&mut self, text: &str, focus: Focus) -> Result<Action, Action> {
+        if !matches!(focus, Focus::DiffFilter) {
+            return Ok(Action::Skip);
+        }
+        let txt = self.filter.paste(text);
+        self.filter.engage_filter(txt, self.generation, self.items(), None)
+    }
+}
@@ -4,19 +4,22 @@ use super::{Component, TreeComponent};
 use crossterm::event::{KeyEvent, MouseEventKind};
 use ego_tree::{NodeId, Tree as ETree};
 use ratatui::layout::{Constraint, Layout, Position, Rect};
-use ratatui::style::{Color, Modifier, Style, Stylize};
+use ratatui::style::Style;
 use ratatui::widgets::block::Block;
 use ratatui::widgets::{Clear, Scrollbar, ScrollbarOrientation};
 
 use ratatui::Frame;
 use tracing::{info};
+use std::collections::{HashMap, HashSet};
 use std::result::Result;
 use std::{fmt, vec};
 use tui_tree_widget::{Tree, TreeItem, TreeState};
 
 use crate::action::Action;
 use crate::app::Focus;
+use crate::config::bookmarks::{self, Bookmark};
 use crate::config::Config;
+use crate::config::theme::Theme;
 use crate::config::cloud_provider_config::cloud_provider_kind::CloudProviderKind;
 use crate::key::Key;
 use crate::util;
@@ -29,6 +32,10 @@ pub struct Connections {
     pub config: Config,
     pub results_pager: ResultsPager,
     pub filter: Box<dyn Filter>,
+    /// Bumped whenever the tree changes, so the filter can tell whether its matcher is up to date
+    generation: u64,
+    /// Shown in a section pinned above the cloud providers
+    pub bookmarks: Vec<Bookmark>,
 }
 
 impl Default for Connections {
@@ -40,12 +47,64 @@ impl Default for Connections {
             config: Config::default(),
             results_pager: ResultsPager::default(),
             filter: Box::new(ConnectionFilter::default()),
+            generation: 0,
+            bookmarks: vec![],
         }
     }
 }
 
 
 impl Connections {
+    /// Replaces the pinned bookmarks
+    pub fn pin_bookmarks(&mut self, bookmarks: Vec<Bookmark>) {
+        self.bookmarks = bookmarks;
+        self.items.retain(|item| item.identifier() != BOOKMARKS_NODE);
+        self.pin_items();
+    }
+
+    /// Puts the bookmarks section in front of the items built from the tree; it is not part of
+    /// the tree itself, so listing connections never looks at it
+    fn pin_items(&mut self) {
+        if self.bookmarks.is_empty() {
+            return;
+        }
+        let children = self
+            .bookmarks
+            .iter()
+            .map(|bookmark| TreeItem::new_leaf(bookmark.name.clone(), format!("* {}", bookmark.name)))
+            .collect();
+        if let Ok(section) = TreeItem::new(BOOKMARKS_NODE.to_string(), BOOKMARKS_NODE.to_string(), children) {
+            self.items.insert(0, section);
+        }
+    }
+
+    /// What the filter matches against: the connections, which are the leaves of the tree
+    fn filter_items(&self) -> Vec<String> {
+        self.tree
+            .nodes()
+            .filter(|n| !n.has_children())
+            .map(|n| n.value().to_string())
+            .collect()
+    }
+
+    /// Hands the filterable connections to the filter's fuzzy matcher whenever the tree changes
+    fn push_to_filter(&mut self) {
+        let items = self.filter_items();
+        self.generation += 1;
+        let matcher = self.filter.get_fuzzy_matcher();
+        matcher.restart();
+        matcher.push(items);
+        matcher.caught_up(self.generation);
+    }
+
+    /// The bookmark selected in the pinned section
+    fn selected_bookmark(&self) -> Option<&Bookmark> {
+        match self.state.selected() {
+            [section, name] if section == BOOKMARKS_NODE => self.bookmarks.iter().find(|bookmark| bookmark.name == *name),
+            _ => None,
+        }
+    }
+
     fn create_nodes(&mut self, config: &Config, node_id: NodeId, cloud_provider: &CloudProviderKind) -> Result<(), Action> {
             match cloud_provider {
                 CloudProviderKind::Azure => {
@@ -238,7 +297,7 @@ impl Component for Connections {
     }
 
     fn init(&mut self, config: &Config) -> Result<(), String> {
-        let mut tree = ETree::new("Cloud Providers".to_string());
+        let mut tree = ETree::new(CONNECTIONS_ROOT.to_string());
 
         config
             .cloud_provider_config
@@ -257,17 +316,28 @@ impl Component for Connections {
                 if let Ok(mut ti) = TreeItem::new(val.clone(), val.clone(), vec![]) {
                     let mut results_pager = self.results_pager.clone();
 
-                    util::add_children(node, &mut ti, &mut results_pager, Focus::Connections);
+                    util::add_children(node, &mut ti, &mut results_pager, Focus::Connections, &HashSet::new(), &HashMap::new(), &self.config.theme);
                     items.push(ti);
                 }
             });
 
         self.tree = tree;
         self.items = items;
+        self.push_to_filter();
+        self.bookmarks = bookmarks::load();
+        self.pin_items();
 
         Ok(())
     }
 
+    fn handle_paste(&mut self, text: &str, focus: Focus) -> Result<Action, Action> {
+        if !matches!(focus, Focus::ConnectionsFilter) {
+            return Ok(Action::Skip);
+        }
+        let txt = self.filter.paste(text);
+        let tree_items = self.filter_items();
+        self.filter.engage_filter(txt, self.generation, tree_items, None)
+    }
 
     fn handle_mouse_event(
         &mut self,
@@ -300,6 +370,8 @@ impl Component for Connections {
         let key: Key = key_event.into();
         match focus {
             Focus::Connections => {
+                // Enter and activate on a bookmark jump to it instead of listing anything
+                let opens = [self.config.key_config.list_item, self.config.key_config.activate].contains(&key);
                 if [self.config.key_config.quit, self.config.key_config.exit]
                     .iter()
                     .any(|kc| kc == &key)
@@ -352,6 +424,14 @@ impl Component for Connections {
                 } else if key == self.config.key_config.toggle_selected {
                     self.state.toggle_selected();
                     Ok(Action::Nothing)
+                } else if let Some(bookmark) = self.selected_bookmark().filter(|_| opens) {
+                    Ok(Action::OpenBookmark(bookmark.clone()))
+                } else if opens && self.state.selected().first().is_some_and(|first| first == BOOKMARKS_NODE) {
+                    // the section itself only opens and closes
+                    self.state.toggle_selected();
+                    Ok(Action::Nothing)
+                } else if key == self.config.key_config.bookmarks {
+                    Ok(Action::Bookmarks)
                 } else if key == self.config.key_config.activate {
                     let selection = self.state.selected().to_vec();
                     // let component_selection: ConnectionComponentSelection 
@@ -382,14 +462,8 @@ impl Component for Connections {
                 let action = self.filter.handle_key_event(key_event, focus)?;
                 match action {
                     Action::Filter(txt) => {
-                        let tree_items = self
-                            .tree
-                            .nodes()
-                            .filter(|n| !n.has_children())
-                            .map(|n| n.value().to_string())
-                            .collect();
-
-                        self.filter.engage_filter(txt, tree_items)
+                        let tree_items = self.filter_items();
+                        self.filter.engage_filter(txt, self.generation, tree_items, None)
                     }
                     _ => Ok(action),
                 }
@@ -401,7 +475,7 @@ impl Component for Connections {
         }
     }
 
-    fn draw(&mut self, frame: &mut Frame, area: Rect, focus: Focus, _config: &Config) -> Result<(), String> {
+    fn draw(&mut self, frame: &mut Frame, area: Rect, focus: Focus, config: &Config) -> Result<(), String> {
         let focused = matches!(focus, Focus::Connections);
         let [content, _] =
             Layout::vertical([Constraint::Min(1), Constraint::Length(3)]).areas(area);
@@ -414,16 +488,13 @@ impl Component for Connections {
                 let widget =
                     tree.block(Block::bordered().title("Cloud Connections").border_style(
                         if focused {
-                            Style::new().blue()
+                            config.theme.border
                         } else {
                             Style::default()
                         },
                     ))
                     .highlight_style(if focused {
-                        Style::new()
-                            .fg(Color::Black)
-                            .bg(Color::LightGreen)
-                            .add_modifier(Modifier::BOLD)
+                        config.theme.highlight
                     } else {
                         Style::default()
                     })
@@ -443,6 +514,15 @@ impl Component for Connections {
         }
     }
 
+    fn tick(&mut self, _focus: Focus, _config: &Config) -> bool {
+        self.filter.poll_matches()
+    }
+
+    fn set_theme(&mut self, theme: Theme) {
+        self.config.theme = theme;
+        self.filter.set_theme(theme);
+    }
+
     fn register_config(&mut self, config: &Config, focus: Focus) -> Result<(), String> {
         // self.config = config.clone();
         if !matches!(focus, Focus::Connections) {
@@ -462,9 +542,11 @@ impl Component for Connections {
                     info!("Tree Node Identified. Creating Stateful Tree for {:?}", &config.app_selection);
 
                     self.create_nodes(config, nid, &cloud_provider_kind).expect("Error Creating Nodes");
+                    self.push_to_filter();
 
                     self.items =
-                        util::make_tree_items(self.tree.nodes(), &mut self.results_pager, Focus::Connections);
+                        util::make_tree_items(self.tree.nodes(), &mut self.results_pager, Focus::Connections, &HashSet::new(), &HashMap::new(), &self.config.theme);
+                    self.pin_items();
                     self.state.open(config.app_selection.to_vec());
                 }
             };
@@ -496,6 +578,12 @@ impl TreeComponent for Connections {
     }
 }
 
+/// Root of the Connections tree, the first element of every selection in it
+pub const CONNECTIONS_ROOT: &str = "Cloud Providers";
+
+/// The section pinned above `CONNECTIONS_ROOT`, holding one item per bookmark
+pub const BOOKMARKS_NODE: &str = "Bookmarks";
+
 #[derive(Debug, Clone)]
 pub struct ConnectionComponentSelection {
     pub cloud_provider_kind: CloudProviderKind,
@@ -1,6 +1,5 @@
 use ratatui::{
     layout::{Constraint, Layout},
-    style::{Style, Stylize},
 };
 use tracing::info;
 use std::result::Result;
@@ -29,13 +28,13 @@ impl Component for ErrorComponent {
         frame: &mut ratatui::Frame,
         area: ratatui::prelude::Rect,
         focus: crate::app::Focus,
-        _config: &Config,
+        config: &Config,
     ) -> Result<(), String> {
         let focused = matches!(focus, Focus::Error);
         let [content, _] =
             Layout::vertical([Constraint::Min(1), Constraint::Length(3)]).areas(area);
 
-        let error = Popup::new(self.message.as_ref()).style(Style::new().red().on_black());
+        let error = Popup::new(self.message.as_ref()).style(config.theme.error_popup);
 
         if focused {
             frame.render_widget(&error, content);
@@ -1,107 +1,423 @@
+use std::collections::HashSet;
+use std::fmt;
+use std::ops::Range;
 use std::sync::Arc;
 use crossterm::event::KeyEvent;
 use nucleo::pattern::{CaseMatching, Normalization};
-use nucleo::{Config as NucleoConfig, Nucleo};
+use nucleo::{Config as NucleoConfig, Matcher, Nucleo};
+use regex_automata::{meta::Regex, util::syntax};
+use serde::{Deserialize, Serialize};
 use ratatui::{
     layout::{Constraint, Layout},
-    style::{Color, Style, Stylize},
-    widgets::{Block, Clear, List},
+    style::Style,
+    text::{Line, Span},
+    widgets::{Block, Clear},
 };
 use std::result::Result;
 use tui_textarea::TextArea;
 
-use crate::{action::Action, app::Focus, config::Config, key::Key};
+use crate::{
+    action::Action,
+    app::Focus,
+    config::{saved_searches::Definition, theme::Theme, Config},
+    key::Key,
+    storage::expression::FilterExpression,
+};
 
-use super::filter_results::{ConnectionFilterResults, FilterResults, ViewerFilterResults};
+use super::filter_results::{result_line, ConnectionFilterResults, DiffFilterResults, FilterResults, ViewerFilterResults};
 
-pub trait Filter: std::fmt::Debug {
-    fn default() -> Self where Self: Sized;
-    fn engage_filter(&mut self, txt: Vec<String>, tree_items: Vec<String>) -> Result<Action, Action> {
-        self.set_filter_result_items(tree_items);
+/// How the typed text is matched against the items
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum FilterMode {
+    #[default]
+    Fuzzy,
+    Substring,
+    Regex,
+    /// Start of the item's name, or of the whole path once the text contains a `/`
+    Prefix,
+}
 
-        let number_of_columns = 1;
+impl FilterMode {
+    fn next(self) -> Self {
+        match self {
+            FilterMode::Fuzzy => FilterMode::Substring,
+            FilterMode::Substring => FilterMode::Regex,
+            FilterMode::Regex => FilterMode::Prefix,
+            FilterMode::Prefix => FilterMode::Fuzzy,
+        }
+    }
+}
 
-        let mut nucleo = Nucleo::new(
-            NucleoConfig::DEFAULT,
-            Arc::new(|| {}),
-            None,
-            number_of_columns,
-        );
+impl fmt::Display for FilterMode {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            FilterMode::Fuzzy => write!(f, "fuzzy"),
+            FilterMode::Substring => write!(f, "substring"),
+            FilterMode::Regex => write!(f, "regex"),
+            FilterMode::Prefix => write!(f, "prefix"),
+        }
+    }
+}
 
-        // Send the strings to search through to the matcher
-        let injector = nucleo.injector();
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct MatchOptions {
+    pub mode: FilterMode,
+    pub case_sensitive: bool,
+}
 
-        let filtered_result_items = self.get_filter_result_items();
+impl MatchOptions {
+    /// Every item matching `pattern` in listing order with the positions of the matched characters,
+    /// for the modes that do not rank; fuzzy matching goes through nucleo
+    fn filter(&self, pattern: &str, items: &[String]) -> Result<Vec<(String, Vec<u32>)>, String> {
+        if pattern.is_empty() {
+            return Ok(items.iter().map(|item| (item.clone(), vec![])).collect());
+        }
+        let case_sensitive = self.case_sensitive;
 
-        for (id, string) in filtered_result_items
-            .iter()
-            .enumerate()
-        {
-            // Only the strings assigned to row in the closure below are matched on,
-            // so it's possible to pass an identifier in.
-            let item = (id, string.to_owned());
-
-            injector.push(item, |(_id, string), row| {
-                // The size of this array is determined by number_of_columns
-                let str_clone = string.clone();
-                row[0] = str_clone.into()
-            });
+        match self.mode {
+            FilterMode::Regex => {
+                let regex = Regex::builder()
+                    .syntax(syntax::Config::new().case_insensitive(!case_sensitive))
+                    .build(pattern)
+                    .map_err(|e| format!("Invalid regex: {e}"))?;
+                Ok(items
+                    .iter()
+                    .filter_map(|item| {
+                        let found = regex.find(item.as_str())?;
+                        let matched = item[found.start()..found.end()].chars().count();
+                        Some((item.clone(), char_positions(item, found.start(), matched)))
+                    })
+                    .collect())
+            }
+            FilterMode::Prefix => Ok(items
+                .iter()
+                .filter_map(|item| {
+                    let start = if pattern.contains('/') {
+                        0
+                    } else {
+                        item.trim_end_matches('/').rfind('/').map_or(0, |idx| idx + 1)
+                    };
+                    let matched = match_len(&item[start..], pattern, case_sensitive)?;
+                    Some((item.clone(), char_positions(item, start, matched)))
+                })
+                .collect()),
+            FilterMode::Substring => Ok(items
+                .iter()
+                .filter_map(|item| {
+                    let (start, matched) = item
+                        .char_indices()
+                        .find_map(|(start, _)| Some((start, match_len(&item[start..], pattern, case_sensitive)?)))?;
+                    Some((item.clone(), char_positions(item, start, matched)))
+                })
+                .collect()),
+            FilterMode::Fuzzy => Err("Fuzzy matching goes through the fuzzy matcher".to_string()),
+        }
+    }
+
+    /// Next mode, keeping the case sensitivity
+    pub fn cycle_mode(&mut self) {
+        self.mode = self.mode.next();
+    }
+}
+
+impl fmt::Display for MatchOptions {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let case = if self.case_sensitive { "match case" } else { "ignore case" };
+        write!(f, "{}, {case}", self.mode)
+    }
+}
+
+/// One nucleo instance for the lifetime of a filter. Items are pushed once, as they are listed,
+/// and every keystroke only reparses the pattern instead of rebuilding the matcher.
+pub struct FuzzyMatcher {
+    nucleo: Nucleo<String>,
+    /// Only used to find the matched characters of the rows on screen
+    matcher: Matcher,
+    pushed: usize,
+    /// The generation of the owner's items that everything pushed so far adds up to
+    generation: Option<u64>,
+    pattern: String,
+    case_sensitive: bool,
+    /// Result rows whose matched characters are already highlighted
+    highlighted: Range<usize>,
+    /// Items left by metadata conditions; matches outside it are dropped
+    narrowed: Option<HashSet<String>>,
+}
+
+impl Default for FuzzyMatcher {
+    fn default() -> Self {
+        Self {
+            nucleo: Nucleo::new(NucleoConfig::DEFAULT, Arc::new(|| {}), None, 1),
+            matcher: Matcher::new(NucleoConfig::DEFAULT),
+            pushed: 0,
+            generation: None,
+            pattern: String::new(),
+            case_sensitive: false,
+            highlighted: 0..0,
+            narrowed: None,
         }
+    }
+}
 
-        if let Some(search_term) = txt.last() {
-            nucleo.pattern.reparse(
-                0,
-                search_term,
-                CaseMatching::Ignore,
-                Normalization::Smart,
-                false,
-            );
+impl fmt::Debug for FuzzyMatcher {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("FuzzyMatcher")
+            .field("pushed", &self.pushed)
+            .field("generation", &self.generation)
+            .field("pattern", &self.pattern)
+            .field("case_sensitive", &self.case_sensitive)
+            .finish()
+    }
+}
+
+impl FuzzyMatcher {
+    /// Adds newly listed items; they are matched in the background
+    pub fn push(&mut self, items: impl IntoIterator<Item = String>) {
+        let injector = self.nucleo.injector();
+        for item in items {
+            injector.push(item, |item, row| row[0] = item.as_str().into());
+            self.pushed += 1;
         }
+    }
 
-        // The search is initialised here...
+    /// Forgets every item, e.g. before a re-listed directory is pushed again
+    pub fn restart(&mut self) {
+        self.nucleo.restart(true);
+        self.pushed = 0;
+        self.generation = None;
+        self.highlighted = 0..0;
+    }
 
-        // ...but actually begins here
-        let _status = nucleo.tick(500);
+    /// Records that the items pushed so far are all of the owner's items as of `generation`
+    pub fn caught_up(&mut self, generation: u64) {
+        self.generation = Some(generation);
+    }
 
-        // Snapshot contains the current set of results
-        let snapshot = nucleo.snapshot();
+    /// The generation of the owner's items the matcher has all of, if any
+    pub fn generation(&self) -> Option<u64> {
+        self.generation
+    }
 
-        // Matching items are returned, ranked by highest score first.
-        // These are just the items as pushed to the injector earlier.
-        let matches: Vec<_> = snapshot.matched_items(..).collect();
+    fn reparse(&mut self, pattern: &str, case_sensitive: bool) {
+        if pattern == self.pattern && case_sensitive == self.case_sensitive {
+            return;
+        }
+        // a longer pattern only has to look at the previous matches
+        let append = case_sensitive == self.case_sensitive && pattern.starts_with(self.pattern.as_str());
+        let case_matching = if case_sensitive { CaseMatching::Respect } else { CaseMatching::Ignore };
+        self.nucleo.pattern.reparse(0, pattern, case_matching, Normalization::Smart, append);
+        self.pattern = pattern.to_string();
+        self.case_sensitive = case_sensitive;
+    }
 
-        let mut data_list: Vec<String> = vec![];
-        for item in matches {
-            let (_, data) = item.data;
+    /// Merges background progress without waiting for it; true when the matches changed
+    fn tick(&mut self) -> bool {
+        self.nucleo.tick(10).changed
+    }
 
-            data_list.push(data.to_string());
+    /// Current matches in nucleo's order: best score first, ties in the order they were pushed.
+    /// Matched characters are left to `highlight`, except for narrowed matches, which do not line
+    /// up with nucleo's rows and are few enough to highlight straight away.
+    fn matches(&mut self) -> Vec<(String, Vec<u32>)> {
+        self.highlighted = 0..0;
+        let snapshot = self.nucleo.snapshot();
+        match &self.narrowed {
+            None => snapshot.matched_items(..).map(|item| (item.data.clone(), vec![])).collect(),
+            Some(narrowed) => {
+                let pattern = snapshot.pattern().column_pattern(0);
+                snapshot
+                    .matched_items(..)
+                    .filter(|item| narrowed.contains(item.data))
+                    .map(|item| {
+                        let mut indices = vec![];
+                        pattern.indices(item.matcher_columns[0].slice(..), &mut self.matcher, &mut indices);
+                        indices.sort_unstable();
+                        indices.dedup();
+                        (item.data.clone(), indices)
+                    })
+                    .collect()
+            }
         }
+    }
 
-        // set filtered items to the data list
-        self.set_filter_result_filtered_items(data_list.clone());
+    /// Matched characters of the result rows in `rows` that are not highlighted yet
+    fn highlight(&mut self, rows: Range<usize>) -> Vec<(usize, String, Vec<u32>)> {
+        if rows == self.highlighted || self.pattern.is_empty() || self.narrowed.is_some() {
+            return vec![];
+        }
+        let snapshot = self.nucleo.snapshot();
+        let pattern = snapshot.pattern().column_pattern(0);
+        let highlights = rows
+            .clone()
+            .filter_map(|row| {
+                let item = snapshot.get_matched_item(row as u32)?;
+                let mut indices = vec![];
+                pattern.indices(item.matcher_columns[0].slice(..), &mut self.matcher, &mut indices);
+                indices.sort_unstable();
+                indices.dedup();
+                Some((row, item.data.clone(), indices))
+            })
+            .collect();
+        self.highlighted = rows;
+        highlights
+    }
+}
 
-        // gather the filtered items
-        let filtered_items = self.get_filter_result_filtered_items().clone();
+/// Number of characters `pattern` matches at the start of `text`. Characters are compared one by
+/// one, so the count holds for the original text even where lowercasing would change its length.
+fn match_len(text: &str, pattern: &str, case_sensitive: bool) -> Option<usize> {
+    let same = |a: char, b: char| a == b || (!case_sensitive && a.to_lowercase().eq(b.to_lowercase()));
+    let mut text = text.chars();
+    let mut matched = 0;
+    for wanted in pattern.chars() {
+        if !same(text.next()?, wanted) {
+            return None;
+        }
+        matched += 1;
+    }
+    Some(matched)
+}
 
-        // add filtered items to the results.items()
-        let filter_result_items = self.get_filter_result_results().clone().items(filtered_items.clone());
+/// Positions of `count` characters of `text` starting at byte `start`
+fn char_positions(text: &str, start: usize, count: usize) -> Vec<u32> {
+    let first = text[..start].chars().count();
+    (first..first + count).map(|position| position as u32).collect()
+}
 
-        // set filtered results to the items above
-        self.set_filter_result_results(filter_result_items);
+pub trait Filter: std::fmt::Debug {
+    fn default() -> Self where Self: Sized;
+    /// Whether `size>1GB`, `class:NEARLINE` and friends mean something for the filtered items
+    fn supports_metadata(&self) -> bool {
+        false
+    }
+    fn parse_expression(&self, txt: &[String]) -> Result<FilterExpression, String> {
+        let input = txt.last().map(String::as_str).unwrap_or_default();
+        if self.supports_metadata() {
+            FilterExpression::parse(input)
+        } else {
+            Ok(FilterExpression::plain(input))
+        }
+    }
+    fn set_syntax_error(&mut self, _error: Option<String>) {}
+    /// Opens the filter with `text` typed in and matched the `options` way, e.g. from a saved filter
+    fn set_query(&mut self, _text: &str, _options: MatchOptions) {}
+    fn match_options(&self) -> MatchOptions {
+        MatchOptions::default()
+    }
+    /// Whether rows show the name first and the dimmed parent path after it
+    fn split_parent(&self) -> bool {
+        true
+    }
+    /// `items` are everything that can be filtered as of `generation`, which the owner bumps
+    /// whenever they change; `narrowed` is what the expression's metadata conditions leave of
+    /// them, if it has any. Only the remaining words are matched here. Fuzzy matching runs in the
+    /// background over the items pushed to the matcher as they were listed, and may not have
+    /// finished when this returns; `refresh_matches` picks up the rest.
+    fn engage_filter(
+        &mut self,
+        txt: Vec<String>,
+        generation: u64,
+        items: Vec<String>,
+        narrowed: Option<Vec<String>>,
+    ) -> Result<Action, Action> {
+        let expression = match self.parse_expression(&txt) {
+            Ok(expression) => {
+                self.set_syntax_error(None);
+                expression
+            }
+            Err(e) => {
+                // keep the last good results until the expression parses again
+                self.set_syntax_error(Some(e));
+                return Ok(Action::Nothing);
+            }
+        };
+        let matcher = self.get_fuzzy_matcher();
+        if matcher.generation != Some(generation) {
+            // the items changed since the matcher last saw them, start over from them
+            matcher.restart();
+            matcher.push(items.iter().cloned());
+            matcher.caught_up(generation);
+        }
+        matcher.narrowed = narrowed.as_ref().map(|narrowed| narrowed.iter().cloned().collect());
+        self.set_filter_result_items(narrowed.unwrap_or(items));
+
+        let options = self.match_options();
+        let data_list = match options.mode {
+            FilterMode::Fuzzy => {
+                let matcher = self.get_fuzzy_matcher();
+                matcher.reparse(&expression.text, options.case_sensitive);
+                matcher.tick();
+                matcher.matches()
+            }
+            _ => match options.filter(&expression.text, self.get_filter_result_items()) {
+                Ok(data_list) => data_list,
+                Err(e) => {
+                    self.set_syntax_error(Some(e));
+                    return Ok(Action::Nothing);
+                }
+            },
+        };
+        self.show_matches(data_list);
 
         Ok(Action::Nothing)
-        
     }
+    /// Shows whatever the background fuzzy matcher has found since the last call and highlights
+    /// the rows that fit in `height`; called on every draw
+    fn refresh_matches(&mut self, height: usize) {
+        if self.match_options().mode != FilterMode::Fuzzy {
+            return;
+        }
+        self.poll_matches();
+
+        let rows = self.visible_result_rows(height);
+        let split_parent = self.split_parent();
+        let theme = *self.theme();
+        for (row, item, indices) in self.get_fuzzy_matcher().highlight(rows) {
+            self.set_filter_result_line(row, result_line(&item, &indices, split_parent, &theme));
+        }
+    }
+    /// Shows whatever the background fuzzy matcher has found since the last call; whether it found anything
+    fn poll_matches(&mut self) -> bool {
+        if self.match_options().mode != FilterMode::Fuzzy {
+            return false;
+        }
+        let matcher = self.get_fuzzy_matcher();
+        if !matcher.tick() {
+            return false;
+        }
+        let data_list = matcher.matches();
+        self.show_matches(data_list);
+        true
+    }
+    fn show_matches(&mut self, data_list: Vec<(String, Vec<u32>)>) {
+        // the rows show highlighted names, selecting one still hands out the plain item
+        let split_parent = self.split_parent();
+        let theme = *self.theme();
+        let lines: Vec<Line<'static>> = data_list
+            .iter()
+            .map(|(item, indices)| result_line(item, indices, split_parent, &theme))
+            .collect();
+
+        self.set_filter_result_filtered_items(data_list.into_iter().map(|(item, _)| item).collect());
+        self.set_filter_result_lines(lines);
+    }
+    fn theme(&self) -> &Theme;
+    fn set_theme(&mut self, theme: Theme);
+    fn get_fuzzy_matcher(&mut self) -> &mut FuzzyMatcher;
     fn switch_active_status(&mut self);
     fn get_filter_result_items(&mut self) -> &Vec<String>;
     fn set_filter_result_items(&mut self, tree_items: Vec<String>);
     fn set_filter_result_filtered_items(&mut self, data_list: Vec<String>);
-    fn get_filter_result_filtered_items(&mut self) -> &Vec<String>;
-    fn get_filter_result_results(&mut self) -> &List<'static>;
-    fn set_filter_result_results(&mut self, filter_result_items: List<'static>);
+    fn set_filter_result_lines(&mut self, lines: Vec<Line<'static>>);
+    fn set_filter_result_line(&mut self, row: usize, line: Line<'static>);
+    /// Result rows that fit in `height` once the selection is scrolled into view
+    fn visible_result_rows(&self, height: usize) -> Range<usize>;
     fn filter_results_handle_key_event(&mut self, key_event: KeyEvent, focus: Focus) -> Result<Action, Action>;
     fn draw(&mut self, frame: &mut ratatui::Frame, area: ratatui::prelude::Rect, focus: Focus) -> Result<(), String>;
     fn handle_key_event(&mut self, key_event: KeyEvent, focus: Focus) -> Result<Action, Action>;
+    /// Inserts pasted text at the cursor and returns the lines to filter by
+    fn paste(&mut self, text: &str) -> Vec<String>;
     fn register_config(&mut self, config: &Config, focus: Focus) -> Result<(), String>;
 } 
 
@@ -111,6 +427,18 @@ pub struct ConnectionFilter {
     pub active: bool,
     pub textarea: TextArea<'static>,
     pub filtered_results: Box<dyn FilterResults>,
+    pub options: MatchOptions,
+    pub syntax_error: Option<String>,
+    pub matcher: FuzzyMatcher,
+}
+
+#[derive(Debug)]
+pub struct DiffFilter {
+    pub config: Config,
+    pub active: bool,
+    pub textarea: TextArea<'static>,
+    pub filtered_results: Box<dyn FilterResults>,
+    pub matcher: FuzzyMatcher,
 }
 
 #[derive(Debug)]
@@ -119,18 +447,47 @@ pub struct ViewerFilter {
     pub active: bool,
     pub textarea: TextArea<'static>,
     pub filtered_results: Box<dyn FilterResults>,
+    pub options: MatchOptions,
+    pub syntax_error: Option<String>,
+    pub matcher: FuzzyMatcher,
 }
 
 
 impl Filter for ConnectionFilter {
     fn default() -> Self where Self: Sized {
-        Self { config: Config::default(), active: false, textarea: TextArea::default(), filtered_results: Box::new(ConnectionFilterResults::default()) }
+        Self {
+            config: Config::default(),
+            active: false,
+            textarea: TextArea::default(),
+            filtered_results: Box::new(ConnectionFilterResults::default()),
+            options: MatchOptions::default(),
+            syntax_error: None,
+            matcher: FuzzyMatcher::default(),
+        }
+    }
+
+    fn set_syntax_error(&mut self, error: Option<String>) {
+        self.syntax_error = error;
+    }
+
+    fn match_options(&self) -> MatchOptions {
+        self.options
     }
 
     fn switch_active_status(&mut self) {
         self.active = !self.active;
     }
 
+    fn theme(&self) -> &Theme {
+        &self.config.theme
+    }
+    fn set_theme(&mut self, theme: Theme) {
+        self.config.theme = theme;
+    }
+    fn get_fuzzy_matcher(&mut self) -> &mut FuzzyMatcher {
+        &mut self.matcher
+    }
+
     fn set_filter_result_items(&mut self, tree_items: Vec<String>) {
         self.filtered_results.set_items(tree_items);
     }
@@ -143,16 +500,16 @@ impl Filter for ConnectionFilter {
         self.filtered_results.set_filtered_items(data_list);
     }
 
-    fn get_filter_result_filtered_items(&mut self) -> &Vec<String> {
-        self.filtered_results.get_filtered_items()
+    fn set_filter_result_lines(&mut self, lines: Vec<Line<'static>>) {
+        self.filtered_results.set_lines(lines);
     }
 
-    fn get_filter_result_results(&mut self) -> &List<'static> {
-        self.filtered_results.get_results()
+    fn set_filter_result_line(&mut self, row: usize, line: Line<'static>) {
+        self.filtered_results.set_line(row, line);
     }
 
-    fn set_filter_result_results(&mut self, filter_result_items: List<'static>) {
-        self.filtered_results.set_results(filter_result_items);
+    fn visible_result_rows(&self, height: usize) -> Range<usize> {
+        self.filtered_results.visible_rows(height)
     }
 
     fn filter_results_handle_key_event(&mut self, key_event: KeyEvent, focus: Focus) -> Result<Action, Action> {
@@ -174,18 +531,20 @@ impl Filter for ConnectionFilter {
             .set_cursor_line_style(ratatui::style::Style::default());
         self.textarea
             .set_placeholder_text("Add some text to begin filtering");
-        self.textarea.set_style(Style::default().fg(Color::White));
-        self.textarea
-            .set_block(
-                Block::bordered()
-                    .title("Filter Connections")
-                    .border_style(if focused {
-                        Style::new().blue()
-                    } else {
-                        Style::default()
-                    }),
-            );
+        self.textarea.set_style(self.config.theme.input);
+        let block = Block::bordered()
+            .title(format!("Filter Connections ({})", self.options))
+            .border_style(match (&self.syntax_error, focused) {
+                (Some(_), _) => self.config.theme.error,
+                (None, true) => self.config.theme.border,
+                (None, false) => Style::default(),
+            });
+        self.textarea.set_block(match &self.syntax_error {
+            Some(e) => block.title_bottom(Span::styled(e.clone(), self.config.theme.error)),
+            None => block,
+        });
         if self.active {
+            self.refresh_matches(list_res.height.saturating_sub(2) as usize);
             frame.render_widget(Clear, filter);
             frame.render_widget(&self.textarea, filter);
             frame.render_widget(Clear, list_res);
@@ -207,6 +566,12 @@ impl Filter for ConnectionFilter {
                 } else if key == self.config.key_config.close_component {
                     self.active = !self.active;
                     Ok(Action::ChangeFocus(Focus::Connections))
+                } else if key == self.config.key_config.filter_mode {
+                    self.options.cycle_mode();
+                    Ok(Action::Filter(self.textarea.clone().into_lines()))
+                } else if key == self.config.key_config.filter_case {
+                    self.options.case_sensitive = !self.options.case_sensitive;
+                    Ok(Action::Filter(self.textarea.clone().into_lines()))
                 } else if matches!(key, Key::Char(_))
                     || [
                         self.config.key_config.backspace,
@@ -237,6 +602,11 @@ impl Filter for ConnectionFilter {
         }
     }
 
+    fn paste(&mut self, text: &str) -> Vec<String> {
+        self.textarea.insert_str(text);
+        self.textarea.clone().into_lines()
+    }
+
     fn register_config(&mut self, config: &Config, focus: Focus) -> Result<(), String> {
         // self.config = config;
         self.filtered_results
@@ -246,7 +616,36 @@ impl Filter for ConnectionFilter {
 
 impl Filter for ViewerFilter {
     fn default() -> Self where Self: Sized {
-        Self { config: Config::default(), active: false, textarea: TextArea::default(), filtered_results: Box::new(ViewerFilterResults::default()) }
+        Self {
+            config: Config::default(),
+            active: false,
+            textarea: TextArea::default(),
+            filtered_results: Box::new(ViewerFilterResults::default()),
+            options: MatchOptions::default(),
+            syntax_error: None,
+            matcher: FuzzyMatcher::default(),
+        }
+    }
+
+    fn match_options(&self) -> MatchOptions {
+        self.options
+    }
+
+    fn supports_metadata(&self) -> bool {
+        true
+    }
+
+    fn set_query(&mut self, text: &str, options: MatchOptions) {
+        if !self.active {
+            self.switch_active_status();
+        }
+        self.textarea = TextArea::from([text]);
+        self.textarea.move_cursor(tui_textarea::CursorMove::End);
+        self.options = options;
+    }
+
+    fn set_syntax_error(&mut self, error: Option<String>) {
+        self.syntax_error = error;
     }
 
     fn set_filter_result_items(&mut self, tree_items: Vec<String>) {
@@ -261,16 +660,16 @@ impl Filter for ViewerFilter {
         self.filtered_results.set_filtered_items(data_list);
     }
 
-    fn get_filter_result_filtered_items(&mut self) -> &Vec<String> {
-        self.filtered_results.get_filtered_items()
+    fn set_filter_result_lines(&mut self, lines: Vec<Line<'static>>) {
+        self.filtered_results.set_lines(lines);
     }
 
-    fn get_filter_result_results(&mut self) -> &List<'static> {
-        self.filtered_results.get_results()
+    fn set_filter_result_line(&mut self, row: usize, line: Line<'static>) {
+        self.filtered_results.set_line(row, line);
     }
 
-    fn set_filter_result_results(&mut self, filter_result_items: List<'static>) {
-        self.filtered_results.set_results(filter_result_items);
+    fn visible_result_rows(&self, height: usize) -> Range<usize> {
+        self.filtered_results.visible_rows(height)
     }
 
     fn filter_results_handle_key_event(&mut self, key_event: KeyEvent, focus: Focus) -> Result<Action, Action> {
@@ -281,6 +680,16 @@ impl Filter for ViewerFilter {
         self.active = !self.active;
     }
 
+    fn theme(&self) -> &Theme {
+        &self.config.theme
+    }
+    fn set_theme(&mut self, theme: Theme) {
+        self.config.theme = theme;
+    }
+    fn get_fuzzy_matcher(&mut self) -> &mut FuzzyMatcher {
+        &mut self.matcher
+    }
+
     fn draw(
         &mut self,
         frame: &mut ratatui::Frame,
@@ -300,18 +709,177 @@ impl Filter for ViewerFilter {
         self.textarea
             .set_cursor_line_style(ratatui::style::Style::default());
         self.textarea
-            .set_placeholder_text("Add some text to begin filtering");
-        self.textarea.set_style(Style::default().fg(Color::White));
+            .set_placeholder_text("Add some text to begin filtering, or e.g. size>1GB updated<7d class:NEARLINE");
+        self.textarea.set_style(self.config.theme.input);
+        let block = Block::bordered()
+            .title(format!("Filter CloudFS Results ({})", self.options))
+            .border_style(match (&self.syntax_error, focused) {
+                (Some(_), _) => self.config.theme.error,
+                (None, true) => self.config.theme.border,
+                (None, false) => Style::default(),
+            });
+        self.textarea.set_block(match &self.syntax_error {
+            Some(e) => block.title_bottom(Span::styled(e.clone(), self.config.theme.error)),
+            None => block,
+        });
+        if self.active {
+            self.refresh_matches(list_res.height.saturating_sub(2) as usize);
+            frame.render_widget(Clear, filter);
+            frame.render_widget(&self.textarea, filter);
+            frame.render_widget(Clear, list_res);
+            self.filtered_results.draw(frame, list_res, focus)?;
+        }
+        Ok(())
+    }
+
+    fn handle_key_event(
+        &mut self,
+        key_event: KeyEvent,
+        focus: crate::app::Focus,
+    ) -> Result<Action, Action> {
+        let key: Key = key_event.into();
+        match focus {
+            Focus::ViewerFilter => {
+                if key == self.config.key_config.exit {
+                    Ok(Action::Quit)
+                } else if key == self.config.key_config.close_component {
+                    self.active = !self.active;
+                    Ok(Action::ChangeFocus(Focus::Viewer))
+                } else if key == self.config.key_config.filter_mode {
+                    self.options.cycle_mode();
+                    Ok(Action::Filter(self.textarea.clone().into_lines()))
+                } else if key == self.config.key_config.filter_case {
+                    self.options.case_sensitive = !self.options.case_sensitive;
+                    Ok(Action::Filter(self.textarea.clone().into_lines()))
+                } else if key == self.config.key_config.save_search {
+                    let text = self.textarea.lines().join(" ");
+                    Ok(Action::SaveSearch(Definition::filter(text, self.options)))
+                } else if matches!(key, Key::Char(_))
+                    || [
+                        self.config.key_config.backspace,
+                        self.config.key_config.delete,
+                        self.config.key_config.arrow_down,
+                        self.config.key_config.arrow_up,
+                        self.config.key_config.arrow_left,
+                        self.config.key_config.arrow_right,
+                    ]
+                    .iter()
+                    .any(|kc| kc == &key)
+                {
+                    self.textarea.input(key_event);
+                    Ok(Action::Filter(self.textarea.clone().into_lines()))
+                } else if [
+                    self.config.key_config.enter,
+                    self.config.key_config.change_focus,
+                ]
+                .iter()
+                .any(|kc| kc == &key)
+                {
+                    Ok(Action::ChangeFocus(Focus::ViewerFilterResults))
+                } else {
+                    Ok(Action::Nothing)
+                }
+            }
+            _ => Ok(Action::Skip),
+        }
+    }
+
+    fn paste(&mut self, text: &str) -> Vec<String> {
+        self.textarea.insert_str(text);
+        self.textarea.clone().into_lines()
+    }
+
+    fn register_config(&mut self, config: &Config, focus: Focus) -> Result<(), String> {
+        // self.config = config;
+        self.filtered_results
+            .register_config(config.clone(), focus)
+    }
+}
+
+impl Filter for DiffFilter {
+    fn default() -> Self where Self: Sized {
+        Self {
+            config: Config::default(),
+            active: false,
+            textarea: TextArea::default(),
+            filtered_results: Box::new(DiffFilterResults::default()),
+            matcher: FuzzyMatcher::default(),
+        }
+    }
+
+    fn split_parent(&self) -> bool {
+        false
+    }
+
+    fn set_filter_result_items(&mut self, tree_items: Vec<String>) {
+        self.filtered_results.set_items(tree_items);
+    }
+
+    fn get_filter_result_items(&mut self) -> &Vec<String> {
+        self.filtered_results.get_items()
+    }
+
+    fn set_filter_result_filtered_items(&mut self, data_list: Vec<String>) {
+        self.filtered_results.set_filtered_items(data_list);
+    }
+
+    fn set_filter_result_lines(&mut self, lines: Vec<Line<'static>>) {
+        self.filtered_results.set_lines(lines);
+    }
+
+    fn set_filter_result_line(&mut self, row: usize, line: Line<'static>) {
+        self.filtered_results.set_line(row, line);
+    }
+
+    fn visible_result_rows(&self, height: usize) -> Range<usize> {
+        self.filtered_results.visible_rows(height)
+    }
+
+    fn filter_results_handle_key_event(&mut self, key_event: KeyEvent, focus: Focus) -> Result<Action, Action> {
+        self.filtered_results.handle_key_event(key_event, focus)
+    }
+
+    fn switch_active_status(&mut self) {
+        self.active = !self.active;
+    }
+
+    fn theme(&self) -> &Theme {
+        &self.config.theme
+    }
+    fn set_theme(&mut self, theme: Theme) {
+        self.config.theme = theme;
+    }
+    fn get_fuzzy_matcher(&mut self) -> &mut FuzzyMatcher {
+        &mut self.matcher
+    }
+
+    fn draw(
+        &mut self,
+        frame: &mut ratatui::Frame,
+        area: ratatui::prelude::Rect,
+        focus: crate::app::Focus,
+    ) -> Result<(), String> {
+        let focused = matches!(focus, Focus::DiffFilter);
+
+        let [filter, list_res] =
+            Layout::vertical([Constraint::Length(3), Constraint::Min(1)]).areas(area);
+
+        self.textarea
+            .set_cursor_line_style(ratatui::style::Style::default());
+        self.textarea
+            .set_placeholder_text("Type to filter, e.g. \"left only\" or a path");
+        self.textarea.set_style(self.config.theme.input);
         self.textarea.set_block(
             Block::bordered()
-                .title("Filter CloudFS Results")
+                .title("Filter Differences")
                 .border_style(if focused {
-                    Style::new().blue()
+                    self.config.theme.border
                 } else {
                     Style::default()
                 }),
         );
         if self.active {
+            self.refresh_matches(list_res.height.saturating_sub(2) as usize);
             frame.render_widget(Clear, filter);
             frame.render_widget(&self.textarea, filter);
             frame.render_widget(Clear, list_res);
@@ -327,7 +895,7 @@ impl Filter for ViewerFilter {
     ) -> Result<Action, Action> {
         let key: Key = key_event.into();
         match focus {
-            Focus::ViewerFilter => {
+            Focus::DiffFilter => {
                 if key == self.config.key_config.exit {
                     Ok(Action::Quit)
                 } else if key == self.config.key_config.close_component {
@@ -354,7 +922,7 @@ impl Filter for ViewerFilter {
                 .iter()
                 .any(|kc| kc == &key)
                 {
-                    Ok(Action::ChangeFocus(Focus::ViewerFilterResults))
+                    Ok(Action::ChangeFocus(Focus::DiffFilterResults))
                 } else {
                     Ok(Action::Nothing)
                 }
@@ -363,9 +931,114 @@ impl Filter for ViewerFilter {
         }
     }
 
+    fn paste(&mut self, text: &str) -> Vec<String> {
+        self.textarea.insert_str(text);
+        self.textarea.clone().into_lines()
+    }
+
     fn register_config(&mut self, config: &Config, focus: Focus) -> Result<(), String> {
-        // self.config = config;
         self.filtered_results
             .register_config(config.clone(), focus)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Every match once nucleo has gone through all pushed items
+    fn settled_matches(matcher: &mut FuzzyMatcher) -> Vec<String> {
+        while matcher.nucleo.tick(10).running {}
+        matcher.matches().into_iter().map(|(item, _)| item).collect()
+    }
+
+    fn objects(names: &[&str]) -> Vec<String> {
+        names.iter().map(|name| format!("gs://bucket/{name}")).collect()
+    }
+
+    fn options(mode: FilterMode, case_sensitive: bool) -> MatchOptions {
+        MatchOptions { mode, case_sensitive }
+    }
+
+    #[test]
+    fn match_len_compares_character_by_character() {
+        assert_eq!(match_len("Report.csv", "rep", false), Some(3));
+        assert_eq!(match_len("Report.csv", "rep", true), None);
+        assert_eq!(match_len("ÄBC", "äb", false), Some(2));
+        assert_eq!(match_len("ab", "abc", false), None);
+        // lowercasing İ gives two characters, which would throw a byte count off
+        assert_eq!(match_len("İx", "i̇x", false), None);
+        assert_eq!(match_len("anything", "", true), Some(0));
+    }
+
+    #[test]
+    fn substring_and_prefix_matches_are_highlighted() -> Result<(), String> {
+        let items = objects(&["Report.csv", "data/rep.txt", "prep/", "reports/"]);
+        let substring = options(FilterMode::Substring, false).filter("rep", &items)?;
+        assert_eq!(
+            substring,
+            vec![
+                (items[0].clone(), vec![12, 13, 14]),
+                (items[1].clone(), vec![17, 18, 19]),
+                (items[2].clone(), vec![13, 14, 15]),
+                (items[3].clone(), vec![12, 13, 14]),
+            ]
+        );
+
+        // the start of the name, or of the whole path once the text has a `/`
+        let names: Vec<String> = options(FilterMode::Prefix, false)
+            .filter("rep", &items)?
+            .into_iter()
+            .map(|(item, _)| item)
+            .collect();
+        assert_eq!(names, objects(&["Report.csv", "data/rep.txt", "reports/"]));
+        let paths = options(FilterMode::Prefix, true).filter("gs://bucket/d", &items)?;
+        assert_eq!(paths, vec![(items[1].clone(), (0..13).collect())]);
+        Ok(())
+    }
+
+    #[test]
+    fn regex_matches_count_characters_not_bytes() -> Result<(), String> {
+        let items = objects(&["ünïcode.txt", "CODE.md"]);
+        assert_eq!(
+            options(FilterMode::Regex, true).filter("code", &items)?,
+            vec![(items[0].clone(), vec![15, 16, 17, 18])]
+        );
+        assert_eq!(options(FilterMode::Regex, false).filter("co.e", &items)?.len(), 2);
+        assert!(options(FilterMode::Regex, false).filter("(", &items).is_err_and(|e| e.starts_with("Invalid regex")));
+        Ok(())
+    }
+
+    #[test]
+    fn an_empty_pattern_keeps_everything() {
+        let items = objects(&["a", "b"]);
+        let all = options(FilterMode::Regex, false).filter("", &items);
+        assert_eq!(all, Ok(vec![(items[0].clone(), vec![]), (items[1].clone(), vec![])]));
+        assert!(options(FilterMode::Fuzzy, false).filter("a", &items).is_err());
+    }
+
+    #[test]
+    fn a_new_generation_replaces_as_many_old_items() -> Result<(), Action> {
+        let mut filter = <ViewerFilter as Filter>::default();
+        filter.engage_filter(vec![String::new()], 1, objects(&["a", "b"]), None)?;
+        filter.engage_filter(vec![String::new()], 2, objects(&["c", "d"]), None)?;
+        assert_eq!(settled_matches(&mut filter.matcher), objects(&["c", "d"]));
+
+        // the same generation is matched without being pushed again
+        filter.engage_filter(vec!["d".to_string()], 2, objects(&["c", "d"]), None)?;
+        assert_eq!(settled_matches(&mut filter.matcher), objects(&["d"]));
+        Ok(())
+    }
+
+    #[test]
+    fn narrowed_items_keep_only_their_matches() -> Result<(), Action> {
+        let mut filter = <ViewerFilter as Filter>::default();
+        filter.engage_filter(vec![String::new()], 1, objects(&["a", "b", "c"]), Some(objects(&["b"])))?;
+        assert_eq!(settled_matches(&mut filter.matcher), objects(&["b"]));
+
+        // dropping the metadata conditions brings the rest back without a restart
+        filter.engage_filter(vec![String::new()], 1, objects(&["a", "b", "c"]), None)?;
+        assert_eq!(settled_matches(&mut filter.matcher), objects(&["a", "b", "c"]));
+        Ok(())
+    }
+}
@@ -1,11 +1,13 @@
 use crossterm::event::KeyEvent;
 use ratatui::{
-    style::{Color, Modifier, Style, Stylize},
+    style::Style,
+    text::{Line, Span},
     widgets::{Block, List, ListDirection, ListState},
 };
+use std::ops::Range;
 use std::result::Result;
 
-use crate::{action::Action, app::Focus, config::Config, key::Key};
+use crate::{action::Action, app::Focus, config::{theme::Theme, Config}, key::Key};
 
 pub trait FilterResults: std::fmt::Debug {
     fn draw(&mut self, frame: &mut ratatui::Frame, area: ratatui::prelude::Rect, focus: Focus) -> Result<(), String>;
@@ -14,9 +16,68 @@ pub trait FilterResults: std::fmt::Debug {
     fn get_items(&mut self) -> &Vec<String>;
     fn set_items(&mut self, tree_items: Vec<String>);
     fn set_filtered_items(&mut self, data_list: Vec<String>);
-    fn get_filtered_items(&mut self) -> &Vec<String>;
-    fn set_results(&mut self, filter_result_items: List<'static>);
-    fn get_results(&mut self) -> &List<'static>;
+    /// One line per filtered item
+    fn set_lines(&mut self, lines: Vec<Line<'static>>);
+    fn set_line(&mut self, row: usize, line: Line<'static>);
+    fn visible_rows(&self, height: usize) -> Range<usize>;
+}
+
+/// Rows `height` rows of a list show once the selection is scrolled into view, the way `List` scrolls
+pub fn visible_rows(state: &ListState, height: usize, len: usize) -> Range<usize> {
+    let mut offset = state.offset();
+    if let Some(selected) = state.selected() {
+        if selected < offset {
+            offset = selected;
+        } else if selected >= offset + height {
+            offset = selected + 1 - height;
+        }
+    }
+    let start = offset.min(len);
+    start..(offset + height).min(len)
+}
+
+/// A result row: the name with its matched characters highlighted, then the parent path dimmed
+/// so equal names under different prefixes can be told apart. `indices` are character positions in `item`.
+pub fn result_line(item: &str, indices: &[u32], split_parent: bool, theme: &Theme) -> Line<'static> {
+    let chars: Vec<char> = item.chars().collect();
+    let name_start = match item.trim_end_matches('/').rfind('/') {
+        Some(idx) if split_parent => item[..=idx].chars().count(),
+        _ => 0,
+    };
+
+    let mut spans = highlighted_spans(&chars, name_start, chars.len(), indices, Style::new(), theme.matched);
+    if name_start > 0 {
+        spans.push(Span::raw("  "));
+        spans.extend(highlighted_spans(&chars, 0, name_start, indices, theme.muted, theme.matched));
+    }
+    Line::from(spans)
+}
+
+/// Spans for `chars[start..end]`, with the characters at `indices` patched with `match_style`
+fn highlighted_spans(chars: &[char], start: usize, end: usize, indices: &[u32], style: Style, match_style: Style) -> Vec<Span<'static>> {
+    let mut spans = vec![];
+    let mut run = String::new();
+    let mut run_matched = false;
+    for (position, c) in chars.iter().enumerate().take(end).skip(start) {
+        let matched = indices.binary_search(&(position as u32)).is_ok();
+        if matched != run_matched && !run.is_empty() {
+            spans.push(highlighted_span(std::mem::take(&mut run), run_matched, style, match_style));
+        }
+        run_matched = matched;
+        run.push(*c);
+    }
+    if !run.is_empty() {
+        spans.push(highlighted_span(run, run_matched, style, match_style));
+    }
+    spans
+}
+
+fn highlighted_span(text: String, matched: bool, style: Style, match_style: Style) -> Span<'static> {
+    if matched {
+        Span::styled(text, style.patch(match_style))
+    } else {
+        Span::styled(text, style)
+    }
 }
 
 #[derive(Debug, Clone, Default)]
@@ -24,7 +85,16 @@ pub struct ConnectionFilterResults {
     pub config: Config,
     pub items: Vec<String>,
     pub filtered_items: Vec<String>,
-    pub results: List<'static>,
+    pub lines: Vec<Line<'static>>,
+    pub state: ListState,
+}
+
+#[derive(Debug, Default, Clone)]
+pub struct DiffFilterResults {
+    pub config: Config,
+    pub items: Vec<String>,
+    pub filtered_items: Vec<String>,
+    pub lines: Vec<Line<'static>>,
     pub state: ListState,
 }
 
@@ -33,7 +103,7 @@ pub struct ViewerFilterResults {
     pub config: Config,
     pub items: Vec<String>,
     pub filtered_items: Vec<String>,
-    pub results: List<'static>,
+    pub lines: Vec<Line<'static>>,
     pub state: ListState,
 }
 
@@ -50,16 +120,18 @@ impl FilterResults for ConnectionFilterResults {
         self.filtered_items = data_list;
     }
 
-    fn get_filtered_items(&mut self) -> &Vec<String> {
-        &self.filtered_items
+    fn set_lines(&mut self, lines: Vec<Line<'static>>) {
+        self.lines = lines;
     }
 
-    fn get_results(&mut self) -> &List<'static> {
-        &self.results
+    fn set_line(&mut self, row: usize, line: Line<'static>) {
+        if let Some(existing) = self.lines.get_mut(row) {
+            *existing = line;
+        }
     }
 
-    fn set_results(&mut self, filter_result_items: List<'static>) {
-        self.results = filter_result_items;
+    fn visible_rows(&self, height: usize) -> Range<usize> {
+        visible_rows(&self.state, height, self.lines.len())
     }
 
     fn draw(
@@ -69,24 +141,19 @@ impl FilterResults for ConnectionFilterResults {
         focus: crate::app::Focus,
     ) -> Result<(), String> {
         let focused = matches!(focus, Focus::ConnectionFilterResults);
-        let list = self
-            .results
-            .clone()
+        let list = List::new(self.lines.clone())
             .block(
                 Block::bordered()
                     .title("Connection Results Filtered")
                     .border_style(if focused {
-                        Style::new().blue()
+                        self.config.theme.border
                     } else {
                         Style::default()
                     }),
             )
-            .style(Style::new().bg(Color::Black))
+            .style(self.config.theme.popup)
             .highlight_style(if focused {
-                Style::new()
-                    .fg(Color::Black)
-                    .bg(Color::LightGreen)
-                    .add_modifier(Modifier::BOLD)
+                self.config.theme.highlight
             } else {
                 Style::default()
             })
@@ -163,16 +230,18 @@ impl FilterResults for ViewerFilterResults {
         self.filtered_items = data_list;
     }
 
-    fn get_filtered_items(&mut self) -> &Vec<String> {
-        &self.filtered_items
+    fn set_lines(&mut self, lines: Vec<Line<'static>>) {
+        self.lines = lines;
     }
 
-    fn get_results(&mut self) -> &List<'static> {
-        &self.results
+    fn set_line(&mut self, row: usize, line: Line<'static>) {
+        if let Some(existing) = self.lines.get_mut(row) {
+            *existing = line;
+        }
     }
 
-    fn set_results(&mut self, filter_result_items: List<'static>) {
-        self.results = filter_result_items;
+    fn visible_rows(&self, height: usize) -> Range<usize> {
+        visible_rows(&self.state, height, self.lines.len())
     }
 
     fn draw(
@@ -182,24 +251,19 @@ impl FilterResults for ViewerFilterResults {
         focus: crate::app::Focus,
     ) -> Result<(), String> {
         let focused = matches!(focus, Focus::ViewerFilterResults);
-        let list = self
-            .results
-            .clone()
+        let list = List::new(self.lines.clone())
             .block(
                 Block::bordered()
                     .title("CloudFS Results Filtered")
                     .border_style(if focused {
-                        Style::new().blue()
+                        self.config.theme.border
                     } else {
                         Style::default()
                     }),
             )
-            .style(Style::new().bg(Color::Black))
+            .style(self.config.theme.popup)
             .highlight_style(if focused {
-                Style::new()
-                    .fg(Color::Black)
-                    .bg(Color::LightGreen)
-                    .add_modifier(Modifier::BOLD)
+                self.config.theme.highlight
             } else {
                 Style::default()
             })
@@ -262,3 +326,107 @@ impl FilterResults for ViewerFilterResults {
     }
 }
 
+impl FilterResults for DiffFilterResults {
+    fn set_items(&mut self, tree_items: Vec<String>) {
+        self.items = tree_items;
+    }
+
+    fn get_items(&mut self) -> &Vec<String> {
+        &self.items
+    }
+
+    fn set_filtered_items(&mut self, data_list: Vec<String>) {
+        self.filtered_items = data_list;
+    }
+
+    fn set_lines(&mut self, lines: Vec<Line<'static>>) {
+        self.lines = lines;
+    }
+
+    fn set_line(&mut self, row: usize, line: Line<'static>) {
+        if let Some(existing) = self.lines.get_mut(row) {
+            *existing = line;
+        }
+    }
+
+    fn visible_rows(&self, height: usize) -> Range<usize> {
+        visible_rows(&self.state, height, self.lines.len())
+    }
+
+    fn draw(
+        &mut self,
+        frame: &mut ratatui::Frame,
+        area: ratatui::prelude::Rect,
+        focus: crate::app::Focus,
+    ) -> Result<(), String> {
+        let focused = matches!(focus, Focus::DiffFilterResults);
+        let list = List::new(self.lines.clone())
+            .block(
+                Block::bordered()
+                    .title(format!("Differences ({} of {})", self.filtered_items.len(), self.items.len()))
+                    .border_style(if focused {
+                        self.config.theme.border
+                    } else {
+                        Style::default()
+                    }),
+            )
+            .style(self.config.theme.popup)
+            .highlight_style(if focused {
+                self.config.theme.highlight
+            } else {
+                Style::default()
+            })
+            .repeat_highlight_symbol(true)
+            .direction(ListDirection::TopToBottom);
+
+        frame.render_stateful_widget(list, area, &mut self.state);
+        Ok(())
+    }
+
+    fn register_config(
+        &mut self,
+        config: crate::config::Config,
+        _focus: Focus,
+    ) -> Result<(), String> {
+        self.config = config;
+        Ok(())
+    }
+
+    fn handle_key_event(
+        &mut self,
+        key_event: crossterm::event::KeyEvent,
+        focus: Focus,
+    ) -> Result<Action, Action> {
+        let key: Key = key_event.into();
+        match focus {
+            Focus::DiffFilterResults => {
+                if key == self.config.key_config.exit {
+                    Ok(Action::Quit)
+                } else if [
+                    self.config.key_config.key_up,
+                    self.config.key_config.arrow_up,
+                ]
+                .iter()
+                .any(|kc| kc == &key)
+                {
+                    self.state.select_previous();
+                    Ok(Action::Nothing)
+                } else if [
+                    self.config.key_config.key_down,
+                    self.config.key_config.arrow_down,
+                ]
+                .iter()
+                .any(|kc| kc == &key)
+                {
+                    self.state.select_next();
+                    Ok(Action::Nothing)
+                } else if key == self.config.key_config.change_focus {
+                    Ok(Action::ChangeFocus(Focus::DiffFilter))
+                } else {
+                    Ok(Action::Nothing)
+                }
+            }
+            _ => Ok(Action::Skip),
+        }
+    }
+}
@@ -2,14 +2,18 @@ use std::result::Result;
 
 use ratatui::{
     layout::{Constraint, Layout},
-    style::{Style, Stylize},
-    text::Line,
+    style::Style,
+    text::{Line, Span},
     widgets::{Block, Borders, Paragraph},
 };
 
 use crate::{action::Action, app::Focus, config::Config};
 
-use super::{results_pager::ResultsPager, Component, TreeComponent};
+use super::{
+    help::{self, Shown},
+    results_pager::ResultsPager,
+    Component, TreeComponent,
+};
 
 #[derive(Debug, Default)]
 pub struct Footer {
@@ -40,148 +44,101 @@ impl Component for Footer {
     ) -> Result<(), String> {
         let [_, footer] = Layout::vertical([Constraint::Min(1), Constraint::Length(3)]).areas(area);
 
-        let [active_connection, commands, quit_and_close] = Layout::horizontal([
+        let [active_connection, commands, transfers, quit_and_close] = Layout::horizontal([
             Constraint::Percentage(15),
             Constraint::Min(1),
+            Constraint::Length(24),
             Constraint::Percentage(15),
         ])
         .areas(footer);
 
+        let theme = config.theme;
         let active_config = match &config.cloud_provider_config.active_cloud_connection {
             None => "No Active Cloud Connection".to_string(),
             Some(s) => s.to_string()
         };
 
-        let active_connection_widget = Paragraph::new(Line::from(vec![active_config.green()]))
+        let active_connection_widget = Paragraph::new(Line::from(vec![Span::styled(active_config, theme.connection)]))
             .block(
                 Block::default()
                     .borders(Borders::ALL)
-                    .title("Active Connections")
+                    .title(if config.read_only { "Active Connections (read-only)" } else { "Active Connections" })
                     .style(Style::default()),
             );
 
         frame.render_widget(active_connection_widget, active_connection);
 
-        let commands_widget = match focus {
-            Focus::Connections => {
-                let connection_commands = vec![
-                    "Switch to Viewer=".into(),
-                    "[Tab] ".blue(),
-                    "List Items=".into(),
-                    "[Enter] ".blue(),
-                    "Activate Account=".into(),
-                    "[a] ".blue(),
-                    "Open Filter=".into(),
-                    "[/]".blue(),
-                ];
-                Paragraph::new(Line::from(connection_commands)).block(
-                    Block::default()
-                        .borders(Borders::ALL)
-                        .title("Connection Commands")
-                        .style(Style::default()),
-                )
-            }
-            Focus::Viewer => {
-                let mut viewer_commands = vec![
-                    "Switch to Connections=".into(),
-                    "[Tab] ".blue(),
-                    "List Items=".into(),
-                    "[Enter] ".blue(),
-                    "Open Filter=".into(),
-                    "[/]".blue(),
-                ];
-                if self.results_pager.num_pages > 1 {
-                    viewer_commands.push("Next Page=".into());
-                    viewer_commands.push("[Ctrl+l] ".blue());
-                    viewer_commands.push("Previous Page=".into());
-                    viewer_commands.push("[Ctrl+h] ".blue());
-                }
-                Paragraph::new(Line::from(viewer_commands)).block(
-                    Block::default()
-                        .borders(Borders::ALL)
-                        .title("Viewer Commands")
-                        .style(Style::default()),
-                )
-            }
-            Focus::ConnectionsFilter => {
-                let filter_commands = vec![
-                    "Switch to Results=".into(),
-                    "[Enter/Tab] ".blue(),
-                    "Close Filtering=".into(),
-                    "[Esc] ".blue(),
-                ];
-                Paragraph::new(Line::from(filter_commands)).block(
-                    Block::default()
-                        .borders(Borders::ALL)
-                        .title("Filter Commands")
-                        .style(Style::default()),
-                )
+        // generated from the bindings, so rebinding a key in the config file shows up here
+        let paged = self.results_pager.num_pages > 1;
+        let mut hints: Vec<Span> = help::commands(focus, &self.config.key_config)
+            .into_iter()
+            .filter(|command| match command.shown {
+                Shown::Footer => true,
+                Shown::FooterWhenPaged => paged,
+                Shown::HelpOnly => false,
+            })
+            .flat_map(|command| {
+                [
+                    Span::from(format!("{}=", command.label)),
+                    Span::styled(format!("[{}] ", command.keys_label()), theme.key_hint),
+                ]
+            })
+            .collect();
+        if matches!(focus, Focus::Error) {
+            hints.push("Press any key to continue".into());
+        }
+
+        let mut title = format!("{} Commands", help::pane(focus));
+        if matches!(focus, Focus::Connections | Focus::Viewer) {
+            let mut status = vec![];
+            if !config.pending_keys.is_empty() {
+                status.push(format!("keys: {}", config.pending_keys));
             }
-            Focus::ConnectionFilterResults => {
-                let filter_commands = vec![
-                    "Up=".into(),
-                    "[k/Up Arrow] ".blue(),
-                    "Down=".into(),
-                    "[j/Down Arrow] ".blue(),
-                    "Switch to Filter=".into(),
-                    "[Tab] ".blue(),
-                    "Select Result=".into(),
-                    "[Enter] ".blue(),
-                ];
-                Paragraph::new(Line::from(filter_commands)).block(
-                    Block::default()
-                        .borders(Borders::ALL)
-                        .title("Filter Results Commands")
-                        .style(Style::default()),
-                )
+            if config.marked > 0 {
+                status.push(format!("{} marked", config.marked));
             }
-            Focus::ViewerFilter => {
-                let filter_commands = vec![
-                    "Switch to Results=".into(),
-                    "[Enter/Tab] ".blue(),
-                    "Close Filtering=".into(),
-                    "[Esc] ".blue(),
-                ];
-                Paragraph::new(Line::from(filter_commands)).block(
-                    Block::default()
-                        .borders(Borders::ALL)
-                        .title("Filter Commands")
-                        .style(Style::default()),
-                )
+            if let Some(clipboard) = &config.clipboard {
+                status.push(format!(
+                    "{} {} on clipboard",
+                    clipboard.sources.len(),
+                    clipboard.kind.to_string().to_lowercase()
+                ));
             }
-            Focus::ViewerFilterResults => {
-                let filter_commands = vec![
-                    "Up=".into(),
-                    "[k/Up Arrow] ".blue(),
-                    "Down=".into(),
-                    "[j/Down Arrow] ".blue(),
-                    "Switch to Filter=".into(),
-                    "[Tab] ".blue(),
-                    "Select Result=".into(),
-                    "[Enter] ".blue(),
-                ];
-                Paragraph::new(Line::from(filter_commands)).block(
-                    Block::default()
-                        .borders(Borders::ALL)
-                        .title("Filter Results Commands")
-                        .style(Style::default()),
-                )
+            if !status.is_empty() {
+                title = format!("{title} ({})", status.join(", "));
             }
-            Focus::Error => {
-                let error_commands = vec!["Press any key to continue".into()];
-                Paragraph::new(Line::from(error_commands)).block(
-                    Block::default()
-                        .borders(Borders::ALL)
-                        .title("Error Commands")
-                        .style(Style::default()),
-                )
-            }
-        };
+        }
+        let commands_widget = Paragraph::new(Line::from(hints)).block(
+            Block::default()
+                .borders(Borders::ALL)
+                .title(title)
+                .style(Style::default()),
+        );
 
         frame.render_widget(commands_widget, commands);
 
+        let running_transfers = config.transfers.running_count();
+        let transfers_line = match running_transfers {
+            0 => Line::from("No transfers running"),
+            1 => Line::styled("1 transfer running", theme.pending),
+            n => Line::styled(format!("{n} transfers running"), theme.pending),
+        };
+        let transfers_widget = Paragraph::new(transfers_line).block(
+            Block::default()
+                .borders(Borders::ALL)
+                .title("Transfers")
+                .style(Style::default()),
+        );
+
+        frame.render_widget(transfers_widget, transfers);
+
+        let quit_keys = format!(
+            "{} / {}",
+            help::key_label(self.config.key_config.exit),
+            help::key_label(self.config.key_config.quit)
+        );
         let quit_and_close_widget =
-            Paragraph::new(Line::from(vec!["Ctrl + C".red(), " / 'q'".red()])).block(
+            Paragraph::new(Line::styled(quit_keys, theme.error)).block(
                 Block::default()
                     .borders(Borders::ALL)
                     .title("Quit/Close")
@@ -0,0 +1,359 @@
+use std::result::Result;
+
+use crossterm::event::KeyEvent;
+use ratatui::{
+    layout::Constraint,
+    style::{Modifier, Style},
+    widgets::{Block, Clear, Row, Table, TableState},
+};
+
+use crate::{
+    action::Action,
+    app::Focus,
+    config::{key_config::KeyConfig, Config},
+    key::Key,
+    util,
+};
+
+use super::Component;
+
+/// Where a command is advertised besides the help popup
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Shown {
+    Footer,
+    /// In the footer only while the listing has more than one page
+    FooterWhenPaged,
+    HelpOnly,
+}
+
+/// One thing the focused pane does, with every key bound to it
+#[derive(Debug, Clone)]
+pub struct Command {
+    pub label: &'static str,
+    pub keys: Vec<Key>,
+    pub shown: Shown,
+}
+
+impl Command {
+    fn new(label: &'static str, keys: &[Key], shown: Shown) -> Self {
+        let mut unique: Vec<Key> = vec![];
+        for key in keys {
+            if !unique.contains(key) {
+                unique.push(*key);
+            }
+        }
+        Self { label, keys: unique, shown }
+    }
+
+    /// The keys as the footer and help show them, e.g. `k/↑`
+    pub fn keys_label(&self) -> String {
+        self.keys.iter().map(|key| key_label(*key)).collect::<Vec<_>>().join("/")
+    }
+}
+
+/// A key without the angle brackets `Display` puts around named keys, e.g. `Ctrl+f` or `Esc`
+pub fn key_label(key: Key) -> String {
+    let text = key.to_string();
+    match text.strip_prefix('<').and_then(|rest| rest.strip_suffix('>')) {
+        Some(inner) if !inner.is_empty() => inner.to_string(),
+        _ => text,
+    }
+}
+
+/// The name the footer and help popup give a focus, e.g. `Viewer` in "Viewer Commands"
+pub fn pane(focus: Focus) -> &'static str {
+    match focus {
+        Focus::Connections => "Connection",
+        Focus::Viewer => "Viewer",
+        Focus::ConnectionsFilter | Focus::ViewerFilter => "Filter",
+        Focus::ConnectionFilterResults | Focus::ViewerFilterResults => "Filter Results",
+        Focus::DiffFilter => "Compare",
+        Focus::DiffFilterResults => "Compare Results",
+        Focus::Transfers => "Transfer",
+        Focus::SyncReview => "Sync",
+        Focus::Search => "Search",
+        Focus::SavedSearches => "Saved Search",
+        Focus::Bookmarks => "Bookmark",
+        Focus::Prompt => "Prompt",
+        Focus::Palette => "Palette",
+        Focus::Help => "Help",
+        Focus::Error => "Error",
+    }
+}
+
+/// Whether `?` and `:` open the help and the command palette in this focus; inputs take them as text instead
+pub fn accepts_shortcuts(focus: Focus) -> bool {
+    !matches!(
+        focus,
+        Focus::ConnectionsFilter
+            | Focus::ViewerFilter
+            | Focus::DiffFilter
+            | Focus::Prompt
+            | Focus::Palette
+            | Focus::Help
+            | Focus::Error
+    )
+}
+
+/// Every command of a focus, in the order the footer shows them, generated from the active bindings
+pub fn commands(focus: Focus, keys: &KeyConfig) -> Vec<Command> {
+    use Shown::{Footer, FooterWhenPaged, HelpOnly};
+
+    let up = Command::new("Up", &[keys.key_up, keys.arrow_up], Footer);
+    let down = Command::new("Down", &[keys.key_down, keys.arrow_down], Footer);
+    let tree = |focus_label| {
+        vec![
+            Command::new("Up", &[keys.key_up, keys.arrow_up], HelpOnly),
+            Command::new("Down", &[keys.key_down, keys.arrow_down], HelpOnly),
+            Command::new("Close/Parent", &[keys.key_left, keys.arrow_left], HelpOnly),
+            Command::new("Open", &[keys.key_right, keys.arrow_right], HelpOnly),
+            Command::new("First", &[keys.select_first], HelpOnly),
+            Command::new("Last", &[keys.select_last], HelpOnly),
+            Command::new(focus_label, &[keys.change_focus], Footer),
+            Command::new("List Items", &[keys.list_item], Footer),
+        ]
+    };
+    let quit = Command::new("Quit", &[keys.quit, keys.exit], HelpOnly);
+    let exit = Command::new("Quit", &[keys.exit], HelpOnly);
+    let help = Command::new("Help", &[keys.help], Footer);
+    let palette = Command::new("Actions", &[keys.palette, keys.palette_alt], Footer);
+
+    let mut commands = match focus {
+        Focus::Connections => {
+            let mut commands = tree("Switch to Viewer");
+            commands.extend([
+                Command::new("Open/Close", &[keys.toggle_selected], HelpOnly),
+                Command::new("Activate Account", &[keys.activate], Footer),
+                Command::new("Open Filter", &[keys.filter], Footer),
+                Command::new("Bookmarks", &[keys.bookmarks], Footer),
+            ]);
+            commands
+        }
+        Focus::Viewer => {
+            let mut commands = tree("Switch to Connections");
+            commands.extend([
+                Command::new("Open Filter", &[keys.filter], Footer),
+                Command::new("Mark", &[keys.mark], Footer),
+                Command::new("Mark All", &[keys.mark_children], Footer),
+                Command::new("Invert Marks", &[keys.invert_marks], Footer),
+                Command::new("Unmark All", &[keys.clear_marks], Footer),
+                Command::new("Download", &[keys.download], Footer),
+                Command::new("Delete", &[keys.delete_object], Footer),
+                Command::new("Upload", &[keys.upload], Footer),
+                Command::new("Verify", &[keys.verify], Footer),
+                Command::new("Copy", &[keys.yank], Footer),
+                Command::new("Move", &[keys.cut], Footer),
+                Command::new("Paste", &[keys.paste], Footer),
+                Command::new("Sync", &[keys.sync], Footer),
+                Command::new("Compare", &[keys.compare], Footer),
+                Command::new("Search", &[keys.search], Footer),
+                Command::new("Saved Searches", &[keys.saved_searches], Footer),
+                Command::new("Go To", &[keys.go_to], Footer),
+                Command::new("Back/Forward", &[keys.history_back, keys.history_forward], Footer),
+                Command::new("Transfers", &[keys.transfers], Footer),
+                Command::new("Bookmark", &[keys.bookmark], Footer),
+                Command::new("Bookmarks", &[keys.bookmarks], Footer),
+                Command::new("Refresh", &[keys.refresh], HelpOnly),
+                Command::new("Next Page", &[keys.next_page], FooterWhenPaged),
+                Command::new("Previous Page", &[keys.previous_page], FooterWhenPaged),
+            ]);
+            commands
+        }
+        Focus::ConnectionsFilter | Focus::ViewerFilter => {
+            let mut commands = vec![
+                Command::new("Switch to Results", &[keys.enter, keys.change_focus], Footer),
+                Command::new("Mode", &[keys.filter_mode], Footer),
+                Command::new("Case", &[keys.filter_case], Footer),
+            ];
+            if matches!(focus, Focus::ViewerFilter) {
+                commands.push(Command::new("Save", &[keys.save_search], Footer));
+            }
+            commands.extend([
+                Command::new("Close Filtering", &[keys.close_component], Footer),
+                Command::new("Move Cursor", &[keys.arrow_left, keys.arrow_right], HelpOnly),
+                Command::new("Delete Character", &[keys.backspace, keys.delete], HelpOnly),
+                exit,
+            ]);
+            commands
+        }
+        Focus::ConnectionFilterResults | Focus::ViewerFilterResults => vec![
+            up,
+            down,
+            Command::new("Switch to Filter", &[keys.change_focus], Footer),
+            Command::new("Select Result", &[keys.enter], Footer),
+            exit,
+        ],
+        Focus::DiffFilter => vec![
+            Command::new("Switch to Results", &[keys.enter, keys.change_focus], Footer),
+            Command::new("Close Comparison", &[keys.close_component], Footer),
+            exit,
+        ],
+        Focus::DiffFilterResults => vec![
+            up,
+            down,
+            Command::new("Switch to Filter", &[keys.change_focus], Footer),
+            Command::new("Close Comparison", &[keys.close_component], Footer),
+            exit,
+        ],
+        Focus::Transfers => vec![
+            up,
+            down,
+            Command::new("Retry", &[keys.retry], Footer),
+            Command::new("Cancel", &[keys.cancel], Footer),
+            Command::new("Close", &[keys.close_component, keys.transfers, keys.quit], Footer),
+            exit,
+        ],
+        Focus::SyncReview => vec![
+            Command::new("Up", &[keys.key_up, keys.arrow_up], HelpOnly),
+            Command::new("Down", &[keys.key_down, keys.arrow_down], HelpOnly),
+            Command::new("Include/Exclude", &[keys.toggle_selected], Footer),
+            Command::new("Reverse", &[keys.sync_reverse], Footer),
+            Command::new("Compare Mode", &[keys.sync_compare], Footer),
+            Command::new("Delete Extraneous", &[keys.sync_delete], Footer),
+            Command::new("Run", &[keys.enter], Footer),
+            Command::new("Cancel", &[keys.close_component, keys.quit], Footer),
+            exit,
+        ],
+        Focus::Search => vec![
+            up,
+            down,
+            Command::new("Show in Viewer", &[keys.enter], Footer),
+            Command::new("Save", &[keys.save_search], Footer),
+            Command::new("Stop and Close", &[keys.close_component, keys.quit], Footer),
+            exit,
+        ],
+        Focus::Bookmarks => vec![
+            up.clone(),
+            down.clone(),
+            Command::new("Open", &[keys.enter], Footer),
+            Command::new("Forget", &[keys.delete], Footer),
+            Command::new("Close", &[keys.close_component, keys.quit], Footer),
+            exit.clone(),
+        ],
+        Focus::SavedSearches => vec![
+            up,
+            down,
+            Command::new("Apply", &[keys.enter], Footer),
+            Command::new("Forget", &[keys.delete], Footer),
+            Command::new("Close", &[keys.close_component, keys.quit], Footer),
+            exit,
+        ],
+        Focus::Prompt => vec![
+            Command::new("Submit", &[keys.enter], Footer),
+            Command::new("Cancel", &[keys.close_component], Footer),
+            exit,
+        ],
+        Focus::Palette => vec![
+            Command::new("Up", &[keys.arrow_up], Footer),
+            Command::new("Down", &[keys.arrow_down], Footer),
+            Command::new("Run", &[keys.enter], Footer),
+            Command::new("Close", &[keys.close_component], Footer),
+            exit,
+        ],
+        Focus::Help => vec![
+            up,
+            down,
+            Command::new("Close", &[keys.close_component, keys.help, keys.quit], Footer),
+            exit,
+        ],
+        Focus::Error => vec![],
+    };
+    if matches!(focus, Focus::Connections | Focus::Viewer) {
+        commands.push(quit);
+    }
+    if accepts_shortcuts(focus) {
+        commands.push(palette);
+        commands.push(help);
+    }
+    commands
+}
+
+/// Lists every command of the pane it was opened from, with the keys currently bound to it
+#[derive(Debug, Default)]
+pub struct Help {
+    pub config: Config,
+    pub state: TableState,
+    /// The focus whose commands are listed, and which closing the help goes back to
+    pub return_focus: Option<Focus>,
+}
+
+impl Help {
+    pub fn open(&mut self, focus: Focus) {
+        self.return_focus = Some(focus);
+        self.state.select(Some(0));
+    }
+
+    fn close(&mut self) -> Focus {
+        self.return_focus.take().unwrap_or(Focus::Connections)
+    }
+}
+
+impl Component for Help {
+    fn name(&self) -> &str {
+        "Help"
+    }
+    fn as_any_mut(&mut self) -> &mut dyn std::any::Any {
+        self
+    }
+
+    fn register_config(&mut self, _config: &Config, _focus: Focus) -> Result<(), String> {
+        Ok(())
+    }
+
+    fn draw(
+        &mut self,
+        frame: &mut ratatui::Frame,
+        area: ratatui::prelude::Rect,
+        focus: Focus,
+        config: &Config,
+    ) -> Result<(), String> {
+        let (Focus::Help, Some(listed)) = (focus, self.return_focus) else {
+            return Ok(());
+        };
+
+        let panel = util::popup_area(area, 60, 70);
+        let rows: Vec<Row> = commands(listed, &self.config.key_config)
+            .iter()
+            .map(|command| Row::new(vec![command.keys_label(), command.label.to_string()]))
+            .collect();
+        let table = Table::new(rows, [Constraint::Percentage(30), Constraint::Fill(1)])
+            .header(Row::new(vec!["Key", "Command"]).style(Style::new().add_modifier(Modifier::BOLD)))
+            .block(
+                Block::bordered()
+                    .title(format!("{} Commands", pane(listed)))
+                    .border_style(config.theme.border),
+            )
+            .style(config.theme.popup)
+            .row_highlight_style(config.theme.highlight);
+
+        frame.render_widget(Clear, panel);
+        frame.render_stateful_widget(table, panel, &mut self.state);
+        Ok(())
+    }
+
+    fn handle_key_event(&mut self, key_event: KeyEvent, focus: Focus) -> Result<Action, Action> {
+        let key: Key = key_event.into();
+        let keys = self.config.key_config;
+        if !matches!(focus, Focus::Help) {
+            // the panes ignore keys they have no binding for, so this wins over their `Nothing`
+            if key == keys.help && accepts_shortcuts(focus) {
+                return Ok(Action::Help);
+            }
+            return Ok(Action::Skip);
+        }
+
+        if key == keys.exit {
+            Ok(Action::Quit)
+        } else if [keys.close_component, keys.help, keys.quit].contains(&key) {
+            Ok(Action::ChangeFocus(self.close()))
+        } else if [keys.key_up, keys.arrow_up].contains(&key) {
+            self.state.select_previous();
+            Ok(Action::Nothing)
+        } else if [keys.key_down, keys.arrow_down].contains(&key) {
+            self.state.select_next();
+            Ok(Action::Nothing)
+        } else {
+            Ok(Action::Nothing)
+        }
+    }
+}
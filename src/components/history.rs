@@ -0,0 +1,41 @@
+/// How many visits back the history reaches
+const HISTORY_LENGTH: usize = 100;
+
+/// Prefixes visited in the Viewer, browser style: visiting something new drops the forward entries
+#[derive(Debug, Clone, Default)]
+pub struct NavigationHistory {
+    back: Vec<String>,
+    current: Option<String>,
+    forward: Vec<String>,
+}
+
+impl NavigationHistory {
+    pub fn visit(&mut self, value: &str) {
+        if self.current.as_deref() == Some(value) {
+            return;
+        }
+        if let Some(current) = self.current.replace(value.to_string()) {
+            self.back.push(current);
+        }
+        if self.back.len() > HISTORY_LENGTH {
+            self.back.remove(0);
+        }
+        self.forward.clear();
+    }
+
+    pub fn back(&mut self) -> Option<String> {
+        let previous = self.back.pop()?;
+        if let Some(current) = self.current.replace(previous.clone()) {
+            self.forward.push(current);
+        }
+        Some(previous)
+    }
+
+    pub fn forward(&mut self) -> Option<String> {
+        let next = self.forward.pop()?;
+        if let Some(current) = self.current.replace(next.clone()) {
+            self.back.push(current);
+        }
+        Some(next)
+    }
+}
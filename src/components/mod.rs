@@ -1,7 +1,8 @@
 use std::result::Result;
 use std::any::Any;
+use std::sync::mpsc::Sender;
 
-use crate::{action::Action, app::Focus, config::Config};
+use crate::{action::Action, app::Focus, config::{theme::Theme, Config}};
 use crossterm::event::{KeyEvent, MouseEvent};
 use ego_tree::{NodeId, Tree};
 use ratatui::{layout::Rect, Frame};
@@ -9,12 +10,22 @@ use tracing::info;
 
 // pub mod connection_filter;
 // pub mod connection_filter_results;
+pub mod bookmarks;
+pub mod compare;
 pub mod connections;
 pub mod error;
 pub mod filter;
 pub mod filter_results;
 pub mod footer;
+pub mod help;
+pub mod history;
+pub mod palette;
+pub mod prompt;
 pub mod results_pager;
+pub mod saved_searches;
+pub mod search;
+pub mod sync_review;
+pub mod transfers;
 pub mod viewer;
 // pub mod viewer_filter;
 // pub mod viewer_filter_results;
@@ -39,7 +50,27 @@ pub trait Component: std::fmt::Debug {
         let _foucs = focus;
         Ok(Action::Nothing)
     }
+    /// Text pasted into the terminal, already joined into one line
+    fn handle_paste(&mut self, text: &str, focus: Focus) -> Result<Action, Action> {
+        let _text = text;
+        let _focus = focus;
+        Ok(Action::Skip)
+    }
     fn register_config(&mut self, config: &Config, focus: Focus) -> Result<(), String>;
+    /// Picks up the progress of work running in the background; whether anything it shows changed
+    fn tick(&mut self, focus: Focus, config: &Config) -> bool {
+        let _focus = focus;
+        let _config = config;
+        false
+    }
+    /// Restyles what the component built with the previous theme; `draw` is handed the new one
+    fn set_theme(&mut self, theme: Theme) {
+        let _theme = theme;
+    }
+    /// A sender for actions the component wants run outside of the key or mouse event that caused them
+    fn register_action_sender(&mut self, sender: Sender<Action>) {
+        let _sender = sender;
+    }
     fn report_error(&mut self, message: &String) -> Result<(), String> {
         let _message = message;
         Ok(())
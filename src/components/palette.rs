@@ -0,0 +1,211 @@
+use std::result::Result;
+use std::sync::mpsc::Sender;
+
+use crossterm::event::KeyEvent;
+use nucleo::pattern::{CaseMatching, Normalization, Pattern};
+use nucleo::{Config as NucleoConfig, Matcher, Utf32Str};
+use ratatui::{
+    layout::{Constraint, Layout},
+    style::Style,
+    text::Line,
+    widgets::{Block, Clear, Row, Table, TableState},
+};
+use tracing::error;
+use tui_textarea::TextArea;
+
+use crate::{action::Action, app::Focus, config::Config, key::Key, util};
+
+use super::{filter_results::result_line, help, Component};
+
+/// One action the palette offers, run through the app like any other `Action`
+#[derive(Debug, Clone)]
+pub struct PaletteEntry {
+    pub label: String,
+    /// The key that does the same in the pane the palette was opened from
+    pub key: Option<Key>,
+    pub action: Action,
+}
+
+impl PaletteEntry {
+    pub fn new(label: impl Into<String>, key: Option<Key>, action: Action) -> Self {
+        Self { label: label.into(), key, action }
+    }
+}
+
+/// Lists every action available where it was opened and fuzzy-matches them as you type
+#[derive(Debug)]
+pub struct CommandPalette {
+    pub config: Config,
+    pub textarea: TextArea<'static>,
+    pub entries: Vec<PaletteEntry>,
+    /// Indices into `entries` with the matched character positions, best match first
+    pub matches: Vec<(usize, Vec<u32>)>,
+    pub state: TableState,
+    pub return_focus: Focus,
+    /// Queues the picked action, to run once the focus is back where the palette was opened
+    pub sender: Option<Sender<Action>>,
+}
+
+impl Default for CommandPalette {
+    fn default() -> Self {
+        Self {
+            config: Config::default(),
+            textarea: TextArea::default(),
+            entries: vec![],
+            matches: vec![],
+            state: TableState::default(),
+            return_focus: Focus::Viewer,
+            sender: None,
+        }
+    }
+}
+
+impl CommandPalette {
+    pub fn open(&mut self, entries: Vec<PaletteEntry>, return_focus: Focus) {
+        self.entries = entries;
+        self.textarea = TextArea::default();
+        self.return_focus = return_focus;
+        self.refilter();
+    }
+
+    /// Ranks the entries against the typed text with nucleo; no text keeps them all in order
+    fn refilter(&mut self) {
+        let text = self.textarea.lines().join("");
+        let pattern = Pattern::parse(&text, CaseMatching::Smart, Normalization::Smart);
+        let mut matcher = Matcher::new(NucleoConfig::DEFAULT);
+        let mut buffer = vec![];
+        let mut scored: Vec<(u32, usize, Vec<u32>)> = self
+            .entries
+            .iter()
+            .enumerate()
+            .filter_map(|(idx, entry)| {
+                let mut indices = vec![];
+                let score = pattern.indices(Utf32Str::new(&entry.label, &mut buffer), &mut matcher, &mut indices)?;
+                indices.sort_unstable();
+                indices.dedup();
+                Some((score, idx, indices))
+            })
+            .collect();
+        scored.sort_by_key(|(score, _, _)| std::cmp::Reverse(*score));
+        self.matches = scored.into_iter().map(|(_, idx, indices)| (idx, indices)).collect();
+        self.state.select(if self.matches.is_empty() { None } else { Some(0) });
+    }
+
+    fn run(&self, action: Action) {
+        match &self.sender {
+            Some(sender) if sender.send(action).is_ok() => (),
+            _ => error!("The palette has no action queue to run its entries in"),
+        }
+    }
+
+    fn selected(&self) -> Option<&PaletteEntry> {
+        let (idx, _) = self.matches.get(self.state.selected()?)?;
+        self.entries.get(*idx)
+    }
+}
+
+impl Component for CommandPalette {
+    fn name(&self) -> &str {
+        "CommandPalette"
+    }
+    fn as_any_mut(&mut self) -> &mut dyn std::any::Any {
+        self
+    }
+
+    fn register_config(&mut self, _config: &Config, _focus: Focus) -> Result<(), String> {
+        Ok(())
+    }
+
+    fn register_action_sender(&mut self, sender: Sender<Action>) {
+        self.sender = Some(sender);
+    }
+
+    fn draw(
+        &mut self,
+        frame: &mut ratatui::Frame,
+        area: ratatui::prelude::Rect,
+        focus: Focus,
+        config: &Config,
+    ) -> Result<(), String> {
+        if !matches!(focus, Focus::Palette) {
+            return Ok(());
+        }
+
+        let panel = util::popup_area(area, 60, 60);
+        let [input, list] = Layout::vertical([Constraint::Length(3), Constraint::Fill(1)]).areas(panel);
+
+        self.textarea.set_cursor_line_style(Style::default());
+        self.textarea.set_style(config.theme.input);
+        self.textarea.set_block(
+            Block::bordered()
+                .title(format!("{} Actions", help::pane(self.return_focus)))
+                .border_style(config.theme.border),
+        );
+
+        let rows: Vec<Row> = self
+            .matches
+            .iter()
+            .filter_map(|(idx, indices)| {
+                let entry = self.entries.get(*idx)?;
+                let key = entry.key.map(help::key_label).unwrap_or_default();
+                Some(Row::new(vec![
+                    result_line(&entry.label, indices, false, &config.theme),
+                    Line::styled(key, config.theme.key_hint),
+                ]))
+            })
+            .collect();
+        let table = Table::new(rows, [Constraint::Fill(1), Constraint::Length(12)])
+            .block(Block::bordered().border_style(config.theme.border))
+            .style(config.theme.popup)
+            .row_highlight_style(config.theme.highlight);
+
+        frame.render_widget(Clear, panel);
+        frame.render_widget(&self.textarea, input);
+        frame.render_stateful_widget(table, list, &mut self.state);
+        Ok(())
+    }
+
+    fn handle_key_event(&mut self, key_event: KeyEvent, focus: Focus) -> Result<Action, Action> {
+        let key: Key = key_event.into();
+        let keys = self.config.key_config;
+        if !matches!(focus, Focus::Palette) {
+            if [keys.palette, keys.palette_alt].contains(&key) && help::accepts_shortcuts(focus) {
+                return Ok(Action::Palette);
+            }
+            return Ok(Action::Skip);
+        }
+
+        if key == keys.exit {
+            Ok(Action::Quit)
+        } else if key == keys.close_component {
+            Ok(Action::ChangeFocus(self.return_focus))
+        } else if key == keys.enter {
+            match self.selected() {
+                Some(entry) => {
+                    self.run(entry.action.clone());
+                    Ok(Action::ChangeFocus(self.return_focus))
+                }
+                None => Ok(Action::Nothing),
+            }
+        } else if key == keys.arrow_up {
+            self.state.select_previous();
+            Ok(Action::Nothing)
+        } else if key == keys.arrow_down {
+            self.state.select_next();
+            Ok(Action::Nothing)
+        } else {
+            self.textarea.input(key_event);
+            self.refilter();
+            Ok(Action::Nothing)
+        }
+    }
+
+    fn handle_paste(&mut self, text: &str, focus: Focus) -> Result<Action, Action> {
+        if !matches!(focus, Focus::Palette) {
+            return Ok(Action::Skip);
+        }
+        self.textarea.insert_str(text);
+        self.refilter();
+        Ok(Action::Nothing)
+    }
+}
@@ -0,0 +1,187 @@
+use std::fmt;
+use std::result::Result;
+
+use crossterm::event::KeyEvent;
+use ratatui::{
+    layout::{Constraint, Layout},
+    style::Style,
+    widgets::{Block, Clear},
+};
+use tui_textarea::TextArea;
+
+use crate::{
+    action::Action,
+    app::Focus,
+    config::{saved_searches::Definition, Config},
+    key::Key,
+    storage::cloud_uri::CloudUri,
+    transfer::TransferKind,
+};
+
+use super::Component;
+
+/// What the text typed into the prompt will be used for
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PromptKind {
+    /// Local file or directory to upload into the given prefix
+    Upload(String),
+    /// Local file to compare with the given object
+    Verify(String),
+    /// Local directory to sync with the given prefix
+    Sync(String),
+    /// Prefix to compare the given prefix with
+    Compare(String),
+    /// Glob or regex to search for recursively
+    Search,
+    /// URI to list every level down to and expand
+    GoTo,
+    /// Name to save the given filter or search under
+    SaveSearch(Definition),
+    /// Name to bookmark the given URI under
+    Bookmark(String),
+    /// Objects and prefixes to delete once confirmed
+    Delete(Vec<CloudUri>),
+    /// Destination for the objects on the clipboard
+    Paste { kind: TransferKind, count: usize },
+}
+
+impl fmt::Display for PromptKind {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            PromptKind::Upload(destination) => write!(f, "Upload local path to {destination}"),
+            PromptKind::Verify(object) => write!(f, "Local file to verify against {object}"),
+            PromptKind::Sync(prefix) => write!(f, "Local directory to sync with {prefix}"),
+            PromptKind::Compare(left) => write!(f, "Compare {left} with"),
+            PromptKind::Search => write!(f, "Search (glob such as gs://bucket/**/*.parquet, or <prefix> re:<regex>)"),
+            PromptKind::GoTo => write!(f, "Go to (gs://bucket/a/b/, s3://..., az://...)"),
+            PromptKind::SaveSearch(definition) => write!(f, "Save {definition} as"),
+            PromptKind::Bookmark(uri) => write!(f, "Bookmark {uri} as"),
+            PromptKind::Delete(targets) => match targets.as_slice() {
+                [target] => write!(f, "Delete {target}? Type yes to confirm"),
+                targets => write!(f, "Delete {} marked items? Type yes to confirm", targets.len()),
+            },
+            PromptKind::Paste { kind, count: 1 } => write!(f, "{kind} 1 item to"),
+            PromptKind::Paste { kind, count } => write!(f, "{kind} {count} items to"),
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct Prompt {
+    pub config: Config,
+    pub kind: Option<PromptKind>,
+    pub textarea: TextArea<'static>,
+    pub return_focus: Focus,
+}
+
+impl Default for Prompt {
+    fn default() -> Self {
+        Self {
+            config: Config::default(),
+            kind: None,
+            textarea: TextArea::default(),
+            return_focus: Focus::Viewer,
+        }
+    }
+}
+
+impl Prompt {
+    pub fn open(&mut self, kind: PromptKind, initial_text: &str, return_focus: Focus) {
+        self.kind = Some(kind);
+        self.textarea = TextArea::from([initial_text]);
+        self.textarea.move_cursor(tui_textarea::CursorMove::End);
+        self.return_focus = return_focus;
+    }
+
+    fn close(&mut self) -> Focus {
+        self.kind = None;
+        self.return_focus
+    }
+}
+
+impl Component for Prompt {
+    fn name(&self) -> &str {
+        "Prompt"
+    }
+    fn as_any_mut(&mut self) -> &mut dyn std::any::Any {
+        self
+    }
+
+    fn register_config(&mut self, _config: &Config, _focus: Focus) -> Result<(), String> {
+        Ok(())
+    }
+
+    fn draw(
+        &mut self,
+        frame: &mut ratatui::Frame,
+        area: ratatui::prelude::Rect,
+        focus: Focus,
+        config: &Config,
+    ) -> Result<(), String> {
+        let Some(kind) = &self.kind else {
+            return Ok(());
+        };
+        if !matches!(focus, Focus::Prompt) {
+            return Ok(());
+        }
+
+        let [_, row, _] = Layout::vertical([
+            Constraint::Fill(1),
+            Constraint::Length(3),
+            Constraint::Fill(1),
+        ])
+        .areas(area);
+        let [_, prompt, _] = Layout::horizontal([
+            Constraint::Percentage(20),
+            Constraint::Percentage(60),
+            Constraint::Percentage(20),
+        ])
+        .areas(row);
+
+        self.textarea
+            .set_cursor_line_style(Style::default());
+        self.textarea.set_style(config.theme.input);
+        self.textarea.set_block(
+            Block::bordered()
+                .title(kind.to_string())
+                .border_style(config.theme.border),
+        );
+
+        frame.render_widget(Clear, prompt);
+        frame.render_widget(&self.textarea, prompt);
+        Ok(())
+    }
+
+    fn handle_key_event(&mut self, key_event: KeyEvent, focus: Focus) -> Result<Action, Action> {
+        if !matches!(focus, Focus::Prompt) {
+            return Ok(Action::Skip);
+        }
+        let key: Key = key_event.into();
+
+        if key == self.config.key_config.exit {
+            Ok(Action::Quit)
+        } else if key == self.config.key_config.close_component {
+            Ok(Action::ChangeFocus(self.close()))
+        } else if key == self.config.key_config.enter {
+            let input = self.textarea.lines().join("").trim().to_string();
+            match self.kind.clone() {
+                Some(kind) if !input.is_empty() => {
+                    self.close();
+                    Ok(Action::SubmitPrompt(kind, input))
+                }
+                _ => Ok(Action::Nothing),
+            }
+        } else {
+            self.textarea.input(key_event);
+            Ok(Action::Nothing)
+        }
+    }
+
+    fn handle_paste(&mut self, text: &str, focus: Focus) -> Result<Action, Action> {
+        if !matches!(focus, Focus::Prompt) {
+            return Ok(Action::Skip);
+        }
+        self.textarea.insert_str(text);
+        Ok(Action::Nothing)
+    }
+}
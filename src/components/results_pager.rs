@@ -1,4 +1,12 @@
 use std::io::BufRead;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+/// Page size of every new pager, set once at startup from `--results-per-page`
+static RESULTS_PER_PAGE: AtomicUsize = AtomicUsize::new(20);
+
+pub fn set_results_per_page(results_per_page: usize) {
+    RESULTS_PER_PAGE.store(results_per_page, Ordering::Relaxed);
+}
 
 #[derive(Debug, Clone)]
 pub struct ResultsPager {
@@ -13,7 +21,7 @@ pub struct ResultsPager {
 impl Default for ResultsPager {
     fn default() -> Self {
         Self {
-            results_per_page: 20,
+            results_per_page: RESULTS_PER_PAGE.load(Ordering::Relaxed),
             page_idx: 0,
             num_pages: 0,
             total_results: 0,
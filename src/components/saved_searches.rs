@@ -0,0 +1,160 @@
+use std::result::Result;
+
+use crossterm::event::KeyEvent;
+use ratatui::{
+    layout::Constraint,
+    style::{Modifier, Style},
+    widgets::{Block, Clear, Row, Table, TableState},
+};
+
+use crate::{
+    action::Action,
+    app::Focus,
+    config::{
+        saved_searches::{self, Definition, SavedSearch},
+        Config,
+    },
+    key::Key,
+    util,
+};
+
+use super::Component;
+
+/// Picks a saved filter or search to apply to the Viewer
+#[derive(Debug, Default)]
+pub struct SavedSearchPicker {
+    pub config: Config,
+    pub state: TableState,
+    pub saved: Vec<SavedSearch>,
+}
+
+impl SavedSearchPicker {
+    pub fn open(&mut self, saved: Vec<SavedSearch>) {
+        self.state.select(if saved.is_empty() { None } else { Some(0) });
+        self.saved = saved;
+    }
+
+    /// Forgets the selected entry, on disk as well
+    fn remove_selected(&mut self) -> Result<(), Action> {
+        let Some(idx) = self.state.selected().filter(|idx| *idx < self.saved.len()) else {
+            return Ok(());
+        };
+        self.saved.remove(idx);
+        if self.saved.is_empty() {
+            self.state.select(None);
+        } else if idx >= self.saved.len() {
+            self.state.select(Some(self.saved.len() - 1));
+        }
+        saved_searches::store(&self.saved)
+    }
+}
+
+impl Component for SavedSearchPicker {
+    fn name(&self) -> &str {
+        "SavedSearchPicker"
+    }
+    fn as_any_mut(&mut self) -> &mut dyn std::any::Any {
+        self
+    }
+
+    fn register_config(&mut self, _config: &Config, _focus: Focus) -> Result<(), String> {
+        Ok(())
+    }
+
+    fn draw(
+        &mut self,
+        frame: &mut ratatui::Frame,
+        area: ratatui::prelude::Rect,
+        focus: Focus,
+        config: &Config,
+    ) -> Result<(), String> {
+        if !matches!(focus, Focus::SavedSearches) {
+            return Ok(());
+        }
+
+        let panel = util::popup_area(area, 70, 50);
+        let rows: Vec<Row> = self
+            .saved
+            .iter()
+            .map(|saved| {
+                let (kind, text) = match &saved.definition {
+                    Definition::Filter { text, mode, .. } => (format!("filter ({mode})"), text.clone()),
+                    Definition::Search { pattern } => ("search".to_string(), pattern.clone()),
+                };
+                Row::new(vec![saved.name.clone(), kind, text])
+            })
+            .collect();
+        let table = Table::new(
+            rows,
+            [
+                Constraint::Percentage(25),
+                Constraint::Length(20),
+                Constraint::Fill(1),
+            ],
+        )
+        .header(
+            Row::new(vec!["Name", "Kind", "Pattern"])
+                .style(Style::new().add_modifier(Modifier::BOLD)),
+        )
+        .block(
+            Block::bordered()
+                .title(format!("Saved Searches ({})", saved_searches::saved_searches_path().display()))
+                .border_style(config.theme.border),
+        )
+        .style(config.theme.popup)
+        .row_highlight_style(
+            config.theme.highlight,
+        );
+
+        frame.render_widget(Clear, panel);
+        frame.render_stateful_widget(table, panel, &mut self.state);
+        Ok(())
+    }
+
+    fn handle_key_event(&mut self, key_event: KeyEvent, focus: Focus) -> Result<Action, Action> {
+        if !matches!(focus, Focus::SavedSearches) {
+            return Ok(Action::Skip);
+        }
+        let key: Key = key_event.into();
+
+        if key == self.config.key_config.exit {
+            Ok(Action::Quit)
+        } else if [
+            self.config.key_config.close_component,
+            self.config.key_config.quit,
+        ]
+        .iter()
+        .any(|kc| kc == &key)
+        {
+            Ok(Action::ChangeFocus(Focus::Viewer))
+        } else if [
+            self.config.key_config.key_up,
+            self.config.key_config.arrow_up,
+        ]
+        .iter()
+        .any(|kc| kc == &key)
+        {
+            self.state.select_previous();
+            Ok(Action::Nothing)
+        } else if [
+            self.config.key_config.key_down,
+            self.config.key_config.arrow_down,
+        ]
+        .iter()
+        .any(|kc| kc == &key)
+        {
+            self.state.select_next();
+            Ok(Action::Nothing)
+        } else if key == self.config.key_config.delete {
+            self.remove_selected()?;
+            Ok(Action::Nothing)
+        } else if key == self.config.key_config.enter {
+            match self.state.selected().and_then(|idx| self.saved.get(idx)) {
+                Some(saved) => Ok(Action::ApplySaved(saved.definition.clone())),
+                None => Ok(Action::Nothing),
+            }
+        } else {
+            Ok(Action::Nothing)
+        }
+    }
+}
@@ -0,0 +1,204 @@
+use std::result::Result;
+
+use crossterm::event::KeyEvent;
+use ratatui::{
+    layout::Constraint,
+    style::{Modifier, Style},
+    text::{Line, Span},
+    widgets::{Block, Clear, Row, Table, TableState},
+};
+
+use crate::{
+    action::Action,
+    app::Focus,
+    config::{saved_searches::Definition, Config},
+    key::Key,
+    storage::{search::RunningSearch, ObjectEntry},
+    util,
+};
+
+use super::Component;
+
+/// Objects matching a recursive search, filled in while the listing is still running
+#[derive(Debug, Default)]
+pub struct Search {
+    pub config: Config,
+    pub state: TableState,
+    pub search: Option<RunningSearch>,
+    pub matches: Vec<ObjectEntry>,
+    pub scanned: usize,
+    pub done: bool,
+    pub error: Option<String>,
+}
+
+impl Search {
+    pub fn open(&mut self, search: RunningSearch) {
+        self.close();
+        self.search = Some(search);
+    }
+
+    pub fn is_open(&self) -> bool {
+        self.search.is_some()
+    }
+
+    fn close(&mut self) {
+        if let Some(search) = self.search.take() {
+            search.cancel();
+        }
+        self.matches.clear();
+        self.scanned = 0;
+        self.done = false;
+        self.error = None;
+        self.state.select(None);
+    }
+
+    /// Takes what the search found since the last call; whether anything it shows changed
+    fn poll(&mut self) -> bool {
+        let Some(search) = &self.search else {
+            return false;
+        };
+        let progress = search.poll();
+        let changed = !progress.matches.is_empty()
+            || progress.scanned != self.scanned
+            || progress.done != self.done
+            || progress.error != self.error;
+        self.matches.extend(progress.matches);
+        self.scanned = progress.scanned;
+        self.done = progress.done;
+        self.error = progress.error;
+        if self.state.selected().is_none() && !self.matches.is_empty() {
+            self.state.select(Some(0));
+        }
+        changed
+    }
+}
+
+impl Component for Search {
+    fn name(&self) -> &str {
+        "Search"
+    }
+    fn as_any_mut(&mut self) -> &mut dyn std::any::Any {
+        self
+    }
+
+    fn register_config(&mut self, _config: &Config, _focus: Focus) -> Result<(), String> {
+        Ok(())
+    }
+
+    fn tick(&mut self, focus: Focus, _config: &Config) -> bool {
+        // the search goes on in the background, but its results only show in the Search pane
+        self.poll() && matches!(focus, Focus::Search)
+    }
+
+    fn draw(
+        &mut self,
+        frame: &mut ratatui::Frame,
+        area: ratatui::prelude::Rect,
+        focus: Focus,
+        config: &Config,
+    ) -> Result<(), String> {
+        if !matches!(focus, Focus::Search) {
+            return Ok(());
+        }
+        self.poll();
+        let Some(search) = &self.search else {
+            return Ok(());
+        };
+
+        let status = match (&self.error, self.done) {
+            (Some(e), _) => Span::styled(format!(" {} matches, failed: {e} ", self.matches.len()), config.theme.error),
+            (None, true) => format!(" {} matches in {} objects ", self.matches.len(), self.scanned).into(),
+            (None, false) => Span::styled(format!(" {} matches in {} objects, searching... ", self.matches.len(), self.scanned), config.theme.pending),
+        };
+
+        let panel = util::popup_area(area, 90, 70);
+        let rows: Vec<Row> = self
+            .matches
+            .iter()
+            .map(|entry| {
+                Row::new(vec![
+                    entry.uri.to_string(),
+                    util::human_bytes(entry.size),
+                    entry.updated.clone().unwrap_or_default(),
+                ])
+            })
+            .collect();
+        let table = Table::new(
+            rows,
+            [
+                Constraint::Fill(1),
+                Constraint::Length(12),
+                Constraint::Length(26),
+            ],
+        )
+        .header(
+            Row::new(vec!["Object", "Size", "Updated"])
+                .style(Style::new().add_modifier(Modifier::BOLD)),
+        )
+        .block(
+            Block::bordered()
+                .title(format!("Search {}", search.pattern.text))
+                .title_bottom(Line::from(status))
+                .border_style(config.theme.border),
+        )
+        .style(config.theme.popup)
+        .row_highlight_style(
+            config.theme.highlight,
+        );
+
+        frame.render_widget(Clear, panel);
+        frame.render_stateful_widget(table, panel, &mut self.state);
+        Ok(())
+    }
+
+    fn handle_key_event(&mut self, key_event: KeyEvent, focus: Focus) -> Result<Action, Action> {
+        if !matches!(focus, Focus::Search) {
+            return Ok(Action::Skip);
+        }
+        let key: Key = key_event.into();
+
+        if key == self.config.key_config.exit {
+            Ok(Action::Quit)
+        } else if [
+            self.config.key_config.close_component,
+            self.config.key_config.quit,
+        ]
+        .iter()
+        .any(|kc| kc == &key)
+        {
+            self.close();
+            Ok(Action::ChangeFocus(Focus::Viewer))
+        } else if [
+            self.config.key_config.key_up,
+            self.config.key_config.arrow_up,
+        ]
+        .iter()
+        .any(|kc| kc == &key)
+        {
+            self.state.select_previous();
+            Ok(Action::Nothing)
+        } else if [
+            self.config.key_config.key_down,
+            self.config.key_config.arrow_down,
+        ]
+        .iter()
+        .any(|kc| kc == &key)
+        {
+            self.state.select_next();
+            Ok(Action::Nothing)
+        } else if key == self.config.key_config.save_search {
+            match &self.search {
+                Some(search) => Ok(Action::SaveSearch(Definition::Search { pattern: search.pattern.text.clone() })),
+                None => Ok(Action::Nothing),
+            }
+        } else if key == self.config.key_config.enter {
+            // the search keeps running, searching again from the Viewer comes back to these results
+            match self.state.selected().and_then(|idx| self.matches.get(idx)) {
+                Some(entry) => Ok(Action::Reveal(entry.uri.to_string())),
+                None => Ok(Action::Nothing),
+            }
+        } else {
+            Ok(Action::Nothing)
+        }
+    }
+}
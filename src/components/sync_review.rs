@@ -0,0 +1,250 @@
+use std::result::Result;
+
+use crossterm::event::KeyEvent;
+use ratatui::{
+    layout::Constraint,
+    style::{Modifier, Style},
+    text::{Line, Span},
+    widgets::{Block, Clear, Row, Table, TableState},
+};
+
+use crate::{
+    action::Action,
+    app::Focus,
+    config::{theme::Theme, Config},
+    key::Key,
+    transfer::sync::{SyncAction, SyncCompare, SyncDirection, SyncEntry, SyncRequest},
+    util,
+};
+
+use super::Component;
+
+/// Preview of a sync: what would be added, updated and deleted, before anything runs
+#[derive(Debug, Default)]
+pub struct SyncReview {
+    pub config: Config,
+    pub state: TableState,
+    pub request: Option<SyncRequest>,
+    pub entries: Vec<SyncEntry>,
+    /// The plan for `request` is still being worked out
+    pub planning: bool,
+}
+
+impl SyncReview {
+    /// Shows `request` while its plan is worked out in the background
+    pub fn start(&mut self, request: SyncRequest) {
+        self.request = Some(request);
+        self.entries.clear();
+        self.planning = true;
+        self.state.select(None);
+    }
+
+    /// Shows the plan, unless the review was closed or asked for another plan in the meantime
+    pub fn open(&mut self, request: SyncRequest, entries: Vec<SyncEntry>) {
+        if self.request.as_ref() != Some(&request) {
+            return;
+        }
+        self.entries = entries;
+        self.planning = false;
+        self.state.select(if self.entries.is_empty() { None } else { Some(0) });
+    }
+
+    /// Whether the review is still waiting for the plan of `request`
+    pub fn is_planning(&self, request: &SyncRequest) -> bool {
+        self.planning && self.request.as_ref() == Some(request)
+    }
+
+    pub fn close(&mut self) {
+        self.request = None;
+        self.entries.clear();
+        self.planning = false;
+    }
+
+    fn replan(&self, change: impl FnOnce(&mut SyncRequest)) -> Action {
+        match &self.request {
+            Some(request) => {
+                let mut request = request.clone();
+                change(&mut request);
+                Action::PlanSync(Box::new(request))
+            }
+            None => Action::Nothing,
+        }
+    }
+
+    fn count(&self, action: SyncAction) -> usize {
+        self.entries
+            .iter()
+            .filter(|entry| entry.action == action && !entry.excluded)
+            .count()
+    }
+
+    fn row(entry: &SyncEntry, theme: &Theme) -> Row<'static> {
+        let included = if entry.excluded { "[ ]" } else { "[x]" };
+        let action_style = match entry.action {
+            SyncAction::Add => theme.success,
+            SyncAction::Update => theme.pending,
+            SyncAction::Delete => theme.error,
+        };
+        let row = Row::new(vec![
+            Span::raw(included),
+            Span::styled(entry.action.to_string(), action_style),
+            Span::raw(entry.relative.clone()),
+            Span::raw(util::human_bytes(entry.size)),
+        ]);
+        if entry.excluded {
+            row.style(theme.muted)
+        } else {
+            row
+        }
+    }
+}
+
+impl Component for SyncReview {
+    fn name(&self) -> &str {
+        "SyncReview"
+    }
+    fn as_any_mut(&mut self) -> &mut dyn std::any::Any {
+        self
+    }
+
+    fn register_config(&mut self, _config: &Config, _focus: Focus) -> Result<(), String> {
+        Ok(())
+    }
+
+    fn draw(
+        &mut self,
+        frame: &mut ratatui::Frame,
+        area: ratatui::prelude::Rect,
+        focus: Focus,
+        config: &Config,
+    ) -> Result<(), String> {
+        let Some(request) = &self.request else {
+            return Ok(());
+        };
+        if !matches!(focus, Focus::SyncReview) {
+            return Ok(());
+        }
+
+        let title = format!(
+            "Sync {} {} {} | compare: {} | delete extraneous: {}",
+            request.local.display(),
+            match request.direction {
+                SyncDirection::Up => "->",
+                SyncDirection::Down => "<-",
+            },
+            request.remote,
+            request.compare,
+            if request.delete_extraneous { "on" } else { "off" },
+        );
+        let summary = if self.planning {
+            " planning... ".to_string()
+        } else {
+            format!(
+                " {} to add, {} to update, {} to delete ",
+                self.count(SyncAction::Add),
+                self.count(SyncAction::Update),
+                self.count(SyncAction::Delete),
+            )
+        };
+
+        let panel = util::popup_area(area, 90, 70);
+        let rows: Vec<Row> = self.entries.iter().map(|entry| Self::row(entry, &config.theme)).collect();
+        let table = Table::new(
+            rows,
+            [
+                Constraint::Length(3),
+                Constraint::Length(6),
+                Constraint::Fill(1),
+                Constraint::Length(12),
+            ],
+        )
+        .header(
+            Row::new(vec!["", "Action", "Path", "Size"])
+                .style(Style::new().add_modifier(Modifier::BOLD)),
+        )
+        .block(
+            Block::bordered()
+                .title(title)
+                .title_bottom(Line::from(summary))
+                .border_style(config.theme.border),
+        )
+        .style(config.theme.popup)
+        .row_highlight_style(
+            config.theme.highlight,
+        );
+
+        frame.render_widget(Clear, panel);
+        frame.render_stateful_widget(table, panel, &mut self.state);
+        Ok(())
+    }
+
+    fn handle_key_event(&mut self, key_event: KeyEvent, focus: Focus) -> Result<Action, Action> {
+        if !matches!(focus, Focus::SyncReview) {
+            return Ok(Action::Skip);
+        }
+        let key: Key = key_event.into();
+
+        if key == self.config.key_config.exit {
+            Ok(Action::Quit)
+        } else if [
+            self.config.key_config.close_component,
+            self.config.key_config.quit,
+        ]
+        .iter()
+        .any(|kc| kc == &key)
+        {
+            self.close();
+            Ok(Action::ChangeFocus(Focus::Viewer))
+        } else if [
+            self.config.key_config.key_up,
+            self.config.key_config.arrow_up,
+        ]
+        .iter()
+        .any(|kc| kc == &key)
+        {
+            self.state.select_previous();
+            Ok(Action::Nothing)
+        } else if [
+            self.config.key_config.key_down,
+            self.config.key_config.arrow_down,
+        ]
+        .iter()
+        .any(|kc| kc == &key)
+        {
+            self.state.select_next();
+            Ok(Action::Nothing)
+        } else if key == self.config.key_config.toggle_selected {
+            if let Some(entry) = self.state.selected().and_then(|idx| self.entries.get_mut(idx)) {
+                entry.excluded = !entry.excluded;
+            }
+            Ok(Action::Nothing)
+        } else if key == self.config.key_config.sync_reverse {
+            Ok(self.replan(|request| {
+                request.direction = match request.direction {
+                    SyncDirection::Up => SyncDirection::Down,
+                    SyncDirection::Down => SyncDirection::Up,
+                }
+            }))
+        } else if key == self.config.key_config.sync_compare {
+            Ok(self.replan(|request| {
+                request.compare = match request.compare {
+                    SyncCompare::SizeMtime => SyncCompare::Checksum,
+                    SyncCompare::Checksum => SyncCompare::SizeMtime,
+                }
+            }))
+        } else if key == self.config.key_config.sync_delete {
+            Ok(self.replan(|request| request.delete_extraneous = !request.delete_extraneous))
+        } else if key == self.config.key_config.enter {
+            match self.request.clone() {
+                Some(request) if !self.planning && self.entries.iter().any(|entry| !entry.excluded) => {
+                    let entries = std::mem::take(&mut self.entries);
+                    self.close();
+                    Ok(Action::RunSync(Box::new(request), entries))
+                }
+                _ => Ok(Action::Nothing),
+            }
+        } else {
+            Ok(Action::Nothing)
+        }
+    }
+}
@@ -0,0 +1,209 @@
+use std::result::Result;
+
+use crossterm::event::KeyEvent;
+use ratatui::{
+    layout::Constraint,
+    style::{Modifier, Style, Stylize},
+    text::Span,
+    widgets::{Block, Clear, Row, Table, TableState},
+};
+
+use crate::{
+    action::Action,
+    app::Focus,
+    config::{theme::Theme, Config},
+    key::Key,
+    transfer::{checksum::Verification, TransferJob, TransferState},
+    util,
+};
+
+use super::Component;
+
+/// Panel listing every queued, running and finished transfer
+#[derive(Debug, Default)]
+pub struct Transfers {
+    pub config: Config,
+    pub state: TableState,
+    pub jobs: Vec<TransferJob>,
+}
+
+impl Transfers {
+    fn selected_job_id(&self) -> Option<usize> {
+        self.state
+            .selected()
+            .and_then(|idx| self.jobs.get(idx))
+            .map(|job| job.id)
+    }
+
+    fn row(job: &TransferJob, theme: &Theme) -> Row<'static> {
+        let progress = match job.total_bytes {
+            Some(total) if total > 0 => format!(
+                "{} / {} ({:.0}%)",
+                util::human_bytes(job.bytes_transferred),
+                util::human_bytes(total),
+                job.bytes_transferred as f64 / total as f64 * 100.0
+            ),
+            _ => util::human_bytes(job.bytes_transferred),
+        };
+        let rate = match job.rate() {
+            Some(rate) => format!("{}/s", util::human_bytes(rate as u64)),
+            None => "-".to_string(),
+        };
+        let eta = match job.eta() {
+            Some(eta) => format!("{}s", eta.as_secs()),
+            None => "-".to_string(),
+        };
+        let state_style = match job.state {
+            TransferState::Queued => theme.secondary,
+            TransferState::Running => theme.pending,
+            TransferState::Done => theme.success,
+            TransferState::Failed(_) => theme.error,
+            TransferState::Cancelled => theme.muted,
+            TransferState::Paused => theme.secondary.italic(),
+        };
+
+        let check = match &job.verification {
+            Some(verification @ Verification::Verified(_)) => Span::styled(verification.to_string(), theme.success),
+            Some(verification @ Verification::Mismatch(_)) => Span::styled(verification.to_string(), theme.error.bold()),
+            Some(verification @ Verification::Unverifiable(_)) => Span::styled(verification.to_string(), theme.muted),
+            None => Span::raw("-"),
+        };
+
+        Row::new(vec![
+            Span::raw(job.kind.to_string()),
+            Span::raw(job.source.to_string()),
+            Span::raw(job.destination.to_string()),
+            Span::raw(progress),
+            Span::raw(rate),
+            Span::raw(eta),
+            Span::styled(job.state.to_string(), state_style),
+            check,
+        ])
+    }
+}
+
+impl Component for Transfers {
+    fn name(&self) -> &str {
+        "Transfers"
+    }
+    fn as_any_mut(&mut self) -> &mut dyn std::any::Any {
+        self
+    }
+
+    fn register_config(&mut self, _config: &Config, _focus: Focus) -> Result<(), String> {
+        Ok(())
+    }
+
+    /// Whether a job was queued, changed state or made progress since the last tick; the footer
+    /// counts running transfers, so this matters in every pane
+    fn tick(&mut self, _focus: Focus, config: &Config) -> bool {
+        let jobs = config.transfers.snapshot();
+        let changed = jobs.len() != self.jobs.len()
+            || jobs.iter().zip(&self.jobs).any(|(job, seen)| {
+                job.state != seen.state
+                    || job.bytes_transferred != seen.bytes_transferred
+                    || job.verification != seen.verification
+            });
+        self.jobs = jobs;
+        changed
+    }
+
+    fn draw(
+        &mut self,
+        frame: &mut ratatui::Frame,
+        area: ratatui::prelude::Rect,
+        focus: Focus,
+        config: &Config,
+    ) -> Result<(), String> {
+        if !matches!(focus, Focus::Transfers) {
+            return Ok(());
+        }
+        self.jobs = config.transfers.snapshot();
+        if self.state.selected().is_none() && !self.jobs.is_empty() {
+            self.state.select(Some(0));
+        }
+
+        let panel = util::popup_area(area, 90, 70);
+        let rows: Vec<Row> = self.jobs.iter().map(|job| Self::row(job, &config.theme)).collect();
+        let table = Table::new(
+            rows,
+            [
+                Constraint::Length(8),
+                Constraint::Fill(2),
+                Constraint::Fill(2),
+                Constraint::Length(28),
+                Constraint::Length(12),
+                Constraint::Length(7),
+                Constraint::Fill(1),
+                Constraint::Fill(1),
+            ],
+        )
+        .header(
+            Row::new(vec!["Kind", "Source", "Destination", "Progress", "Rate", "ETA", "State", "Checksum"])
+                .style(Style::new().add_modifier(Modifier::BOLD)),
+        )
+        .block(
+            Block::bordered()
+                .title("Transfers")
+                .border_style(config.theme.border),
+        )
+        .style(config.theme.popup)
+        .row_highlight_style(
+            config.theme.highlight,
+        );
+
+        frame.render_widget(Clear, panel);
+        frame.render_stateful_widget(table, panel, &mut self.state);
+        Ok(())
+    }
+
+    fn handle_key_event(&mut self, key_event: KeyEvent, focus: Focus) -> Result<Action, Action> {
+        if !matches!(focus, Focus::Transfers) {
+            return Ok(Action::Skip);
+        }
+        let key: Key = key_event.into();
+
+        if key == self.config.key_config.exit {
+            Ok(Action::Quit)
+        } else if [
+            self.config.key_config.close_component,
+            self.config.key_config.transfers,
+            self.config.key_config.quit,
+        ]
+        .iter()
+        .any(|kc| kc == &key)
+        {
+            Ok(Action::ChangeFocus(Focus::Viewer))
+        } else if [
+            self.config.key_config.key_up,
+            self.config.key_config.arrow_up,
+        ]
+        .iter()
+        .any(|kc| kc == &key)
+        {
+            self.state.select_previous();
+            Ok(Action::Nothing)
+        } else if [
+            self.config.key_config.key_down,
+            self.config.key_config.arrow_down,
+        ]
+        .iter()
+        .any(|kc| kc == &key)
+        {
+            self.state.select_next();
+            Ok(Action::Nothing)
+        } else if key == self.config.key_config.retry {
+            match self.selected_job_id() {
+                Some(id) => Ok(Action::RetryTransfer(id)),
+                None => Ok(Action::Nothing),
+            }
+        } else if key == self.config.key_config.cancel {
+            match self.selected_job_id() {
+                Some(id) => Ok(Action::CancelTransfer(id)),
+                None => Ok(Action::Nothing),
+            }
+        } else {
+            Ok(Action::Nothing)
+        }
+    }
+}
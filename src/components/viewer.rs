@@ -1,10 +1,12 @@
+use std::collections::{HashMap, HashSet};
+use std::fmt;
 use std::io::BufRead;
 use std::result::Result;
 
 use crossterm::event::{KeyEvent, MouseEventKind};
 use ego_tree::{NodeId, NodeRef, Tree as ETree};
 use ratatui::layout::{Constraint, Layout, Position};
-use ratatui::style::{Color, Modifier, Style, Stylize};
+use ratatui::style::Style;
 use ratatui::text::Span;
 use ratatui::widgets::Clear;
 use ratatui::{
@@ -18,14 +20,44 @@ use tui_tree_widget::{Tree, TreeItem, TreeState};
 use crate::action::Action;
 use crate::app::Focus;
 use crate::config::Config;
+use crate::config::theme::Theme;
 use crate::config::cloud_provider_config::cloud_provider_connection::CloudConnection;
 use crate::key::Key;
+use crate::storage::{cloud_uri::CloudUri, expression::FilterExpression, ObjectMetadata};
 use crate::util;
 
-use super::filter::{Filter, ViewerFilter};
+use super::filter::{Filter, MatchOptions, ViewerFilter};
+use super::history::NavigationHistory;
 use super::results_pager::ResultsPager;
 use super::{Component, TreeComponent};
 
+/// Details of each object the Viewer can show after its name
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Column {
+    Size,
+    Modified,
+}
+
+impl Column {
+    pub const ALL: [Column; 2] = [Column::Size, Column::Modified];
+
+    fn value(self, metadata: &ObjectMetadata) -> String {
+        match self {
+            Column::Size => util::human_bytes(metadata.size),
+            Column::Modified => metadata.updated.clone().unwrap_or_else(|| "-".to_string()),
+        }
+    }
+}
+
+impl fmt::Display for Column {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Column::Size => write!(f, "size"),
+            Column::Modified => write!(f, "modified"),
+        }
+    }
+}
+
 #[derive(Debug)]
 pub struct Viewer {
     pub config: Config,
@@ -35,6 +67,22 @@ pub struct Viewer {
     pub results_pager: ResultsPager,
     pub pagers: Vec<ResultsPager>,
     pub filter: Box<dyn Filter>,
+    /// Bumped whenever nodes are added to or removed from the tree, so the filter can tell
+    /// whether its matcher has seen all of them
+    generation: u64,
+    /// Metadata of the objects under each listed prefix, fetched the first time a filter or a column asks for it
+    pub metadata: HashMap<String, Vec<(String, ObjectMetadata)>>,
+    /// Columns shown after each object's name, in the order they were turned on
+    pub columns: Vec<Column>,
+    /// Text of the shown columns for each object whose metadata is known
+    details: HashMap<String, String>,
+    /// Prefixes whose metadata was asked for, so each is fetched once
+    requested: HashSet<String>,
+    /// Filter text whose metadata conditions wait on prefixes still being fetched, run again as each arrives
+    waiting_filter: Option<Vec<String>>,
+    /// Marked objects and prefixes, which batch actions apply to instead of the selection
+    pub marks: HashSet<String>,
+    pub history: NavigationHistory,
 }
 
 impl Default for Viewer {
@@ -47,6 +95,14 @@ impl Default for Viewer {
             results_pager: ResultsPager::default(),
             pagers: Vec::new(),
             filter: Box::new(ViewerFilter::default()),
+            generation: 0,
+            metadata: HashMap::new(),
+            columns: Vec::new(),
+            details: HashMap::new(),
+            requested: HashSet::new(),
+            waiting_filter: None,
+            marks: HashSet::new(),
+            history: NavigationHistory::default(),
         }
     }
 }
@@ -55,14 +111,19 @@ impl Viewer {
     fn create_nodes(&mut self, config: &Config, node_id: NodeId) -> Result<(), Action> {
             let ac = config.cloud_provider_config.active_cloud_connection.clone().expect("error viewer");
             if let CloudConnection::Gcs(c) = ac {
+                 let mut listed = vec![];
                  c.data.expect("error getting data").lines().for_each(|result| {
                      let res = result.expect("error getting result");
 
                      match self.tree.get_mut(node_id) {
                          None => (),
-                         Some(mut tree) => {tree.append(res);}
+                         Some(mut tree) => {
+                             tree.append(res.clone());
+                             listed.push(res);
+                         }
                      }
                 });
+                self.push_to_filter(listed);
                 Ok(())
                 
             } else {
@@ -96,6 +157,300 @@ impl Viewer {
             // }
         
     }
+    /// Hands newly listed nodes to the filter's fuzzy matcher, so filtering does not have to
+    fn push_to_filter(&mut self, values: Vec<String>) {
+        let matcher = self.filter.get_fuzzy_matcher();
+        let in_step = matcher.generation() == Some(self.generation);
+        matcher.push(values.into_iter().filter(|value| value.contains('/')));
+        self.generation += 1;
+        if in_step {
+            matcher.caught_up(self.generation);
+        }
+    }
+
+    /// Starts the filter's fuzzy matcher over from the nodes left in the tree, after some were removed
+    fn repush_to_filter(&mut self) {
+        let values = self.filterable_nodes();
+        self.generation += 1;
+        let matcher = self.filter.get_fuzzy_matcher();
+        matcher.restart();
+        matcher.push(values);
+        matcher.caught_up(self.generation);
+    }
+
+    /// What the filter matches against: every listed bucket, prefix and object
+    fn filterable_nodes(&self) -> Vec<String> {
+        self.tree
+            .nodes()
+            .filter(|n| n.value().contains('/'))
+            .map(|n| n.value().to_string())
+            .collect()
+    }
+
+    /// Closest listed node at or above `prefix`, i.e. the one whose children show the change
+    pub fn listed_ancestor(&self, prefix: &CloudUri) -> Option<String> {
+        let mut candidate = prefix.clone();
+        loop {
+            let value = candidate.to_string();
+            match self.tree.nodes().find(|node| node.value() == &value) {
+                Some(node) if node.has_children() => return Some(value),
+                Some(_) => return None,
+                None if candidate.path.is_empty() => return None,
+                None => candidate = candidate.parent(),
+            }
+        }
+    }
+
+    /// Replaces the children of `value` with the listing currently held by the active connection
+    pub fn refresh_node(&mut self, config: &Config, value: &str) -> Result<(), Action> {
+        let Some(node_id) = self.tree.nodes().find(|node| node.value() == value).map(|node| node.id()) else {
+            return Ok(());
+        };
+
+        // ego_tree keeps detached nodes around as extra roots, so copy the tree without them
+        let mut pruned = ETree::new(self.tree.root().value().clone());
+        let root_id = pruned.root().id();
+        copy_children(self.tree.root(), &mut pruned, root_id, node_id);
+        self.tree = pruned;
+
+        // the old children are gone from the tree, so the matcher starts over from what is left
+        self.repush_to_filter();
+        if let Some(node_id) = self.tree.nodes().find(|node| node.value() == value).map(|node| node.id()) {
+            self.create_nodes(config, node_id)?;
+        }
+        self.metadata.remove(value);
+        self.requested.remove(value);
+        self.items = util::make_tree_items(self.tree.nodes(), &mut self.results_pager, Focus::Viewer, &self.marks, &self.details, &self.config.theme);
+        Ok(())
+    }
+
+    /// Listed objects whose fetched metadata satisfies the conditions of `expression`, and whether
+    /// every listed prefix has been fetched; the rest is fetched in the background
+    fn matching_metadata(&self, expression: &FilterExpression) -> (Vec<String>, bool) {
+        let complete = self.listed_prefixes().iter().all(|prefix| self.metadata.contains_key(prefix));
+        let listed: HashSet<&String> = self.tree.nodes().map(|node| node.value()).collect();
+        let matching = self
+            .metadata
+            .values()
+            .flatten()
+            .filter(|(uri, metadata)| listed.contains(uri) && expression.matches(metadata))
+            .map(|(uri, _)| uri.clone())
+            .collect();
+        (matching, complete)
+    }
+
+    /// Buckets and prefixes whose listing is in the tree
+    fn listed_prefixes(&self) -> Vec<String> {
+        self.tree
+            .nodes()
+            .filter(|node| node.has_children() && node.value().contains("://") && node.value().ends_with('/'))
+            .map(|node| node.value().to_string())
+            .collect()
+    }
+
+    /// Shows `column` after each object's name, or hides it if it is shown
+    pub fn toggle_column(&mut self, column: Column) {
+        match self.columns.iter().position(|shown| *shown == column) {
+            Some(position) => {
+                self.columns.remove(position);
+            }
+            None => self.columns.push(column),
+        }
+        self.update_details();
+    }
+
+    /// Listed prefixes whose metadata the shown columns or a waiting filter still need, each handed out only once
+    pub fn take_unfetched_prefixes(&mut self) -> Vec<String> {
+        if self.columns.is_empty() && self.waiting_filter.is_none() {
+            return vec![];
+        }
+        let prefixes: Vec<String> = self
+            .listed_prefixes()
+            .into_iter()
+            .filter(|prefix| !self.metadata.contains_key(prefix) && !self.requested.contains(prefix))
+            .collect();
+        self.requested.extend(prefixes.iter().cloned());
+        prefixes
+    }
+
+    /// Metadata of the objects under `prefix`, fetched in the background for the columns or the filter
+    pub fn add_metadata(&mut self, prefix: String, listing: Vec<(String, ObjectMetadata)>) -> Result<(), Action> {
+        self.metadata.insert(prefix, listing);
+        self.update_details();
+        if let Some(txt) = self.waiting_filter.clone() {
+            self.run_filter(txt)?;
+        }
+        Ok(())
+    }
+
+    fn update_details(&mut self) {
+        self.details = match self.columns.is_empty() {
+            true => HashMap::new(),
+            false => self
+                .metadata
+                .values()
+                .flatten()
+                .map(|(uri, metadata)| {
+                    let values: Vec<String> = self.columns.iter().map(|column| column.value(metadata)).collect();
+                    (uri.clone(), values.join("  "))
+                })
+                .collect(),
+        };
+        self.remake_items();
+    }
+
+    /// Identifiers from the root down to the node showing `value`, as the tree widget addresses it
+    pub fn tree_path(&self, value: &str) -> Option<Vec<String>> {
+        let node = self.tree.nodes().find(|node| node.value() == value)?;
+        let mut path: Vec<String> = node.ancestors().map(|ancestor| ancestor.value().clone()).collect();
+        path.reverse();
+        path.push(value.to_string());
+        Some(path)
+    }
+
+    /// Whether the node showing `value` has been listed
+    pub fn is_listed(&self, value: &str) -> bool {
+        self.tree.nodes().any(|node| node.value() == value && node.has_children())
+    }
+
+    /// Opens every node above `value` and selects it
+    pub fn reveal(&mut self, value: &str) -> Result<(), Action> {
+        let Some(path) = self.tree_path(value) else {
+            return Err(Action::Error(format!("{value} is not in the Viewer")));
+        };
+        for depth in 1..path.len() {
+            self.state.open(path[..depth].to_vec());
+        }
+        self.state.select(path);
+        Ok(())
+    }
+
+    /// Opens the node showing `value`, so its listing is visible
+    pub fn expand(&mut self, value: &str) {
+        if let Some(path) = self.tree_path(value) {
+            self.state.open(path);
+        }
+    }
+
+    /// Open prefixes, parents before their children and the paged listing last, with its page
+    pub fn open_prefixes(&self) -> (Vec<String>, usize) {
+        let mut opened: Vec<&Vec<String>> = self.state.opened().iter().collect();
+        opened.sort_by_key(|path| path.len());
+        let paged = self.results_pager.paged_item.last();
+        let mut expanded: Vec<String> = opened
+            .into_iter()
+            .filter_map(|path| path.last())
+            .filter(|value| value.contains("://") && value.ends_with('/') && Some(*value) != paged)
+            .cloned()
+            .collect();
+        let page = match paged {
+            Some(paged) if paged.contains("://") && self.state.opened().contains(&self.results_pager.paged_item) => {
+                expanded.push(paged.clone());
+                self.results_pager.page_idx
+            }
+            _ => 0,
+        };
+        (expanded, page)
+    }
+
+    /// Turns the paged listing to `page` if it still has that many
+    pub fn restore_page(&mut self, page: usize) {
+        if page < self.results_pager.num_pages {
+            self.results_pager.page_idx = page;
+            self.remake_items();
+        }
+    }
+
+    /// Marked items in listing order, leaving out any that are no longer listed
+    pub fn marked(&self) -> Vec<String> {
+        self.tree
+            .nodes()
+            .filter(|node| self.marks.contains(node.value()))
+            .map(|node| node.value().to_string())
+            .collect()
+    }
+
+    pub fn clear_marks(&mut self) {
+        self.marks.clear();
+        self.remake_items();
+    }
+
+    fn remake_items(&mut self) {
+        self.items = util::make_tree_items(self.tree.nodes(), &mut self.results_pager, Focus::Viewer, &self.marks, &self.details, &self.config.theme);
+    }
+
+    /// Marks or unmarks the selected item and moves on to the next one
+    fn toggle_mark(&mut self) {
+        let Some(selected) = self.state.selected().last().filter(|value| value.contains("://")).cloned() else {
+            return;
+        };
+        if !self.marks.remove(&selected) {
+            self.marks.insert(selected);
+        }
+        self.remake_items();
+        self.state.key_down();
+    }
+
+    /// The listing the selection belongs to: the children of a listed prefix, otherwise its siblings
+    fn selected_listing(&self, children_of_prefix: bool) -> Vec<String> {
+        let Some(selected) = self.state.selected().last() else {
+            return vec![];
+        };
+        let Some(node) = self.tree.nodes().find(|node| node.value() == selected) else {
+            return vec![];
+        };
+        let listing = match node.parent() {
+            _ if children_of_prefix && node.has_children() => node,
+            Some(parent) => parent,
+            None => return vec![],
+        };
+        listing
+            .children()
+            .map(|child| child.value().to_string())
+            .filter(|value| value.contains("://"))
+            .collect()
+    }
+
+    fn mark_children(&mut self) {
+        self.marks.extend(self.selected_listing(true));
+        self.remake_items();
+    }
+
+    /// Flips every mark in the directory the selection is in
+    fn invert_marks(&mut self) {
+        for value in self.selected_listing(false) {
+            if !self.marks.remove(&value) {
+                self.marks.insert(value);
+            }
+        }
+        self.remake_items();
+    }
+
+    /// Filters the listed nodes by `txt`, narrowed down by its metadata conditions first. Those only
+    /// see the metadata fetched so far; the filter runs again as the rest arrives.
+    fn run_filter(&mut self, txt: Vec<String>) -> Result<Action, Action> {
+        let narrowed = match self.filter.parse_expression(&txt) {
+            Ok(expression) if !expression.predicates.is_empty() => {
+                let (matching, complete) = self.matching_metadata(&expression);
+                self.waiting_filter = (!complete).then(|| txt.clone());
+                Some(matching)
+            }
+            _ => {
+                self.waiting_filter = None;
+                None
+            }
+        };
+
+        self.filter.engage_filter(txt, self.generation, self.filterable_nodes(), narrowed)
+    }
+
+    /// Opens the filter with a saved text and mode and shows what it matches
+    pub fn apply_filter(&mut self, text: &str, options: MatchOptions) -> Result<(), Action> {
+        self.filter.set_query(text, options);
+        self.run_filter(vec![text.to_string()])?;
+        Ok(())
+    }
+
     pub fn increase_results_page(&mut self) -> Option<()> {
         // only increase page idx if we are on a page less than the number of pages
         if self.results_pager.page_idx + 1 < self.results_pager.num_pages {
@@ -129,6 +484,16 @@ impl Component for Viewer {
         self
     }
 
+    fn tick(&mut self, _focus: Focus, _config: &Config) -> bool {
+        self.filter.poll_matches()
+    }
+
+    fn set_theme(&mut self, theme: Theme) {
+        self.config.theme = theme;
+        self.filter.set_theme(theme);
+        self.remake_items();
+    }
+
     fn register_config(&mut self, config: &Config, focus: Focus) -> Result<(), String> {
         if config.app_selection.is_empty() {
             return Ok(())
@@ -150,6 +515,7 @@ impl Component for Viewer {
                 };
 
                 self.tree = ETree::new(active_config);
+                self.repush_to_filter();
 
                 let mut items = vec![];
                     // let nodes = tree.nodes();
@@ -170,7 +536,7 @@ impl Component for Viewer {
                         self.create_nodes(config, nid).expect("Error Creating Nodes");
 
                         self.items =
-                            util::make_tree_items(self.tree.nodes(), &mut self.results_pager, Focus::Connections);
+                            util::make_tree_items(self.tree.nodes(), &mut self.results_pager, Focus::Connections, &self.marks, &self.details, &self.config.theme);
 
                         self.state.open(config.app_selection.to_vec());
                 
@@ -183,7 +549,7 @@ impl Component for Viewer {
                         let mut ti = TreeItem::new(val.clone(), val.clone(), vec![])
                             .expect("error creating nodes under parent");
 
-                        util::add_children(node, &mut ti, &mut results_pager.clone(), focus);
+                        util::add_children(node, &mut ti, &mut results_pager.clone(), focus, &self.marks, &self.details, &self.config.theme);
                         items.push(ti);
                     });
             
@@ -207,7 +573,7 @@ impl Component for Viewer {
 
 
                         self.items =
-                            util::make_tree_items(self.tree.nodes(), &mut self.results_pager, Focus::Connections);
+                            util::make_tree_items(self.tree.nodes(), &mut self.results_pager, Focus::Connections, &self.marks, &self.details, &self.config.theme);
 
                         self.state.open(config.app_selection.to_vec());
                 
@@ -220,7 +586,7 @@ impl Component for Viewer {
                         let mut ti = TreeItem::new(val.clone(), val.clone(), vec![])
                             .expect("error creating nodes under parent");
 
-                        util::add_children(node, &mut ti, &mut results_pager.clone(), focus);
+                        util::add_children(node, &mut ti, &mut results_pager.clone(), focus, &self.marks, &self.details, &self.config.theme);
                         items.push(ti);
                     });
                 self.items = items;
@@ -319,9 +685,20 @@ impl Component for Viewer {
                 } else if key == self.config.key_config.select_first {
                     self.state.select_first();
                     Ok(Action::Nothing)
-                } else if key == self.config.key_config.toggle_selected {
-                    self.state.toggle_selected();
+                } else if key == self.config.key_config.mark {
+                    self.toggle_mark();
                     Ok(Action::Nothing)
+                } else if key == self.config.key_config.mark_children {
+                    self.mark_children();
+                    Ok(Action::Nothing)
+                } else if key == self.config.key_config.invert_marks {
+                    self.invert_marks();
+                    Ok(Action::Nothing)
+                } else if key == self.config.key_config.clear_marks {
+                    self.clear_marks();
+                    Ok(Action::Nothing)
+                } else if key == self.config.key_config.delete_object {
+                    Ok(Action::Delete(self.state.selected().to_vec()))
                 } else if key == self.config.key_config.list_item {
                     let selected = self.state.selected().to_vec();
                     Ok(Action::ViewerList(selected))
@@ -333,15 +710,55 @@ impl Component for Viewer {
                 } else if key == self.config.key_config.next_page {
                     self.increase_results_page();
                     self.items =
-                        util::make_tree_items(self.tree.nodes(), &mut self.results_pager, focus);
+                        util::make_tree_items(self.tree.nodes(), &mut self.results_pager, focus, &self.marks, &self.details, &self.config.theme);
                     self.state.select(self.results_pager.paged_item.clone());
                     Ok(Action::Nothing)
                 } else if key == self.config.key_config.previous_page {
                     self.decrease_results_page();
                     self.items =
-                        util::make_tree_items(self.tree.nodes(), &mut self.results_pager, focus);
+                        util::make_tree_items(self.tree.nodes(), &mut self.results_pager, focus, &self.marks, &self.details, &self.config.theme);
                     self.state.select(self.results_pager.paged_item.clone());
                     Ok(Action::Nothing)
+                } else if key == self.config.key_config.download {
+                    Ok(Action::Download(self.state.selected().to_vec()))
+                } else if key == self.config.key_config.upload {
+                    Ok(Action::Upload(self.state.selected().to_vec()))
+                } else if key == self.config.key_config.verify {
+                    Ok(Action::Verify(self.state.selected().to_vec()))
+                } else if key == self.config.key_config.yank {
+                    Ok(Action::Yank(self.state.selected().to_vec()))
+                } else if key == self.config.key_config.cut {
+                    Ok(Action::Cut(self.state.selected().to_vec()))
+                } else if key == self.config.key_config.paste {
+                    Ok(Action::Paste(self.state.selected().to_vec()))
+                } else if key == self.config.key_config.sync {
+                    Ok(Action::Sync(self.state.selected().to_vec()))
+                } else if key == self.config.key_config.compare {
+                    Ok(Action::Compare(self.state.selected().to_vec()))
+                } else if key == self.config.key_config.search {
+                    Ok(Action::Search(self.state.selected().to_vec()))
+                } else if key == self.config.key_config.go_to {
+                    Ok(Action::GoTo(self.state.selected().to_vec()))
+                } else if key == self.config.key_config.history_back {
+                    match self.history.back() {
+                        Some(value) => Ok(Action::Navigate(value)),
+                        None => Ok(Action::Nothing),
+                    }
+                } else if key == self.config.key_config.history_forward {
+                    match self.history.forward() {
+                        Some(value) => Ok(Action::Navigate(value)),
+                        None => Ok(Action::Nothing),
+                    }
+                } else if key == self.config.key_config.saved_searches {
+                    Ok(Action::SavedSearches)
+                } else if key == self.config.key_config.transfers {
+                    Ok(Action::ChangeFocus(Focus::Transfers))
+                } else if key == self.config.key_config.bookmark {
+                    Ok(Action::Bookmark(self.state.selected().to_vec()))
+                } else if key == self.config.key_config.bookmarks {
+                    Ok(Action::Bookmarks)
+                } else if key == self.config.key_config.refresh {
+                    Ok(Action::Refresh(self.state.selected().to_vec()))
                 } else if key == self.config.key_config.filter {
                     // activate filter
                     self.filter.switch_active_status();
@@ -354,16 +771,7 @@ impl Component for Viewer {
             Focus::ViewerFilter => {
                 let action = self.filter.handle_key_event(key_event, focus)?;
                 match action {
-                    Action::Filter(txt) => {
-                        let tree_items = self
-                            .tree
-                            .nodes()
-                            .filter(|n| n.value().contains('/'))
-                            .map(|n| n.value().to_string())
-                            .collect();
-
-                        self.filter.engage_filter(txt, tree_items)
-                    }
+                    Action::Filter(txt) => self.run_filter(txt),
                     _ => Ok(action),
                 }
             }
@@ -374,6 +782,14 @@ impl Component for Viewer {
         }
     }
 
+    fn handle_paste(&mut self, text: &str, focus: Focus) -> Result<Action, Action> {
+        if !matches!(focus, Focus::ViewerFilter) {
+            return Ok(Action::Skip);
+        }
+        let txt = self.filter.paste(text);
+        self.run_filter(txt)
+    }
+
     fn handle_mouse_event(
         &mut self,
         mouse_event: crossterm::event::MouseEvent,
@@ -405,7 +821,7 @@ impl Component for Viewer {
         frame: &mut Frame,
         area: Rect,
         focus: crate::app::Focus,
-        _config: &Config,
+        config: &Config,
     ) -> Result<(), String> {
         let focused = matches!(focus, Focus::Viewer);
         let [content, _] =
@@ -420,16 +836,13 @@ impl Component for Viewer {
                 Block::bordered()
                     .title("Cloud Viewer")
                     .border_style(if focused {
-                        Style::new().blue()
+                        config.theme.border
                     } else {
                         Style::default()
                     }),
             )
             .highlight_style(if focused {
-                Style::new()
-                    .fg(Color::Black)
-                    .bg(Color::LightGreen)
-                    .add_modifier(Modifier::BOLD)
+                config.theme.highlight
             } else {
                 Style::default()
             })
@@ -471,7 +884,7 @@ impl Component for Viewer {
             width: paging_info.len() as u16,
         };
         frame.render_widget(
-            Span::styled(paging_info, Style::new().fg(Color::Black).bg(Color::Gray)),
+            Span::styled(paging_info, config.theme.paging),
             paging_area,
         );
         // }
@@ -499,12 +912,14 @@ impl TreeComponent for Viewer {
                 match is_directory {
                     true => {
                         add_tree_items(data.clone(), &mut self.tree, node_id);
+                        self.push_to_filter(data.lines().map_while(Result::ok).collect());
                     }
                     false => {
                         let root = self.tree.root().value();
                         if root == selection {
                             
                             add_tree_items(data.clone(), &mut self.tree, node_id);
+                            self.push_to_filter(data.lines().map_while(Result::ok).collect());
                         } else {
                             {}
                         }
@@ -520,7 +935,7 @@ impl TreeComponent for Viewer {
                 self.results_pager.init(&data, path_identifier.clone());
                 self.pagers.push(self.results_pager.clone());
                 self.items =
-                    util::make_tree_items(self.tree.nodes(), &mut self.results_pager, focus);
+                    util::make_tree_items(self.tree.nodes(), &mut self.results_pager, focus, &self.marks, &self.details, &self.config.theme);
 
                 self.state.open(path_identifier.clone());
                 self.state.select(path_identifier);
@@ -567,11 +982,7 @@ impl TreeComponent for Viewer {
 
                         // set the page, re-list-items
                         self.results_pager.set_page_idx(new_page_idx);
-                        self.items = util::make_tree_items(
-                            self.tree.nodes(),
-                            &mut self.results_pager,
-                            focus,
-                        );
+                        self.items = util::make_tree_items(self.tree.nodes(), &mut self.results_pager, focus, &self.marks, &self.details, &self.config.theme);
                     }
                 }
                 false => {
@@ -600,11 +1011,7 @@ impl TreeComponent for Viewer {
 
                         // set the page, re-list-items
                         self.results_pager.set_page_idx(new_page_idx);
-                        self.items = util::make_tree_items(
-                            self.tree.nodes(),
-                            &mut self.results_pager,
-                            focus,
-                        );
+                        self.items = util::make_tree_items(self.tree.nodes(), &mut self.results_pager, focus, &self.marks, &self.details, &self.config.theme);
                     }
                 }
             }
@@ -628,3 +1035,17 @@ pub fn add_tree_items(data: Vec<u8>, tree: &mut ETree<String>, node_id: NodeId)
             .append(res);
     });
 }
+
+/// Copies the subtree below `from` into `into` under `parent`, leaving out the children of `skip_below`
+fn copy_children(from: NodeRef<String>, into: &mut ETree<String>, parent: NodeId, skip_below: NodeId) {
+    if from.id() == skip_below {
+        return;
+    }
+    for child in from.children() {
+        let child_id = match into.get_mut(parent) {
+            Some(mut node) => node.append(child.value().clone()).id(),
+            None => return,
+        };
+        copy_children(child, into, child_id, skip_below);
+    }
+}
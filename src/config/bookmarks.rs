@@ -0,0 +1,84 @@
+use std::path::PathBuf;
+
+use serde::{Deserialize, Serialize};
+use tracing::{error, info};
+
+use crate::action::Action;
+use crate::logging::get_config_dir;
+
+const BOOKMARKS_FILE: &str = "bookmarks.toml";
+
+/// A bucket, prefix or object to jump back to, with the connection it was bookmarked from
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Bookmark {
+    pub name: String,
+    pub uri: String,
+    /// The connection that was active, activated again when the bookmark is opened
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub connection: Option<String>,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct Bookmarks {
+    #[serde(default)]
+    bookmarks: Vec<Bookmark>,
+}
+
+pub fn bookmarks_path() -> PathBuf {
+    get_config_dir().join(BOOKMARKS_FILE)
+}
+
+/// Every bookmark, in the order they were added
+pub fn load() -> Vec<Bookmark> {
+    let path = bookmarks_path();
+    let contents = match std::fs::read_to_string(&path) {
+        Ok(contents) => contents,
+        Err(_) => return Vec::new(),
+    };
+    match toml::from_str::<Bookmarks>(&contents) {
+        Ok(store) => store.bookmarks,
+        Err(e) => {
+            error!("Ignoring unreadable bookmarks {}: {e}", path.display());
+            Vec::new()
+        }
+    }
+}
+
+pub fn store(bookmarks: &[Bookmark]) -> Result<(), Action> {
+    let path = bookmarks_path();
+    let contents = toml::to_string(&Bookmarks { bookmarks: bookmarks.to_vec() })
+        .map_err(|e| Action::Error(format!("Could not serialize bookmarks: {e}")))?;
+    if let Some(directory) = path.parent() {
+        std::fs::create_dir_all(directory)
+            .map_err(|e| Action::Error(format!("Could not create {}: {e}", directory.display())))?;
+    }
+    std::fs::write(&path, contents)
+        .map_err(|e| Action::Error(format!("Could not write bookmarks to {}: {e}", path.display())))
+}
+
+/// Bookmarks `uri` under `name`, replacing an earlier bookmark with the same name
+pub fn add(name: &str, uri: String, connection: Option<String>) -> Result<(), Action> {
+    let name = name.trim();
+    if name.is_empty() {
+        return Err(Action::Error("A bookmark needs a name".to_string()));
+    }
+    // the Connections tree shows only what follows the last '/'
+    if name.contains('/') {
+        return Err(Action::Error("A bookmark name cannot contain '/'".to_string()));
+    }
+    let mut bookmarks = load();
+    let entry = Bookmark { name: name.to_string(), uri, connection };
+    match bookmarks.iter_mut().find(|bookmark| bookmark.name == name) {
+        Some(existing) => *existing = entry,
+        None => bookmarks.push(entry),
+    }
+    info!("Saving bookmark {name} to {}", bookmarks_path().display());
+    store(&bookmarks)
+}
+
+/// Forgets the bookmark called `name`
+pub fn remove(name: &str) -> Result<(), Action> {
+    let mut bookmarks = load();
+    bookmarks.retain(|bookmark| bookmark.name != name);
+    store(&bookmarks)
+}
@@ -1,3 +1,6 @@
+use std::collections::BTreeMap;
+use std::sync::OnceLock;
+
 use crate::key::Key;
 
 #[derive(Debug, Clone, Copy)]
@@ -19,7 +22,13 @@ pub struct KeyConfig {
     pub next_page: Key,
     pub previous_page: Key,
     pub toggle_selected: Key,
+    pub mark: Key,
+    pub mark_children: Key,
+    pub invert_marks: Key,
+    pub clear_marks: Key,
     pub filter: Key,
+    pub filter_mode: Key,
+    pub filter_case: Key,
     pub close_component: Key, // ESC
     // pub page_down: Key,
     // pub page_up: Key,
@@ -27,10 +36,51 @@ pub struct KeyConfig {
     pub enter: Key,
     pub backspace: Key,
     pub delete: Key,
+    pub download: Key,
+    pub delete_object: Key,
+    pub upload: Key,
+    pub verify: Key,
+    pub yank: Key,
+    pub cut: Key,
+    pub paste: Key,
+    pub sync: Key,
+    pub compare: Key,
+    pub search: Key,
+    pub go_to: Key,
+    pub history_back: Key,
+    pub history_forward: Key,
+    pub save_search: Key,
+    pub saved_searches: Key,
+    pub sync_reverse: Key,
+    pub sync_compare: Key,
+    pub sync_delete: Key,
+    pub transfers: Key,
+    pub retry: Key,
+    pub cancel: Key,
+    pub help: Key,
+    pub palette: Key,
+    pub palette_alt: Key,
+    pub refresh: Key,
+    pub bookmark: Key,
+    pub bookmarks: Key,
+}
+
+/// Bindings every component starts with, set once at startup from the `[keys]` table
+static KEY_CONFIG: OnceLock<KeyConfig> = OnceLock::new();
+
+pub fn set_key_config(key_config: KeyConfig) {
+    let _ = KEY_CONFIG.set(key_config);
 }
 
 impl Default for KeyConfig {
     fn default() -> Self {
+        KEY_CONFIG.get().copied().unwrap_or_else(Self::builtin)
+    }
+}
+
+impl KeyConfig {
+    /// The bindings before the config file is applied
+    fn builtin() -> Self {
         Self {
             quit: Key::Char('q'),
             exit: Key::Ctrl('c'),
@@ -48,7 +98,13 @@ impl Default for KeyConfig {
             next_page: Key::Ctrl('l'),
             previous_page: Key::Ctrl('h'),
             toggle_selected: Key::Char(' '),
+            mark: Key::Char(' '),
+            mark_children: Key::Char('m'),
+            invert_marks: Key::Char('i'),
+            clear_marks: Key::Char('U'),
             filter: Key::Char('/'),
+            filter_mode: Key::Ctrl('t'),
+            filter_case: Key::Ctrl('s'),
             close_component: Key::Esc,
             // page_down: Key::Ctrl('f'),
             // page_up: Key::Ctrl('b'),
@@ -57,6 +113,270 @@ impl Default for KeyConfig {
             change_focus: Key::Tab,
             backspace: Key::Backspace,
             delete: Key::Delete,
+            download: Key::Char('d'),
+            delete_object: Key::Char('D'),
+            upload: Key::Char('u'),
+            verify: Key::Char('v'),
+            yank: Key::Char('y'),
+            cut: Key::Char('x'),
+            paste: Key::Char('p'),
+            sync: Key::Char('s'),
+            compare: Key::Char('c'),
+            search: Key::Ctrl('f'),
+            go_to: Key::Ctrl('g'),
+            history_back: Key::Char('H'),
+            history_forward: Key::Char('L'),
+            save_search: Key::Ctrl('w'),
+            saved_searches: Key::Char('o'),
+            sync_reverse: Key::Char('r'),
+            sync_compare: Key::Char('m'),
+            sync_delete: Key::Char('D'),
+            transfers: Key::Char('t'),
+            retry: Key::Char('r'),
+            cancel: Key::Char('c'),
+            help: Key::Char('?'),
+            palette: Key::Char(':'),
+            palette_alt: Key::Ctrl('p'),
+            refresh: Key::Char('R'),
+            bookmark: Key::Char('b'),
+            bookmarks: Key::Char('B'),
+        }
+    }
+}
+
+/// Lists every binding once, so the config file can name any field
+macro_rules! bindings {
+    ($($field:ident),* $(,)?) => {
+        impl KeyConfig {
+            pub fn binding(&self, name: &str) -> Option<Key> {
+                match name {
+                    $(stringify!($field) => Some(self.$field),)*
+                    _ => None,
+                }
+            }
+
+            fn binding_mut(&mut self, name: &str) -> Option<&mut Key> {
+                match name {
+                    $(stringify!($field) => Some(&mut self.$field),)*
+                    _ => None,
+                }
+            }
+        }
+    };
+}
+
+bindings!(
+    quit,
+    change_focus,
+    exit,
+    key_down,
+    key_up,
+    key_left,
+    key_right,
+    arrow_down,
+    arrow_up,
+    arrow_left,
+    arrow_right,
+    activate,
+    select_last,
+    select_first,
+    next_page,
+    previous_page,
+    toggle_selected,
+    mark,
+    mark_children,
+    invert_marks,
+    clear_marks,
+    filter,
+    filter_mode,
+    filter_case,
+    close_component,
+    list_item,
+    enter,
+    backspace,
+    delete,
+    download,
+    delete_object,
+    upload,
+    verify,
+    yank,
+    cut,
+    paste,
+    sync,
+    compare,
+    search,
+    go_to,
+    history_back,
+    history_forward,
+    save_search,
+    saved_searches,
+    sync_reverse,
+    sync_compare,
+    sync_delete,
+    transfers,
+    retry,
+    cancel,
+    help,
+    palette,
+    palette_alt,
+    refresh,
+    bookmark,
+    bookmarks,
+);
+
+/// Bindings one pane reads together; two of them on the same key would shadow each other
+const SCOPES: &[(&str, &[&str])] = &[
+    ("Connections", &[
+        "quit", "exit", "change_focus", "key_down", "key_up", "key_left", "key_right",
+        "arrow_down", "arrow_up", "arrow_left", "arrow_right", "activate", "select_first",
+        "select_last", "toggle_selected", "filter", "list_item", "bookmarks", "help", "palette",
+        "palette_alt",
+    ]),
+    ("Viewer", &[
+        "quit", "exit", "change_focus", "key_down", "key_up", "key_left", "key_right",
+        "arrow_down", "arrow_up", "arrow_left", "arrow_right", "select_first", "select_last",
+        "next_page", "previous_page", "mark", "mark_children", "invert_marks", "clear_marks",
+        "filter", "list_item", "download", "delete_object", "upload", "verify", "yank", "cut",
+        "paste", "sync", "compare", "search", "go_to", "history_back", "history_forward",
+        "saved_searches", "transfers", "refresh", "bookmark", "bookmarks", "help", "palette",
+        "palette_alt",
+    ]),
+    ("Filter", &[
+        "exit", "change_focus", "close_component", "enter", "backspace", "delete",
+        "arrow_down", "arrow_up", "arrow_left", "arrow_right", "filter_mode", "filter_case",
+        "save_search",
+    ]),
+    ("Filter results", &[
+        "exit", "change_focus", "enter", "key_down", "key_up", "arrow_down", "arrow_up", "help",
+        "palette", "palette_alt",
+    ]),
+    ("Prompt", &["exit", "close_component", "enter"]),
+    ("Help", &["quit", "exit", "close_component", "help", "key_down", "key_up", "arrow_down", "arrow_up"]),
+    ("Palette", &["exit", "close_component", "enter", "arrow_down", "arrow_up"]),
+    ("Search", &[
+        "quit", "exit", "close_component", "enter", "key_down", "key_up", "arrow_down", "arrow_up",
+        "save_search", "help", "palette", "palette_alt",
+    ]),
+    ("Bookmarks", &[
+        "quit", "exit", "close_component", "enter", "delete", "key_down", "key_up", "arrow_down",
+        "arrow_up", "help", "palette", "palette_alt",
+    ]),
+    ("Saved searches", &[
+        "quit", "exit", "close_component", "enter", "delete", "key_down", "key_up", "arrow_down",
+        "arrow_up", "help", "palette", "palette_alt",
+    ]),
+    ("Sync review", &[
+        "quit", "exit", "close_component", "enter", "key_down", "key_up", "arrow_down", "arrow_up",
+        "toggle_selected", "sync_reverse", "sync_compare", "sync_delete", "help", "palette",
+        "palette_alt",
+    ]),
+    ("Transfers", &[
+        "quit", "exit", "close_component", "transfers", "key_down", "key_up", "arrow_down",
+        "arrow_up", "retry", "cancel", "help", "palette", "palette_alt",
+    ]),
+];
+
+impl KeyConfig {
+    /// The defaults with `overrides` (field name to key, e.g. `download = "Ctrl+d"`) applied.
+    /// Unknown names, unreadable keys and bindings that clash within a pane are left at their
+    /// defaults and described in the returned messages.
+    pub fn with_overrides(overrides: &BTreeMap<String, String>) -> (Self, Vec<String>) {
+        let defaults = Self::builtin();
+        let mut config = defaults;
+        let mut problems = vec![];
+
+        for (name, value) in overrides {
+            let Some(binding) = config.binding_mut(name) else {
+                problems.push(format!("Unknown key binding '{name}'"));
+                continue;
+            };
+            match value.parse::<Key>() {
+                Ok(key) => *binding = key,
+                Err(e) => problems.push(format!("{name}: {e}")),
+            }
         }
+
+        // put clashing overrides back until nothing clashes; the defaults never do
+        while let Some((scope, first, second)) = config.clash() {
+            let key = config.binding(first).unwrap_or(Key::Unknown);
+            let mut reverted = vec![];
+            for name in [first, second] {
+                if let (Some(binding), Some(default)) = (config.binding_mut(name), defaults.binding(name)) {
+                    if *binding != default {
+                        *binding = default;
+                        reverted.push(name);
+                    }
+                }
+            }
+            if reverted.is_empty() {
+                break;
+            }
+            problems.push(format!(
+                "{first} and {second} are both bound to {key} in {scope}, using the default for {}",
+                reverted.join(" and ")
+            ));
+        }
+
+        (config, problems)
+    }
+
+    /// The binding on `key` in the Connections or Viewer tree, where sequences are typed, and its pane
+    pub fn tree_binding(&self, key: Key) -> Option<(&'static str, &'static str)> {
+        SCOPES
+            .iter()
+            .filter(|(scope, _)| matches!(*scope, "Connections" | "Viewer"))
+            .find_map(|(scope, names)| {
+                names.iter().find(|name| self.binding(name) == Some(key)).map(|name| (*scope, *name))
+            })
+    }
+
+    /// The first pane with two bindings on the same key
+    fn clash(&self) -> Option<(&'static str, &'static str, &'static str)> {
+        SCOPES.iter().find_map(|(scope, names)| {
+            names.iter().enumerate().find_map(|(idx, first)| {
+                names[idx + 1..]
+                    .iter()
+                    .find(|second| self.binding(first) == self.binding(second))
+                    .map(|second| (*scope, *first, *second))
+            })
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn overrides(pairs: &[(&str, &str)]) -> BTreeMap<String, String> {
+        pairs.iter().map(|(name, key)| (name.to_string(), key.to_string())).collect()
+    }
+
+    #[test]
+    fn overrides_replace_the_defaults() {
+        let (config, problems) = KeyConfig::with_overrides(&overrides(&[("download", "Ctrl+d"), ("quit", "Q")]));
+        assert!(problems.is_empty(), "{problems:?}");
+        assert_eq!((config.download, config.quit), (Key::Ctrl('d'), Key::Char('Q')));
+    }
+
+    #[test]
+    fn unknown_names_and_keys_are_reported() {
+        let (config, problems) = KeyConfig::with_overrides(&overrides(&[("downlaod", "x"), ("upload", "Hyper+u")]));
+        assert_eq!(config.upload, Key::Char('u'));
+        assert_eq!(problems.len(), 2, "{problems:?}");
+        assert!(problems[0].contains("downlaod"));
+        assert!(problems[1].starts_with("upload:"));
+    }
+
+    #[test]
+    fn clashing_overrides_are_reverted() {
+        // `y` is the default for yank, which shares the Viewer with download
+        let (config, problems) = KeyConfig::with_overrides(&overrides(&[("download", "y")]));
+        assert_eq!((config.download, config.yank), (Key::Char('d'), Key::Char('y')));
+        assert_eq!(problems, vec!["download and yank are both bound to y in Viewer, using the default for download"]);
+
+        // the same key in panes that never see each other's bindings is fine
+        let (config, problems) = KeyConfig::with_overrides(&overrides(&[("filter_mode", "d")]));
+        assert_eq!(config.filter_mode, Key::Char('d'));
+        assert!(problems.is_empty(), "{problems:?}");
     }
 }
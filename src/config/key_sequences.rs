@@ -0,0 +1,308 @@
+use std::collections::{BTreeMap, VecDeque};
+use std::time::{Duration, Instant};
+
+use crate::components::help;
+use crate::config::key_config::KeyConfig;
+use crate::key::Key;
+
+/// How long a started sequence waits for its next key, like vim's `timeoutlen`
+pub const DEFAULT_TIMEOUT: Duration = Duration::from_millis(1000);
+
+/// The largest count a prefix like the 5 in `5j` can repeat a key
+const MAX_COUNT: usize = 999;
+
+/// Bindings a count repeats; any other binding runs once
+const REPEATABLE: &[&str] = &[
+    "key_down", "key_up", "key_left", "key_right", "arrow_down", "arrow_up", "arrow_left",
+    "arrow_right", "next_page", "previous_page",
+];
+
+/// Sequences the Connections and Viewer trees understand without a `[sequences]` table
+const DEFAULTS: &[(&str, &str)] = &[
+    ("g g", "select_first"),
+    ("G", "select_last"),
+    ("z o", "key_right"),
+    ("z c", "key_left"),
+];
+
+/// Reads a sequence like `g g`, `Ctrl+w j` or the short form `gg`
+pub fn parse_sequence(text: &str) -> Result<Vec<Key>, String> {
+    let mut keys = vec![];
+    for token in text.split_whitespace() {
+        match token.parse::<Key>() {
+            Ok(key) => keys.push(key),
+            // `gg` is short for `g g`
+            Err(_) if !token.contains(['+', '-', '<']) => keys.extend(token.chars().map(Key::Char)),
+            Err(e) => return Err(e),
+        }
+    }
+    match keys.first() {
+        None => Err("no keys given".to_string()),
+        Some(Key::Char('1'..='9')) => Err("starts with a digit, which is read as a count".to_string()),
+        Some(_) => Ok(keys),
+    }
+}
+
+/// Turns the keys typed in a tree into the keys its bindings know: counts repeat a key, and a
+/// sequence stands for the single key of the binding it is mapped to
+#[derive(Debug, Clone)]
+pub struct KeySequences {
+    /// Every sequence with the `KeyConfig` field it stands for
+    sequences: Vec<(Vec<Key>, String)>,
+    timeout: Duration,
+    count: Option<usize>,
+    pending: Vec<Key>,
+    last_key: Option<Instant>,
+    /// Resolved keys not sent to the components yet
+    ready: VecDeque<Key>,
+}
+
+impl Default for KeySequences {
+    fn default() -> Self {
+        Self {
+            sequences: DEFAULTS
+                .iter()
+                .filter_map(|(sequence, name)| Some((parse_sequence(sequence).ok()?, name.to_string())))
+                .collect(),
+            timeout: DEFAULT_TIMEOUT,
+            count: None,
+            pending: vec![],
+            last_key: None,
+            ready: VecDeque::new(),
+        }
+    }
+}
+
+impl KeySequences {
+    /// The defaults with `overrides` (sequence to field name, e.g. `"g t" = "transfers"`) applied.
+    /// An empty name removes a default sequence. Unreadable sequences, unknown names and sequences
+    /// starting with a key `keys` binds in the trees are skipped and described in the returned messages.
+    pub fn with_overrides(
+        overrides: &BTreeMap<String, String>,
+        keys: &KeyConfig,
+        timeout: Option<Duration>,
+    ) -> (Self, Vec<String>) {
+        let mut sequences = Self {
+            timeout: timeout.unwrap_or(DEFAULT_TIMEOUT),
+            ..Self::default()
+        };
+        let mut problems = vec![];
+
+        for (text, name) in overrides {
+            let sequence = match parse_sequence(text) {
+                Ok(sequence) => sequence,
+                Err(e) => {
+                    problems.push(format!("Sequence '{text}': {e}"));
+                    continue;
+                }
+            };
+            sequences.sequences.retain(|(existing, _)| *existing != sequence);
+            if name.is_empty() {
+                continue;
+            }
+            if keys.binding(name).is_none() {
+                problems.push(format!("Unknown key binding '{name}' for sequence '{text}'"));
+                continue;
+            }
+            sequences.sequences.push((sequence, name.clone()));
+        }
+
+        // a sequence would take its first key away from the binding, which then only acts after the timeout
+        sequences.sequences.retain(|(sequence, name)| {
+            let Some(first) = sequence.first() else {
+                return true;
+            };
+            match keys.tree_binding(*first) {
+                Some((_, bound)) if sequence.len() == 1 && bound == name => true,
+                Some((scope, bound)) => {
+                    let text: Vec<String> = sequence.iter().map(|key| key.to_string()).collect();
+                    problems.push(format!(
+                        "Sequence '{}' starts with {first}, the key of {bound} in {scope}, leaving the sequence out",
+                        text.join(" ")
+                    ));
+                    false
+                }
+                None => true,
+            }
+        });
+
+        (sequences, problems)
+    }
+
+    /// Takes one key typed in a tree. Nothing is ready while it may still continue a sequence.
+    pub fn push(&mut self, key: Key, keys: &KeyConfig) {
+        self.last_key = Some(Instant::now());
+        if let Key::Char(digit @ '0'..='9') = key {
+            // a leading 0 is not a count, so it stays free for a binding
+            if self.pending.is_empty() && (digit != '0' || self.count.is_some()) {
+                let value = digit.to_digit(10).unwrap_or_default() as usize;
+                self.count = Some((self.count.unwrap_or_default() * 10 + value).min(MAX_COUNT));
+                return;
+            }
+        }
+
+        self.pending.push(key);
+        let continues = self
+            .sequences
+            .iter()
+            .any(|(sequence, _)| sequence.len() > self.pending.len() && sequence.starts_with(&self.pending));
+        if !continues {
+            let pending = std::mem::take(&mut self.pending);
+            self.resolve(pending, keys);
+        }
+    }
+
+    /// Resolves a sequence that waited longer than the timeout for its next key; whether one did
+    pub fn expire(&mut self, keys: &KeyConfig) -> bool {
+        if self.last_key.is_none_or(|at| at.elapsed() < self.timeout) {
+            return false;
+        }
+        let pending = std::mem::take(&mut self.pending);
+        self.resolve(pending, keys);
+        true
+    }
+
+    /// Forgets a count or sequence, e.g. when the focus moves to a pane without sequences
+    pub fn clear(&mut self) {
+        self.count = None;
+        self.pending.clear();
+        self.last_key = None;
+    }
+
+    /// The next key to send to the components
+    pub fn next_ready(&mut self) -> Option<Key> {
+        self.ready.pop_front()
+    }
+
+    pub fn has_ready(&self) -> bool {
+        !self.ready.is_empty()
+    }
+
+    /// The count and keys typed so far, e.g. `5g`; empty when nothing is pending
+    pub fn pending(&self) -> String {
+        let count = self.count.map(|count| count.to_string()).unwrap_or_default();
+        let keys: String = self.pending.iter().map(|key| help::key_label(*key)).collect();
+        count + &keys
+    }
+
+    /// A whole sequence becomes its binding's key; otherwise the first key goes on its own and
+    /// the rest are typed again, since they may start a sequence of their own
+    fn resolve(&mut self, pending: Vec<Key>, keys: &KeyConfig) {
+        let count = self.count.take().unwrap_or(1);
+        self.last_key = None;
+        let matched = self
+            .sequences
+            .iter()
+            .find(|(sequence, _)| *sequence == pending)
+            .and_then(|(_, name)| keys.binding(name));
+        let (key, rest) = match (matched, pending.split_first()) {
+            (Some(key), _) => (key, &[][..]),
+            (None, Some((first, rest))) => (*first, rest),
+            (None, None) => return,
+        };
+
+        let repeatable = REPEATABLE.iter().any(|name| keys.binding(name) == Some(key));
+        let times = if repeatable { count } else { 1 };
+        self.ready.extend(std::iter::repeat_n(key, times));
+        for key in rest.iter().copied() {
+            self.push(key, keys);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn overrides(pairs: &[(&str, &str)]) -> BTreeMap<String, String> {
+        pairs.iter().map(|(text, name)| (text.to_string(), name.to_string())).collect()
+    }
+
+    /// Pushes every key and takes what is ready afterwards
+    fn typed(sequences: &mut KeySequences, keys: &[Key]) -> Vec<Key> {
+        let config = KeyConfig::default();
+        for key in keys {
+            sequences.push(*key, &config);
+        }
+        std::iter::from_fn(|| sequences.next_ready()).collect()
+    }
+
+    fn chars(text: &str) -> Vec<Key> {
+        text.chars().map(Key::Char).collect()
+    }
+
+    #[test]
+    fn sequences_parse_spaced_or_written_together() {
+        assert_eq!(parse_sequence("g g"), Ok(chars("gg")));
+        assert_eq!(parse_sequence("gg"), Ok(chars("gg")));
+        assert_eq!(parse_sequence("Ctrl+w j"), Ok(vec![Key::Ctrl('w'), Key::Char('j')]));
+        assert_eq!(parse_sequence("0 x"), Ok(chars("0x")));
+        assert!(parse_sequence("").is_err());
+        assert!(parse_sequence("5 j").is_err());
+        assert!(parse_sequence("Hyper+x y").is_err());
+    }
+
+    #[test]
+    fn counts_repeat_movements_only() {
+        let mut sequences = KeySequences::default();
+        assert_eq!(typed(&mut sequences, &chars("5j")), chars("jjjjj"));
+        assert_eq!(typed(&mut sequences, &chars("3d")), chars("d"));
+        assert_eq!(typed(&mut sequences, &chars("10k")).len(), 10);
+        assert_eq!(typed(&mut sequences, &chars("12345j")).len(), MAX_COUNT);
+    }
+
+    #[test]
+    fn a_leading_zero_is_a_key_of_its_own() {
+        let mut sequences = KeySequences::default();
+        assert_eq!(typed(&mut sequences, &chars("0")), chars("0"));
+        assert_eq!(typed(&mut sequences, &chars("20j")), chars(&"j".repeat(20)));
+    }
+
+    #[test]
+    fn whole_sequences_become_their_binding() {
+        let keys = KeyConfig::default();
+        let mut sequences = KeySequences::default();
+        assert_eq!(typed(&mut sequences, &chars("gg")), vec![keys.select_first]);
+        assert_eq!(typed(&mut sequences, &chars("G")), vec![keys.select_last]);
+        assert_eq!(typed(&mut sequences, &chars("3zo")), vec![keys.key_right; 3]);
+    }
+
+    #[test]
+    fn a_prefix_followed_by_another_key_sends_both() {
+        let mut sequences = KeySequences::default();
+        assert_eq!(typed(&mut sequences, &chars("g")), vec![]);
+        assert_eq!(sequences.pending(), "g");
+        assert_eq!(typed(&mut sequences, &chars("j")), chars("gj"));
+        // the second key may start a sequence of its own
+        assert_eq!(typed(&mut sequences, &chars("zgg")), vec![Key::Char('z'), KeyConfig::default().select_first]);
+    }
+
+    #[test]
+    fn a_prefix_acts_on_its_own_after_the_timeout() {
+        let keys = KeyConfig::default();
+        let mut waiting = KeySequences::default();
+        assert_eq!(typed(&mut waiting, &chars("g")), vec![]);
+        assert!(!waiting.expire(&keys));
+
+        let (mut sequences, _) = KeySequences::with_overrides(&BTreeMap::new(), &keys, Some(Duration::ZERO));
+        assert_eq!(typed(&mut sequences, &chars("g")), vec![]);
+        assert!(sequences.expire(&keys));
+        assert_eq!(sequences.next_ready(), Some(Key::Char('g')));
+        assert!(!sequences.expire(&keys));
+    }
+
+    #[test]
+    fn sequences_starting_with_a_bound_key_are_left_out() {
+        let keys = KeyConfig::default();
+        let (sequences, problems) =
+            KeySequences::with_overrides(&overrides(&[("d x", "transfers"), ("g t", "transfers")]), &keys, None);
+        assert_eq!(problems, vec!["Sequence 'd x' starts with d, the key of download in Viewer, leaving the sequence out"]);
+        let names: Vec<&str> = sequences.sequences.iter().map(|(_, name)| name.as_str()).collect();
+        assert_eq!(names, vec!["select_first", "select_last", "key_right", "key_left", "transfers"]);
+
+        // the defaults give way to a key bound in the config file
+        let (keys, _) = KeyConfig::with_overrides(&overrides(&[("transfers", "g")]));
+        let (_, problems) = KeySequences::with_overrides(&BTreeMap::new(), &keys, None);
+        assert_eq!(problems, vec!["Sequence 'g g' starts with g, the key of transfers in Viewer, leaving the sequence out"]);
+    }
+}
@@ -1,15 +1,33 @@
 use cloud_provider_config::CloudProviderConfig;
 use key_config::KeyConfig;
+use theme::Theme;
 
+use crate::transfer::{Clipboard, TransferQueue};
 
+
+pub mod bookmarks;
 pub mod cloud_provider_config;
 pub mod key_config;
+pub mod key_sequences;
+pub mod saved_searches;
+pub mod session;
+pub mod settings;
+pub mod theme;
 
 #[derive(Debug, Clone, Default)]
 pub struct Config {
     pub key_config: KeyConfig,
+    pub theme: Theme,
     pub cloud_provider_config: CloudProviderConfig,
     pub app_selection: Vec<String>,
+    pub transfers: TransferQueue,
+    pub clipboard: Option<Clipboard>,
+    /// How many items are marked in the Viewer
+    pub marked: usize,
+    /// Started with `--read-only`: nothing that changes a bucket is allowed
+    pub read_only: bool,
+    /// Count and keys of a sequence typed so far in a tree, e.g. `5g`
+    pub pending_keys: String,
 }
 
 // fn is_directory(loc: &str) -> bool {
@@ -0,0 +1,104 @@
+use std::fmt;
+use std::path::PathBuf;
+
+use serde::{Deserialize, Serialize};
+use tracing::{error, info};
+
+use crate::action::Action;
+use crate::components::filter::{FilterMode, MatchOptions};
+use crate::logging::get_config_dir;
+
+const SAVED_SEARCHES_FILE: &str = "saved_searches.toml";
+
+/// What a saved entry re-applies to the Viewer
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "lowercase")]
+pub enum Definition {
+    /// Viewer filter text, including any metadata conditions, and how it is matched
+    Filter {
+        text: String,
+        mode: FilterMode,
+        case_sensitive: bool,
+    },
+    /// Recursive search pattern, as typed into the search prompt
+    Search { pattern: String },
+}
+
+impl Definition {
+    pub fn filter(text: String, options: MatchOptions) -> Self {
+        Definition::Filter { text, mode: options.mode, case_sensitive: options.case_sensitive }
+    }
+}
+
+impl fmt::Display for Definition {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Definition::Filter { text, mode, case_sensitive } => {
+                let options = MatchOptions { mode: *mode, case_sensitive: *case_sensitive };
+                write!(f, "filter {text} ({options})")
+            }
+            Definition::Search { pattern } => write!(f, "search {pattern}"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SavedSearch {
+    pub name: String,
+    #[serde(flatten)]
+    pub definition: Definition,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct SavedSearches {
+    #[serde(default)]
+    saved: Vec<SavedSearch>,
+}
+
+pub fn saved_searches_path() -> PathBuf {
+    get_config_dir().join(SAVED_SEARCHES_FILE)
+}
+
+/// Every saved filter and search, in the order they were saved
+pub fn load() -> Vec<SavedSearch> {
+    let path = saved_searches_path();
+    let contents = match std::fs::read_to_string(&path) {
+        Ok(contents) => contents,
+        Err(_) => return Vec::new(),
+    };
+    match toml::from_str::<SavedSearches>(&contents) {
+        Ok(store) => store.saved,
+        Err(e) => {
+            error!("Ignoring unreadable saved searches {}: {e}", path.display());
+            Vec::new()
+        }
+    }
+}
+
+pub fn store(saved: &[SavedSearch]) -> Result<(), Action> {
+    let path = saved_searches_path();
+    let contents = toml::to_string(&SavedSearches { saved: saved.to_vec() })
+        .map_err(|e| Action::Error(format!("Could not serialize saved searches: {e}")))?;
+    if let Some(directory) = path.parent() {
+        std::fs::create_dir_all(directory)
+            .map_err(|e| Action::Error(format!("Could not create {}: {e}", directory.display())))?;
+    }
+    std::fs::write(&path, contents)
+        .map_err(|e| Action::Error(format!("Could not write saved searches to {}: {e}", path.display())))
+}
+
+/// Saves `definition` under `name`, replacing an earlier entry with the same name
+pub fn add(name: &str, definition: Definition) -> Result<(), Action> {
+    let name = name.trim();
+    if name.is_empty() {
+        return Err(Action::Error("A saved search needs a name".to_string()));
+    }
+    let mut saved = load();
+    let entry = SavedSearch { name: name.to_string(), definition };
+    match saved.iter_mut().find(|saved| saved.name == name) {
+        Some(existing) => *existing = entry,
+        None => saved.push(entry),
+    }
+    info!("Saving search {name} to {}", saved_searches_path().display());
+    store(&saved)
+}
@@ -0,0 +1,63 @@
+use std::path::PathBuf;
+
+use serde::{Deserialize, Serialize};
+use tracing::{error, info};
+
+use crate::cli::Provider;
+use crate::logging::get_data_dir;
+
+const SESSION_FILE: &str = "session.toml";
+
+/// What was open when the app last quit, so the next start can pick up from there
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Session {
+    pub provider: Option<Provider>,
+    pub connection: Option<String>,
+    /// Prefixes open in the Viewer, parents before their children
+    pub expanded: Vec<String>,
+    pub selected: Option<String>,
+    /// Page of the paged listing, which is the last of `expanded`
+    pub page: usize,
+}
+
+impl Session {
+    /// Nothing to restore without a connection to list
+    pub fn is_empty(&self) -> bool {
+        self.provider.is_none() || self.connection.is_none()
+    }
+}
+
+pub fn session_path() -> PathBuf {
+    get_data_dir().join(SESSION_FILE)
+}
+
+/// Writes the session to the data directory, replacing the previous one
+pub fn save(session: &Session) {
+    let path = session_path();
+    match toml::to_string(session) {
+        Ok(contents) => {
+            if let Err(e) = std::fs::write(&path, contents) {
+                error!("Could not save the session to {}: {e}", path.display());
+            }
+        }
+        Err(e) => error!("Could not serialize the session: {e}"),
+    }
+}
+
+/// The session the previous run left behind, if there is one worth restoring
+pub fn load() -> Option<Session> {
+    let path = session_path();
+    let contents = std::fs::read_to_string(&path).ok()?;
+    match toml::from_str::<Session>(&contents) {
+        Ok(session) if session.is_empty() => None,
+        Ok(session) => {
+            info!("Restoring the session from {}", path.display());
+            Some(session)
+        }
+        Err(e) => {
+            error!("Ignoring unreadable session {}: {e}", path.display());
+            None
+        }
+    }
+}
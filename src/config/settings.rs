@@ -0,0 +1,70 @@
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+use serde::Deserialize;
+
+use crate::cli::Provider;
+use crate::config::theme::ThemeName;
+use crate::logging::get_config_dir;
+use crate::tui::RATES;
+
+const SETTINGS_FILE: &str = "config.toml";
+
+/// Defaults for the command-line options, read from `config.toml`; flags given on the command line win
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct Settings {
+    pub provider: Option<Provider>,
+    pub connection: Option<String>,
+    pub log_level: Option<String>,
+    pub results_per_page: Option<usize>,
+    pub read_only: Option<bool>,
+    pub theme: Option<ThemeName>,
+    /// Set to false to always start fresh, as with `--fresh`
+    pub restore_session: Option<bool>,
+    /// `[keys]` table rebinding `KeyConfig` fields, e.g. `download = "Ctrl+d"`
+    pub keys: BTreeMap<String, String>,
+    /// `[sequences]` table mapping key sequences to `KeyConfig` fields, e.g. `"g g" = "select_first"`
+    pub sequences: BTreeMap<String, String>,
+    /// How long a sequence waits for its next key, in milliseconds
+    pub sequence_timeout_ms: Option<u64>,
+    /// Housekeeping rounds per second, which also set how often transfer progress is redrawn
+    pub tick_rate: Option<f64>,
+    /// Most frames drawn per second
+    pub frame_rate: Option<f64>,
+}
+
+pub fn settings_path() -> PathBuf {
+    get_config_dir().join(SETTINGS_FILE)
+}
+
+impl Settings {
+    /// Reads `path`, or `config.toml` in the config directory. Only a file named on the command line has to exist.
+    pub fn load(path: Option<&Path>) -> Result<Self, String> {
+        let (path, required) = match path {
+            Some(path) => (path.to_path_buf(), true),
+            None => (settings_path(), false),
+        };
+        let contents = match std::fs::read_to_string(&path) {
+            Ok(contents) => contents,
+            Err(_) if !required => return Ok(Self::default()),
+            Err(e) => return Err(format!("Could not read config file {}: {e}", path.display())),
+        };
+        let settings: Self =
+            toml::from_str(&contents).map_err(|e| format!("Invalid config file {}: {e}", path.display()))?;
+        if settings.results_per_page == Some(0) {
+            return Err(format!("results_per_page in {} must be at least 1", path.display()));
+        }
+        for (name, rate) in [("tick_rate", settings.tick_rate), ("frame_rate", settings.frame_rate)] {
+            if rate.is_some_and(|rate| !RATES.contains(&rate)) {
+                return Err(format!(
+                    "{name} in {} must be between {} and {} per second",
+                    path.display(),
+                    RATES.start(),
+                    RATES.end()
+                ));
+            }
+        }
+        Ok(settings)
+    }
+}
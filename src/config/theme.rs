@@ -0,0 +1,199 @@
+use std::fmt;
+use std::sync::RwLock;
+
+use clap::ValueEnum;
+use ratatui::style::{Color, Modifier, Style};
+use serde::Deserialize;
+
+/// Styles every component draws with, set at startup from `--theme`, `theme` in the config
+/// file or `NO_COLOR`, and switched from the command palette
+static THEME: RwLock<Option<Theme>> = RwLock::new(None);
+
+pub fn set_theme(theme: Theme) {
+    if let Ok(mut current) = THEME.write() {
+        *current = Some(theme);
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, ValueEnum, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum ThemeName {
+    #[default]
+    Dark,
+    Light,
+    Solarized,
+    HighContrast,
+    /// No colors at all, only bold, dim, underlined and reversed text
+    Monochrome,
+}
+
+impl ThemeName {
+    /// The theme to use: a name given on the command line wins, then `NO_COLOR`, then the config file
+    pub fn resolve(flag: Option<ThemeName>, setting: Option<ThemeName>) -> ThemeName {
+        let no_color = std::env::var("NO_COLOR").is_ok_and(|value| !value.is_empty());
+        match (flag, setting) {
+            (Some(name), _) => name,
+            _ if no_color => ThemeName::Monochrome,
+            (None, setting) => setting.unwrap_or_default(),
+        }
+    }
+}
+
+impl fmt::Display for ThemeName {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.to_possible_value() {
+            Some(value) => write!(f, "{}", value.get_name()),
+            None => write!(f, "{self:?}"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Theme {
+    pub name: ThemeName,
+    /// Border of the focused pane or popup
+    pub border: Style,
+    /// Selected row of the focused tree, list or table
+    pub highlight: Style,
+    /// Popups drawn over the panes
+    pub popup: Style,
+    /// Text typed into inputs
+    pub input: Style,
+    /// Key names in the footer hints
+    pub key_hint: Style,
+    pub connection: Style,
+    pub error: Style,
+    /// Work in progress: running transfers and searches, updated sync entries
+    pub pending: Style,
+    pub success: Style,
+    /// Queued transfers, italic when paused
+    pub secondary: Style,
+    /// Parent paths, excluded sync entries, cancelled transfers
+    pub muted: Style,
+    /// Items marked in the Viewer
+    pub marked: Style,
+    /// Characters a filter matched
+    pub matched: Style,
+    /// The paging summary in the Viewer's corner
+    pub paging: Style,
+    pub error_popup: Style,
+}
+
+impl Default for Theme {
+    fn default() -> Self {
+        THEME.read().ok().and_then(|theme| *theme).unwrap_or_else(|| Theme::named(ThemeName::Dark))
+    }
+}
+
+impl Theme {
+    pub fn named(name: ThemeName) -> Self {
+        let bold = Modifier::BOLD;
+        match name {
+            ThemeName::Dark => Theme {
+                name,
+                border: Style::new().fg(Color::Blue),
+                highlight: Style::new().fg(Color::Black).bg(Color::LightGreen).add_modifier(bold),
+                popup: Style::new().bg(Color::Black),
+                input: Style::new().fg(Color::White),
+                key_hint: Style::new().fg(Color::Blue),
+                connection: Style::new().fg(Color::Green),
+                error: Style::new().fg(Color::Red),
+                pending: Style::new().fg(Color::Yellow),
+                success: Style::new().fg(Color::Green),
+                secondary: Style::new().fg(Color::Gray),
+                muted: Style::new().fg(Color::DarkGray),
+                marked: Style::new().fg(Color::Magenta).add_modifier(bold),
+                matched: Style::new().fg(Color::Yellow).add_modifier(bold),
+                paging: Style::new().fg(Color::Black).bg(Color::Gray),
+                error_popup: Style::new().fg(Color::Red).bg(Color::Black),
+            },
+            ThemeName::Light => Theme {
+                name,
+                border: Style::new().fg(Color::Blue),
+                highlight: Style::new().fg(Color::White).bg(Color::Blue).add_modifier(bold),
+                popup: Style::new().fg(Color::Black).bg(Color::White),
+                input: Style::new().fg(Color::Black),
+                key_hint: Style::new().fg(Color::Blue),
+                connection: Style::new().fg(Color::Green),
+                error: Style::new().fg(Color::Red),
+                // yellow disappears on white, a dark orange does not
+                pending: Style::new().fg(Color::Indexed(130)),
+                success: Style::new().fg(Color::Green),
+                secondary: Style::new().fg(Color::DarkGray),
+                muted: Style::new().fg(Color::Gray),
+                marked: Style::new().fg(Color::Magenta).add_modifier(bold),
+                matched: Style::new().fg(Color::Blue).add_modifier(bold | Modifier::UNDERLINED),
+                paging: Style::new().fg(Color::White).bg(Color::DarkGray),
+                error_popup: Style::new().fg(Color::Red).bg(Color::White),
+            },
+            ThemeName::Solarized => {
+                let base03 = Color::Rgb(0x00, 0x2b, 0x36);
+                let base02 = Color::Rgb(0x07, 0x36, 0x42);
+                let base01 = Color::Rgb(0x58, 0x6e, 0x75);
+                let base0 = Color::Rgb(0x83, 0x94, 0x96);
+                let base1 = Color::Rgb(0x93, 0xa1, 0xa1);
+                let yellow = Color::Rgb(0xb5, 0x89, 0x00);
+                let orange = Color::Rgb(0xcb, 0x4b, 0x16);
+                let red = Color::Rgb(0xdc, 0x32, 0x2f);
+                let magenta = Color::Rgb(0xd3, 0x36, 0x82);
+                let blue = Color::Rgb(0x26, 0x8b, 0xd2);
+                let cyan = Color::Rgb(0x2a, 0xa1, 0x98);
+                let green = Color::Rgb(0x85, 0x99, 0x00);
+                Theme {
+                    name,
+                    border: Style::new().fg(blue),
+                    highlight: Style::new().fg(base03).bg(cyan).add_modifier(bold),
+                    popup: Style::new().fg(base0).bg(base03),
+                    input: Style::new().fg(base1),
+                    key_hint: Style::new().fg(blue),
+                    connection: Style::new().fg(green),
+                    error: Style::new().fg(red),
+                    pending: Style::new().fg(yellow),
+                    success: Style::new().fg(green),
+                    secondary: Style::new().fg(base0),
+                    muted: Style::new().fg(base01),
+                    marked: Style::new().fg(magenta).add_modifier(bold),
+                    matched: Style::new().fg(orange).add_modifier(bold),
+                    paging: Style::new().fg(base03).bg(base1),
+                    error_popup: Style::new().fg(red).bg(base02),
+                }
+            }
+            ThemeName::HighContrast => Theme {
+                name,
+                border: Style::new().fg(Color::White).add_modifier(bold),
+                highlight: Style::new().fg(Color::Black).bg(Color::Yellow).add_modifier(bold),
+                popup: Style::new().fg(Color::White).bg(Color::Black),
+                input: Style::new().fg(Color::White).add_modifier(bold),
+                key_hint: Style::new().fg(Color::Cyan).add_modifier(bold),
+                connection: Style::new().fg(Color::LightGreen).add_modifier(bold),
+                error: Style::new().fg(Color::LightRed).add_modifier(bold),
+                pending: Style::new().fg(Color::LightYellow),
+                success: Style::new().fg(Color::LightGreen),
+                secondary: Style::new().fg(Color::White),
+                muted: Style::new().fg(Color::Gray),
+                marked: Style::new().fg(Color::LightMagenta).add_modifier(bold | Modifier::UNDERLINED),
+                matched: Style::new().fg(Color::LightYellow).add_modifier(bold | Modifier::UNDERLINED),
+                paging: Style::new().fg(Color::Black).bg(Color::White),
+                error_popup: Style::new().fg(Color::White).bg(Color::Red).add_modifier(bold),
+            },
+            ThemeName::Monochrome => Theme {
+                name,
+                border: Style::new().add_modifier(bold),
+                highlight: Style::new().add_modifier(bold | Modifier::REVERSED),
+                popup: Style::new(),
+                input: Style::new(),
+                key_hint: Style::new().add_modifier(bold),
+                connection: Style::new().add_modifier(bold),
+                error: Style::new().add_modifier(bold),
+                pending: Style::new().add_modifier(Modifier::ITALIC),
+                success: Style::new(),
+                secondary: Style::new(),
+                muted: Style::new().add_modifier(Modifier::DIM),
+                marked: Style::new().add_modifier(bold | Modifier::UNDERLINED),
+                matched: Style::new().add_modifier(bold | Modifier::UNDERLINED),
+                paging: Style::new().add_modifier(Modifier::REVERSED),
+                error_popup: Style::new().add_modifier(bold | Modifier::REVERSED),
+            },
+        }
+    }
+}
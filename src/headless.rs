@@ -0,0 +1,507 @@
+use std::collections::HashSet;
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::thread;
+use std::time::Duration;
+
+use serde::Serialize;
+use tracing::info;
+
+use crate::action::Action;
+use crate::cli::Command;
+use crate::components::connections::CONNECTIONS_ROOT;
+use crate::config::cloud_provider_config::{cloud_provider_kind::CloudProviderKind, CloudProviderConfig};
+use crate::storage::{self, cloud_uri::CloudUri, ObjectMetadata};
+use crate::transfer::{self, Endpoint, TransferKind, TransferQueue, TransferState};
+use crate::util;
+
+/// How often `cp` checks on its transfers
+const POLL_INTERVAL: Duration = Duration::from_millis(100);
+
+/// One line of `ls` output
+#[derive(Debug, Serialize)]
+struct ListedEntry {
+    uri: String,
+    prefix: bool,
+    #[serde(flatten)]
+    metadata: Option<ObjectMetadata>,
+}
+
+/// Runs a subcommand against the same storage and transfer code the TUI uses
+pub fn run(command: Command, connection: Option<String>, read_only: bool) -> Result<(), String> {
+    info!("Running {command:?}");
+    let result = (|| {
+        if let Some(connection) = connection {
+            use_connection(&command_uris(&command)?, &connection)?;
+        }
+        match command {
+            Command::Ls { uri, long, json } => ls(&CloudUri::parse(&uri)?, long, json),
+            Command::Stat { uri, json } => stat(&CloudUri::parse(&uri)?, json),
+            Command::Cat { uris } => cat(&uris),
+            Command::Cp { source, destination, recursive } => cp(&source, &destination, recursive, read_only),
+        }
+    })();
+    result.map_err(|e| match e {
+        Action::Error(message) => message,
+        other => format!("{other:?}"),
+    })
+}
+
+/// Every bucket location the command touches; local paths are left out
+fn command_uris(command: &Command) -> Result<Vec<CloudUri>, Action> {
+    match command {
+        Command::Ls { uri, .. } | Command::Stat { uri, .. } => Ok(vec![CloudUri::parse(uri)?]),
+        Command::Cat { uris } => uris.iter().map(|uri| CloudUri::parse(uri)).collect(),
+        Command::Cp { source, destination, .. } => Ok([source, destination]
+            .into_iter()
+            .filter_map(|path| match Endpoint::from(path.as_str()) {
+                Endpoint::Remote(uri) => Some(uri),
+                Endpoint::Local(_) => None,
+            })
+            .collect()),
+    }
+}
+
+/// Checks `connection` exists the way the Connections tree does, then points the provider CLI at it
+/// for this process only, leaving the globally active one alone
+fn use_connection(uris: &[CloudUri], connection: &str) -> Result<(), Action> {
+    let kind = CloudProviderKind::Gcs;
+    if uris.is_empty() {
+        return Ok(());
+    }
+    if uris.iter().any(|uri| uri.kind != kind) {
+        return Err(Action::Error(format!("--connection is only supported for {kind}")));
+    }
+    let mut providers = CloudProviderConfig::default();
+    providers.list_connections(&kind)?;
+    providers.activate(vec![CONNECTIONS_ROOT.to_string(), kind.to_string(), connection.to_string()])?;
+    if providers.active_cloud_connection.map(|active| active.name()).as_deref() != Some(connection) {
+        return Err(Action::Error(format!("No {kind} connection named {connection}")));
+    }
+    std::env::set_var("CLOUDSDK_ACTIVE_CONFIG_NAME", connection);
+    Ok(())
+}
+
+fn ls(uri: &CloudUri, long: bool, json: bool) -> Result<(), Action> {
+    let entries = if uri.is_prefix() {
+        let mut prefixes = storage::list_prefixes(uri)?;
+        prefixes.sort_by_key(|prefix| prefix.to_string());
+        let mut objects = storage::list_metadata(uri)?;
+        objects.sort_by_key(|(object, _)| object.to_string());
+        prefixes
+            .into_iter()
+            .map(|prefix| ListedEntry { uri: prefix.to_string(), prefix: true, metadata: None })
+            .chain(
+                objects
+                    .into_iter()
+                    // skip the placeholder object some tools create for the "directory" itself
+                    .filter(|(object, _)| object != uri)
+                    .map(|(object, metadata)| ListedEntry { uri: object.to_string(), prefix: false, metadata: Some(metadata) }),
+            )
+            .collect()
+    } else {
+        vec![ListedEntry { uri: uri.to_string(), prefix: false, metadata: Some(storage::object_metadata(uri)?) }]
+    };
+
+    if json {
+        return print(&to_json(&entries)?);
+    }
+    print(&ls_lines(&entries, long).join("\n"))
+}
+
+/// One line per entry: just the URI, or size, update time, storage class and URI with `long`
+fn ls_lines(entries: &[ListedEntry], long: bool) -> Vec<String> {
+    entries
+        .iter()
+        .map(|entry| match (&entry.metadata, long) {
+            (Some(metadata), true) => format!(
+                "{:>12}  {:<30}  {:<10}  {}",
+                metadata.size,
+                metadata.updated.as_deref().unwrap_or("-"),
+                metadata.storage_class.as_deref().unwrap_or("-"),
+                entry.uri
+            ),
+            (None, true) => format!("{:>12}  {:<30}  {:<10}  {}", "DIR", "", "", entry.uri),
+            (_, false) => entry.uri.clone(),
+        })
+        .collect()
+}
+
+fn stat(uri: &CloudUri, json: bool) -> Result<(), Action> {
+    if uri.is_prefix() {
+        return Err(Action::Error(format!("{uri} is a prefix, not an object")));
+    }
+    let metadata = storage::object_metadata(uri)?;
+    if json {
+        let entry = ListedEntry { uri: uri.to_string(), prefix: false, metadata: Some(metadata) };
+        return print(&to_json(&entry)?);
+    }
+    print(&stat_lines(uri, metadata).join("\n"))
+}
+
+/// The URI, then one indented `Name:  value` line per field the provider reported
+fn stat_lines(uri: &CloudUri, metadata: ObjectMetadata) -> Vec<String> {
+    let fields = [
+        ("Size", Some(format!("{} ({})", metadata.size, util::human_bytes(metadata.size)))),
+        ("Updated", metadata.updated),
+        ("Content type", metadata.content_type),
+        ("Storage class", metadata.storage_class),
+        ("CRC32C", metadata.crc32c),
+        ("MD5", metadata.md5),
+        ("ETag", metadata.etag),
+    ];
+    let mut lines = vec![format!("{uri}:")];
+    lines.extend(
+        fields
+            .into_iter()
+            .filter_map(|(name, value)| value.map(|value| format!("    {:<16}{value}", format!("{name}:")))),
+    );
+    lines
+}
+
+fn cat(uris: &[String]) -> Result<(), Action> {
+    let stdout = io::stdout();
+    let mut out = stdout.lock();
+    for uri in uris {
+        let uri = CloudUri::parse(uri)?;
+        if uri.is_prefix() {
+            return Err(Action::Error(format!("{uri} is a prefix, not an object")));
+        }
+        transfer::read_object(&uri, &mut out).map_err(|e| Action::Error(format!("Could not read {uri}: {e}")))?;
+    }
+    out.flush().map_err(|e| Action::Error(e.to_string()))
+}
+
+/// Queues the copy on a transfer queue that is never persisted and waits for it, so multipart
+/// uploads and checksum verification work as they do in the TUI
+fn cp(source: &str, destination: &str, recursive: bool, read_only: bool) -> Result<(), Action> {
+    let source = Endpoint::from(source);
+    let destination = Endpoint::from(destination);
+    if read_only && matches!(destination, Endpoint::Remote(_)) {
+        return Err(Action::Error("Read-only: refusing to copy into a bucket".to_string()));
+    }
+
+    let queue = TransferQueue::ephemeral();
+    queue_copy(&queue, source, destination, recursive)?;
+    if queue.snapshot().is_empty() {
+        return Err(Action::Error("Nothing to copy".to_string()));
+    }
+
+    let mut reported: HashSet<usize> = HashSet::new();
+    let mut failed = 0;
+    loop {
+        queue.start_queued();
+        let idle = queue.is_idle();
+        for job in queue.snapshot() {
+            if reported.contains(&job.id) {
+                continue;
+            }
+            match &job.state {
+                TransferState::Done => eprintln!("Copied {} -> {}", job.source, job.destination),
+                TransferState::Failed(reason) => {
+                    failed += 1;
+                    eprintln!("Failed {} -> {}: {reason}", job.source, job.destination);
+                }
+                _ => continue,
+            }
+            reported.insert(job.id);
+        }
+        if idle {
+            break;
+        }
+        thread::sleep(POLL_INTERVAL);
+    }
+
+    match failed {
+        0 => Ok(()),
+        failed => Err(Action::Error(format!("{failed} of {} copies failed", reported.len()))),
+    }
+}
+
+/// `source` under its own name in the local `directory`, unless its name would leave the directory
+fn download_target(directory: &Path, source: &CloudUri) -> Result<PathBuf, Action> {
+    transfer::local_destination(directory, &source.file_name())
+        .ok_or_else(|| Action::Error(format!("{source} has no name that stays inside {}", directory.display())))
+}
+
+/// Names the targets like `cp`: a local directory or a URI ending in '/' receives the source under
+/// its own name, anything else is the new name
+fn queue_copy(queue: &TransferQueue, source: Endpoint, destination: Endpoint, recursive: bool) -> Result<(), Action> {
+    match (source, destination) {
+        (Endpoint::Remote(source), Endpoint::Local(destination)) if source.is_prefix() => {
+            require_recursive(recursive, &source.to_string())?;
+            let base = if destination.is_dir() {
+                download_target(&destination, &source)?
+            } else {
+                destination
+            };
+            for entry in storage::list_recursive(&source)? {
+                let relative = entry.uri.relative_to(&source).unwrap_or_else(|| entry.uri.file_name());
+                if relative.is_empty() || relative.ends_with('/') {
+                    continue;
+                }
+                match transfer::local_destination(&base, &relative) {
+                    Some(local) => {
+                        queue.enqueue(TransferKind::Download, Endpoint::Remote(entry.uri), Endpoint::Local(local), Some(entry.size))
+                    }
+                    None => eprintln!("Skipped {}: its key would land outside {}", entry.uri, base.display()),
+                }
+            }
+            Ok(())
+        }
+        (Endpoint::Remote(source), Endpoint::Local(destination)) => {
+            let target = if destination.is_dir() {
+                download_target(&destination, &source)?
+            } else {
+                destination
+            };
+            queue.enqueue(TransferKind::Download, Endpoint::Remote(source), Endpoint::Local(target), None);
+            Ok(())
+        }
+        (Endpoint::Local(source), Endpoint::Remote(destination)) if source.is_dir() => {
+            require_recursive(recursive, &source.display().to_string())?;
+            let base = prefix_destination(&destination)?.join(&format!("{}/", local_name(&source)));
+            for file in transfer::local_files(&source)? {
+                let relative = file.strip_prefix(&source).unwrap_or(&file).to_string_lossy().to_string();
+                queue.enqueue(TransferKind::Upload, Endpoint::Local(file.clone()), Endpoint::Remote(base.join(&relative)), None);
+            }
+            Ok(())
+        }
+        (Endpoint::Local(source), Endpoint::Remote(destination)) => {
+            if !source.is_file() {
+                return Err(Action::Error(format!("No such file or directory: {}", source.display())));
+            }
+            let target = if destination.is_prefix() {
+                destination.join(&local_name(&source))
+            } else {
+                destination
+            };
+            queue.enqueue(TransferKind::Upload, Endpoint::Local(source), Endpoint::Remote(target), None);
+            Ok(())
+        }
+        (Endpoint::Remote(source), Endpoint::Remote(destination)) if source.is_prefix() => {
+            require_recursive(recursive, &source.to_string())?;
+            let base = prefix_destination(&destination)?.join(&format!("{}/", source.file_name()));
+            if base.relative_to(&source).is_some() {
+                return Err(Action::Error(format!("Cannot copy {source} into itself")));
+            }
+            for entry in storage::list_recursive(&source)? {
+                let relative = entry.uri.relative_to(&source).unwrap_or_else(|| entry.uri.file_name());
+                if relative.is_empty() || relative.ends_with('/') {
+                    continue;
+                }
+                queue.enqueue(TransferKind::Copy, Endpoint::Remote(entry.uri), Endpoint::Remote(base.join(&relative)), Some(entry.size));
+            }
+            Ok(())
+        }
+        (Endpoint::Remote(source), Endpoint::Remote(destination)) => {
+            let target = if destination.is_prefix() {
+                destination.join(&source.file_name())
+            } else {
+                destination
+            };
+            if target == source {
+                return Err(Action::Error(format!("{source} is already there")));
+            }
+            queue.enqueue(TransferKind::Copy, Endpoint::Remote(source), Endpoint::Remote(target), None);
+            Ok(())
+        }
+        (Endpoint::Local(_), Endpoint::Local(_)) => {
+            Err(Action::Error("One side of a copy has to be a URI, e.g. gs://bucket/key".to_string()))
+        }
+    }
+}
+
+fn require_recursive(recursive: bool, source: &str) -> Result<(), Action> {
+    if recursive {
+        Ok(())
+    } else {
+        Err(Action::Error(format!("{source} is a directory, copy it with --recursive")))
+    }
+}
+
+/// A directory only goes into a prefix, otherwise its files would be renamed onto one key
+fn prefix_destination(destination: &CloudUri) -> Result<CloudUri, Action> {
+    if destination.is_prefix() {
+        Ok(destination.clone())
+    } else {
+        Err(Action::Error(format!("Copy a directory into a prefix ending in '/', not {destination}")))
+    }
+}
+
+fn local_name(path: &Path) -> String {
+    path.file_name()
+        .map(|name| name.to_string_lossy().to_string())
+        .unwrap_or_default()
+}
+
+fn to_json(value: &impl Serialize) -> Result<String, Action> {
+    serde_json::to_string_pretty(value).map_err(|e| Action::Error(format!("Could not serialize output: {e}")))
+}
+
+/// Writes `text` and a newline to stdout; a reader that went away, like `head`, is not an error
+fn print(text: &str) -> Result<(), Action> {
+    if text.is_empty() {
+        return Ok(());
+    }
+    match writeln!(io::stdout().lock(), "{text}") {
+        Err(e) if e.kind() != io::ErrorKind::BrokenPipe => Err(Action::Error(e.to_string())),
+        _ => Ok(()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+    use std::path::PathBuf;
+
+    use super::*;
+
+    /// A fresh scratch directory for one test
+    fn scratch(name: &str) -> Result<PathBuf, Action> {
+        let dir = std::env::temp_dir().join(format!("headless-{}-{name}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).map_err(|e| Action::Error(e.to_string()))?;
+        Ok(dir)
+    }
+
+    fn write(path: &Path) -> Result<(), Action> {
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent).map_err(|e| Action::Error(e.to_string()))?;
+        }
+        fs::write(path, b"data").map_err(|e| Action::Error(e.to_string()))
+    }
+
+    /// Source and destination of every job `cp SOURCE DESTINATION` would queue
+    fn targets(source: Endpoint, destination: Endpoint, recursive: bool) -> Result<Vec<(String, String)>, Action> {
+        let queue = TransferQueue::ephemeral();
+        queue_copy(&queue, source, destination, recursive)?;
+        let mut jobs: Vec<(String, String)> = queue
+            .snapshot()
+            .into_iter()
+            .map(|job| (job.source.to_string(), job.destination.to_string()))
+            .collect();
+        jobs.sort();
+        Ok(jobs)
+    }
+
+    fn remote(uri: &str) -> Result<Endpoint, Action> {
+        Ok(Endpoint::Remote(CloudUri::parse(uri)?))
+    }
+
+    #[test]
+    fn upload_into_a_prefix_keeps_the_file_name() -> Result<(), Action> {
+        let dir = scratch("upload-file")?;
+        let file = dir.join("report.csv");
+        write(&file)?;
+
+        let source = file.display().to_string();
+        assert_eq!(
+            targets(Endpoint::Local(file.clone()), remote("gs://bucket/in/")?, false)?,
+            vec![(source.clone(), "gs://bucket/in/report.csv".to_string())]
+        );
+        assert_eq!(
+            targets(Endpoint::Local(file), remote("gs://bucket/in/renamed.csv")?, false)?,
+            vec![(source, "gs://bucket/in/renamed.csv".to_string())]
+        );
+        let _ = fs::remove_dir_all(dir);
+        Ok(())
+    }
+
+    #[test]
+    fn directory_upload_lands_under_its_own_name() -> Result<(), Action> {
+        let dir = scratch("upload-dir")?;
+        let source = dir.join("photos");
+        write(&source.join("a.jpg"))?;
+        write(&source.join("2024").join("b.jpg"))?;
+
+        assert!(targets(Endpoint::Local(source.clone()), remote("s3://bucket/backup/")?, false).is_err());
+        assert!(targets(Endpoint::Local(source.clone()), remote("s3://bucket/backup")?, true).is_err());
+        let destinations: Vec<String> = targets(Endpoint::Local(source), remote("s3://bucket/backup/")?, true)?
+            .into_iter()
+            .map(|(_, destination)| destination)
+            .collect();
+        assert_eq!(
+            destinations,
+            vec!["s3://bucket/backup/photos/2024/b.jpg".to_string(), "s3://bucket/backup/photos/a.jpg".to_string()]
+        );
+        let _ = fs::remove_dir_all(dir);
+        Ok(())
+    }
+
+    #[test]
+    fn download_into_a_directory_keeps_the_object_name() -> Result<(), Action> {
+        let dir = scratch("download")?;
+
+        assert_eq!(
+            targets(remote("gs://bucket/dir/key.txt")?, Endpoint::Local(dir.clone()), false)?,
+            vec![("gs://bucket/dir/key.txt".to_string(), dir.join("key.txt").display().to_string())]
+        );
+        let renamed = dir.join("local.txt");
+        assert_eq!(
+            targets(remote("gs://bucket/dir/key.txt")?, Endpoint::Local(renamed.clone()), false)?,
+            vec![("gs://bucket/dir/key.txt".to_string(), renamed.display().to_string())]
+        );
+        let _ = fs::remove_dir_all(dir);
+        Ok(())
+    }
+
+    #[test]
+    fn bucket_to_bucket_copies_are_named_like_cp() -> Result<(), Action> {
+        assert_eq!(
+            targets(remote("gs://a/dir/key")?, remote("s3://b/in/")?, false)?,
+            vec![("gs://a/dir/key".to_string(), "s3://b/in/key".to_string())]
+        );
+        assert_eq!(
+            targets(remote("gs://a/dir/key")?, remote("gs://a/other")?, false)?,
+            vec![("gs://a/dir/key".to_string(), "gs://a/other".to_string())]
+        );
+        assert!(targets(remote("gs://a/dir/key")?, remote("gs://a/dir/")?, false).is_err());
+        assert!(targets(Endpoint::Local(PathBuf::from("a")), Endpoint::Local(PathBuf::from("b")), false).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn ls_prints_uris_or_columns() {
+        let entries = vec![
+            ListedEntry { uri: "gs://bucket/dir/".to_string(), prefix: true, metadata: None },
+            ListedEntry {
+                uri: "gs://bucket/key".to_string(),
+                prefix: false,
+                metadata: Some(ObjectMetadata {
+                    size: 1234,
+                    updated: Some("2024-01-01T00:00:00Z".to_string()),
+                    ..ObjectMetadata::default()
+                }),
+            },
+        ];
+        assert_eq!(ls_lines(&entries, false), vec!["gs://bucket/dir/", "gs://bucket/key"]);
+        assert_eq!(
+            ls_lines(&entries, true),
+            vec![
+                format!("{:>12}  {:<30}  {:<10}  gs://bucket/dir/", "DIR", "", ""),
+                format!("{:>12}  {:<30}  {:<10}  gs://bucket/key", 1234, "2024-01-01T00:00:00Z", "-"),
+            ]
+        );
+    }
+
+    #[test]
+    fn stat_prints_the_fields_that_are_known() -> Result<(), Action> {
+        let metadata = ObjectMetadata {
+            size: 2048,
+            content_type: Some("text/plain".to_string()),
+            etag: Some("abc".to_string()),
+            ..ObjectMetadata::default()
+        };
+        assert_eq!(
+            stat_lines(&CloudUri::parse("s3://bucket/key")?, metadata),
+            vec![
+                "s3://bucket/key:",
+                "    Size:           2048 (2.0 KiB)",
+                "    Content type:   text/plain",
+                "    ETag:           abc",
+            ]
+        );
+        Ok(())
+    }
+}
@@ -1,6 +1,7 @@
 use crossterm::event;
 use serde::Deserialize;
 use std::fmt;
+use std::str::FromStr;
 
 #[cfg(test)]
 use serde::Serialize;
@@ -100,6 +101,71 @@ impl Key {
     }
 }
 
+impl FromStr for Key {
+    type Err = String;
+
+    /// Parses the names used in the config file: `j`, `Space`, `Enter`, `F5`, `Ctrl+f`, `Alt+x`.
+    /// What `Display` prints, like `<Ctrl+f>` or `↑`, is read back as well.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let name = match s.strip_prefix('<').and_then(|rest| rest.strip_suffix('>')) {
+            Some(inner) if !inner.is_empty() => inner,
+            _ => s,
+        };
+        let single = |text: &str| -> Option<char> {
+            let mut chars = text.chars();
+            match (chars.next(), chars.next()) {
+                (Some(c), None) => Some(c),
+                _ if text.eq_ignore_ascii_case("space") => Some(' '),
+                _ => None,
+            }
+        };
+
+        // the arrows `Display` prints are single characters too, but never meant literally
+        let arrow = match name {
+            "\u{2190}" => Some(Key::Left),
+            "\u{2192}" => Some(Key::Right),
+            "\u{2191}" => Some(Key::Up),
+            "\u{2193}" => Some(Key::Down),
+            _ => None,
+        };
+        if let Some(key) = arrow {
+            return Ok(key);
+        }
+        if let Some(c) = single(name) {
+            return Ok(Key::Char(c));
+        }
+        if let Some((modifier, rest)) = name.split_once(['+', '-']) {
+            let c = single(rest).ok_or_else(|| format!("'{s}' needs a single character after {modifier}"))?;
+            return match modifier.to_lowercase().as_str() {
+                "ctrl" | "c" => Ok(Key::Ctrl(c.to_ascii_lowercase())),
+                "alt" | "a" | "m" => Ok(Key::Alt(c)),
+                _ => Err(format!("Unknown modifier '{modifier}' in '{s}', use Ctrl or Alt")),
+            };
+        }
+        let key = match name.to_lowercase().as_str() {
+            "enter" | "return" => Key::Enter,
+            "tab" => Key::Tab,
+            "backspace" => Key::Backspace,
+            "esc" | "escape" => Key::Esc,
+            "left" => Key::Left,
+            "right" => Key::Right,
+            "up" => Key::Up,
+            "down" => Key::Down,
+            "ins" | "insert" => Key::Ins,
+            "delete" | "del" => Key::Delete,
+            "home" => Key::Home,
+            "end" => Key::End,
+            "pageup" => Key::PageUp,
+            "pagedown" => Key::PageDown,
+            function => match function.strip_prefix('f').and_then(|n| n.parse::<u8>().ok()) {
+                Some(n) if n <= 12 => Key::from_f(n),
+                _ => return Err(format!("Unknown key '{s}'")),
+            },
+        };
+        Ok(key)
+    }
+}
+
 impl fmt::Display for Key {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         match *self {
@@ -212,3 +278,97 @@ impl From<event::KeyEvent> for Key {
         }
     }
 }
+
+/// The event a key is read back from, for keys the app sends to the components itself
+impl From<Key> for event::KeyEvent {
+    fn from(key: Key) -> Self {
+        let (code, modifiers) = match key {
+            Key::Enter => (event::KeyCode::Enter, event::KeyModifiers::NONE),
+            Key::Tab => (event::KeyCode::Tab, event::KeyModifiers::NONE),
+            Key::Backspace => (event::KeyCode::Backspace, event::KeyModifiers::NONE),
+            Key::Esc => (event::KeyCode::Esc, event::KeyModifiers::NONE),
+            Key::Left => (event::KeyCode::Left, event::KeyModifiers::NONE),
+            Key::Right => (event::KeyCode::Right, event::KeyModifiers::NONE),
+            Key::Up => (event::KeyCode::Up, event::KeyModifiers::NONE),
+            Key::Down => (event::KeyCode::Down, event::KeyModifiers::NONE),
+            Key::Ins => (event::KeyCode::Insert, event::KeyModifiers::NONE),
+            Key::Delete => (event::KeyCode::Delete, event::KeyModifiers::NONE),
+            Key::Home => (event::KeyCode::Home, event::KeyModifiers::NONE),
+            Key::End => (event::KeyCode::End, event::KeyModifiers::NONE),
+            Key::PageUp => (event::KeyCode::PageUp, event::KeyModifiers::NONE),
+            Key::PageDown => (event::KeyCode::PageDown, event::KeyModifiers::NONE),
+            Key::F0 => (event::KeyCode::F(0), event::KeyModifiers::NONE),
+            Key::F1 => (event::KeyCode::F(1), event::KeyModifiers::NONE),
+            Key::F2 => (event::KeyCode::F(2), event::KeyModifiers::NONE),
+            Key::F3 => (event::KeyCode::F(3), event::KeyModifiers::NONE),
+            Key::F4 => (event::KeyCode::F(4), event::KeyModifiers::NONE),
+            Key::F5 => (event::KeyCode::F(5), event::KeyModifiers::NONE),
+            Key::F6 => (event::KeyCode::F(6), event::KeyModifiers::NONE),
+            Key::F7 => (event::KeyCode::F(7), event::KeyModifiers::NONE),
+            Key::F8 => (event::KeyCode::F(8), event::KeyModifiers::NONE),
+            Key::F9 => (event::KeyCode::F(9), event::KeyModifiers::NONE),
+            Key::F10 => (event::KeyCode::F(10), event::KeyModifiers::NONE),
+            Key::F11 => (event::KeyCode::F(11), event::KeyModifiers::NONE),
+            Key::F12 => (event::KeyCode::F(12), event::KeyModifiers::NONE),
+            Key::Char(c) => (event::KeyCode::Char(c), event::KeyModifiers::NONE),
+            Key::Ctrl(c) => (event::KeyCode::Char(c), event::KeyModifiers::CONTROL),
+            Key::Alt(c) => (event::KeyCode::Char(c), event::KeyModifiers::ALT),
+            Key::Unknown => (event::KeyCode::Null, event::KeyModifiers::NONE),
+        };
+        event::KeyEvent::new(code, modifiers)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn config_names_parse() {
+        let cases = [
+            ("j", Key::Char('j')),
+            ("D", Key::Char('D')),
+            ("Space", Key::Char(' ')),
+            ("/", Key::Char('/')),
+            ("-", Key::Char('-')),
+            ("Enter", Key::Enter),
+            ("esc", Key::Esc),
+            ("PageDown", Key::PageDown),
+            ("F5", Key::F5),
+            ("Ctrl+f", Key::Ctrl('f')),
+            ("Ctrl+F", Key::Ctrl('f')),
+            ("C-x", Key::Ctrl('x')),
+            ("Alt+x", Key::Alt('x')),
+            ("M-X", Key::Alt('X')),
+            ("Ctrl+Space", Key::Ctrl(' ')),
+        ];
+        for (name, key) in cases {
+            assert_eq!(name.parse::<Key>(), Ok(key), "{name}");
+        }
+    }
+
+    #[test]
+    fn what_display_prints_reads_back() {
+        let keys = [
+            Key::Char('j'),
+            Key::Char(' '),
+            Key::Ctrl('f'),
+            Key::Alt(' '),
+            Key::Enter,
+            Key::Backspace,
+            Key::Up,
+            Key::Left,
+            Key::F12,
+        ];
+        for key in keys {
+            assert_eq!(key.to_string().parse::<Key>(), Ok(key), "{key}");
+        }
+    }
+
+    #[test]
+    fn unknown_names_are_errors() {
+        for name in ["", "Enterr", "F13", "Hyper+x", "Ctrl+ab", "Alt+"] {
+            assert!(name.parse::<Key>().is_err(), "{name}");
+        }
+    }
+}
@@ -12,6 +12,10 @@ lazy_static! {
         std::env::var(format!("{}_DATA", PROJECT_NAME.clone()))
             .ok()
             .map(PathBuf::from);
+    pub static ref CONFIG_FOLDER: Option<PathBuf> =
+        std::env::var(format!("{}_CONFIG", PROJECT_NAME.clone()))
+            .ok()
+            .map(PathBuf::from);
     pub static ref LOG_ENV: String = format!("{}_LOGLEVEL", PROJECT_NAME.clone());
     pub static ref LOG_FILE: String = format!("{}.log", env!("CARGO_PKG_NAME"));
 }
@@ -31,17 +35,32 @@ pub fn get_data_dir() -> PathBuf {
     directory
 }
 
-pub fn initialize_logging() -> Result<()> {
+pub fn get_config_dir() -> PathBuf {
+    let directory = if let Some(s) = CONFIG_FOLDER.clone() {
+        s
+    } else if let Some(proj_dirs) = project_directory() {
+        proj_dirs.config_local_dir().to_path_buf()
+    } else {
+        PathBuf::from(".").join(".config")
+    };
+    directory
+}
+
+/// `log_level` is either a level such as `debug` for this crate, or a full tracing filter;
+/// without it `RUST_LOG` and then the `_LOGLEVEL` variable are used
+pub fn initialize_logging(log_level: Option<&str>) -> Result<()> {
     let directory = get_data_dir();
     std::fs::create_dir_all(directory.clone())?;
     let log_path = directory.join(LOG_FILE.clone());
     let log_file = std::fs::File::create(log_path)?;
-    std::env::set_var(
-        "RUST_LOG",
-        std::env::var("RUST_LOG")
+    let filter = match log_level {
+        Some(level) if level.contains('=') => level.to_string(),
+        Some(level) => format!("{}={level}", env!("CARGO_CRATE_NAME")),
+        None => std::env::var("RUST_LOG")
             .or_else(|_| std::env::var(LOG_ENV.clone()))
             .unwrap_or_else(|_| format!("{}=info", env!("CARGO_CRATE_NAME"))),
-    );
+    };
+    std::env::set_var("RUST_LOG", filter);
     let file_subscriber = tracing_subscriber::fmt::layer()
         .with_file(true)
         .with_line_number(true)
@@ -3,24 +3,68 @@
 #![deny(clippy::panic)]
 #![deny(unused_must_use)]
 
+use clap::Parser;
 use logging::initialize_logging;
 use std::result::Result;
+use std::time::Duration;
 
 mod action;
 mod app;
+mod cli;
 mod components;
 mod config;
+mod headless;
 mod key;
 mod logging;
+mod storage;
+mod transfer;
 mod tui;
 mod util;
 
 use crate::app::App;
+use crate::cli::{Cli, StartAt};
+use crate::components::results_pager;
+use crate::config::key_config::{self, KeyConfig};
+use crate::config::key_sequences::KeySequences;
+use crate::config::session;
+use crate::config::settings::Settings;
+use crate::config::theme::{self, Theme, ThemeName};
 
 fn main() -> Result<(), String> {
-    match initialize_logging() {
+    let cli = Cli::parse();
+    let settings = Settings::load(cli.config.as_deref())?;
+    let log_level = cli.log_level.or(settings.log_level);
+    match initialize_logging(log_level.as_deref()) {
         Ok(_) => {
+            if let Some(command) = cli.command {
+                let read_only = cli.read_only || settings.read_only.unwrap_or_default();
+                // the configured default connection is for the TUI; a one-off command only switches when asked to
+                return headless::run(command, cli.connection, read_only);
+            }
+            if let Some(results_per_page) = cli.results_per_page.or(settings.results_per_page) {
+                results_pager::set_results_per_page(results_per_page);
+            }
+            // components copy their bindings and theme when they are created, so this comes before the app
+            let (key_config, mut key_problems) = KeyConfig::with_overrides(&settings.keys);
+            key_config::set_key_config(key_config);
+            let timeout = settings.sequence_timeout_ms.map(Duration::from_millis);
+            let (key_sequences, sequence_problems) = KeySequences::with_overrides(&settings.sequences, &key_config, timeout);
+            key_problems.extend(sequence_problems);
+            theme::set_theme(Theme::named(ThemeName::resolve(cli.theme, settings.theme)));
             let mut app = App::new();
+            app.key_problems = key_problems;
+            app.key_sequences = key_sequences;
+            app.tick_rate = settings.tick_rate.unwrap_or(tui::DEFAULT_TICK_RATE);
+            app.frame_rate = settings.frame_rate.unwrap_or(tui::DEFAULT_FRAME_RATE);
+            app.config.read_only = cli.read_only || settings.read_only.unwrap_or_default();
+            if !cli.fresh && settings.restore_session.unwrap_or(true) {
+                app.session = session::load();
+            }
+            app.start_at = StartAt {
+                uri: cli.uri,
+                provider: cli.provider.or(settings.provider).map(Into::into),
+                connection: cli.connection.or(settings.connection),
+            };
             app.run()?;
             Ok(())
         }
@@ -0,0 +1,174 @@
+use std::fmt;
+
+use crate::action::Action;
+use crate::config::cloud_provider_config::cloud_provider_kind::CloudProviderKind;
+
+/// A location within object storage, e.g. `gs://bucket/a/b/`, `s3://bucket/key`
+/// or `az://account/container/blob`.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct CloudUri {
+    pub kind: CloudProviderKind,
+    /// Storage account, only used by Azure
+    pub account: Option<String>,
+    /// Bucket (GCS/S3) or container (Azure)
+    pub bucket: String,
+    /// Object key or prefix, without a leading slash
+    pub path: String,
+}
+
+impl CloudUri {
+    pub fn parse(uri: &str) -> Result<Self, Action> {
+        let (scheme, rest) = match uri.split_once("://") {
+            Some(parts) => parts,
+            None => return Err(Action::Error(format!("Not a cloud storage URI: {uri}"))),
+        };
+
+        let kind = match scheme {
+            "gs" => CloudProviderKind::Gcs,
+            "s3" => CloudProviderKind::S3,
+            "az" => CloudProviderKind::Azure,
+            _ => return Err(Action::Error(format!("Unknown URI scheme '{scheme}://' in {uri}"))),
+        };
+
+        let mut segments = rest.splitn(2, '/');
+        let first = segments.next().unwrap_or_default().to_string();
+        let remainder = segments.next().unwrap_or_default().to_string();
+
+        let (account, bucket, path) = match kind {
+            CloudProviderKind::Azure => {
+                let mut container_split = remainder.splitn(2, '/');
+                let container = container_split.next().unwrap_or_default().to_string();
+                let path = container_split.next().unwrap_or_default().to_string();
+                (Some(first), container, path)
+            }
+            _ => (None, first, remainder),
+        };
+
+        if bucket.is_empty() {
+            return Err(Action::Error(format!("No bucket given in {uri}")));
+        }
+
+        Ok(Self { kind, account, bucket, path })
+    }
+
+    pub fn scheme(&self) -> &str {
+        match self.kind {
+            CloudProviderKind::Gcs => "gs",
+            CloudProviderKind::S3 => "s3",
+            CloudProviderKind::Azure => "az",
+        }
+    }
+
+    /// A bucket root or anything ending with `/` is treated as a directory
+    pub fn is_prefix(&self) -> bool {
+        self.path.is_empty() || self.path.ends_with('/')
+    }
+
+    /// Last segment of the path, ignoring a trailing `/`
+    pub fn file_name(&self) -> String {
+        let trimmed = self.path.trim_end_matches('/');
+        match trimmed.rsplit_once('/') {
+            Some((_, name)) => name.to_string(),
+            None if trimmed.is_empty() => self.bucket.clone(),
+            None => trimmed.to_string(),
+        }
+    }
+
+    /// The prefix containing this object (or this prefix's parent)
+    pub fn parent(&self) -> Self {
+        let trimmed = self.path.trim_end_matches('/');
+        let path = match trimmed.rsplit_once('/') {
+            Some((parent, _)) => format!("{parent}/"),
+            None => String::new(),
+        };
+        Self { path, ..self.clone() }
+    }
+
+    /// Append a relative key to this prefix
+    pub fn join(&self, relative: &str) -> Self {
+        let mut path = self.path.clone();
+        if !path.is_empty() && !path.ends_with('/') {
+            path.push('/');
+        }
+        path.push_str(relative.trim_start_matches('/'));
+        Self { path, ..self.clone() }
+    }
+
+    /// Path of `self` relative to `prefix`, if `self` lives under it
+    pub fn relative_to(&self, prefix: &CloudUri) -> Option<String> {
+        if self.kind != prefix.kind || self.bucket != prefix.bucket || self.account != prefix.account {
+            return None;
+        }
+        self.path.strip_prefix(&prefix.path).map(|rel| rel.to_string())
+    }
+
+    /// The URI of the bucket (or container) root
+    pub fn root(&self) -> Self {
+        Self { path: String::new(), ..self.clone() }
+    }
+}
+
+impl fmt::Display for CloudUri {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match &self.account {
+            Some(account) => write!(f, "{}://{}/{}/{}", self.scheme(), account, self.bucket, self.path),
+            None => write!(f, "{}://{}/{}", self.scheme(), self.bucket, self.path),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_gcs_prefix() -> Result<(), Action> {
+        let uri = CloudUri::parse("gs://bucket/a/b/")?;
+        assert_eq!(uri.kind, CloudProviderKind::Gcs);
+        assert_eq!(uri.account, None);
+        assert_eq!(uri.bucket, "bucket");
+        assert_eq!(uri.path, "a/b/");
+        assert!(uri.is_prefix());
+        assert_eq!(uri.file_name(), "b");
+        Ok(())
+    }
+
+    #[test]
+    fn parses_s3_object() -> Result<(), Action> {
+        let uri = CloudUri::parse("s3://bucket/dir/key.txt")?;
+        assert_eq!(uri.kind, CloudProviderKind::S3);
+        assert_eq!(uri.path, "dir/key.txt");
+        assert!(!uri.is_prefix());
+        assert_eq!(uri.parent().to_string(), "s3://bucket/dir/");
+        Ok(())
+    }
+
+    #[test]
+    fn parses_azure_account_and_container() -> Result<(), Action> {
+        let uri = CloudUri::parse("az://account/container/dir/blob")?;
+        assert_eq!(uri.kind, CloudProviderKind::Azure);
+        assert_eq!(uri.account.as_deref(), Some("account"));
+        assert_eq!(uri.bucket, "container");
+        assert_eq!(uri.path, "dir/blob");
+        assert_eq!(uri.to_string(), "az://account/container/dir/blob");
+        Ok(())
+    }
+
+    #[test]
+    fn bucket_root_is_a_prefix() -> Result<(), Action> {
+        for text in ["gs://bucket", "gs://bucket/"] {
+            let uri = CloudUri::parse(text)?;
+            assert_eq!(uri.path, "");
+            assert!(uri.is_prefix());
+            assert_eq!(uri.to_string(), "gs://bucket/");
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn rejects_what_is_not_a_bucket_uri() {
+        for text in ["bucket/key", "ftp://host/file", "gs://", "s3:///key", "az://account"] {
+            assert!(CloudUri::parse(text).is_err(), "{text} should not parse");
+        }
+    }
+}
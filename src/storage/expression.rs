@@ -0,0 +1,261 @@
+use std::cmp::Ordering;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::util;
+
+use super::ObjectMetadata;
+
+/// One metadata condition of a filter expression
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Predicate {
+    /// `size>1GB`, `size<=500KiB`
+    Size(Comparison, u64),
+    /// `updated<7d` (changed within the last 7 days), `updated>2024-01-01`
+    Updated(Comparison, UpdatedBound),
+    /// `class:NEARLINE`
+    Class(String),
+    /// `type:application/json`, `type:image/` for every image type
+    Type(String),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Comparison {
+    Less,
+    LessOrEqual,
+    Equal,
+    GreaterOrEqual,
+    Greater,
+}
+
+impl Comparison {
+    fn holds(self, ordering: Ordering) -> bool {
+        match self {
+            Comparison::Less => ordering.is_lt(),
+            Comparison::LessOrEqual => ordering.is_le(),
+            Comparison::Equal => ordering.is_eq(),
+            Comparison::GreaterOrEqual => ordering.is_ge(),
+            Comparison::Greater => ordering.is_gt(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum UpdatedBound {
+    /// Seconds before now; `<` means more recent than that
+    Age(u64),
+    /// Seconds since the epoch; `<` means before that date
+    Date(u64),
+}
+
+/// A filter as typed: the words matched against names, and the metadata conditions next to them
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct FilterExpression {
+    pub text: String,
+    pub predicates: Vec<Predicate>,
+}
+
+impl FilterExpression {
+    pub fn parse(input: &str) -> Result<Self, String> {
+        let mut words = vec![];
+        let mut predicates = vec![];
+        for token in input.split_whitespace() {
+            match Predicate::parse(token) {
+                Some(predicate) => predicates.push(predicate?),
+                None => words.push(token),
+            }
+        }
+        Ok(Self { text: words.join(" "), predicates })
+    }
+
+    /// An expression that only matches names, for filters over things without metadata
+    pub fn plain(input: &str) -> Self {
+        Self { text: input.to_string(), predicates: vec![] }
+    }
+
+    /// Whether `metadata` holds everything beyond the size and update time, which every listing has
+    pub fn needs_full_metadata(&self) -> bool {
+        self.predicates
+            .iter()
+            .any(|predicate| matches!(predicate, Predicate::Class(_) | Predicate::Type(_)))
+    }
+
+    pub fn matches(&self, metadata: &ObjectMetadata) -> bool {
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|since| since.as_secs())
+            .unwrap_or_default();
+        self.predicates.iter().all(|predicate| predicate.matches(metadata, now))
+    }
+}
+
+impl Predicate {
+    /// `None` when `token` is not a metadata condition at all, so it is matched against names
+    fn parse(token: &str) -> Option<Result<Self, String>> {
+        if let Some(class) = token.strip_prefix("class:") {
+            return Some(match class {
+                "" => Err("class: needs a storage class, e.g. class:NEARLINE".to_string()),
+                class => Ok(Predicate::Class(class.to_string())),
+            });
+        }
+        if let Some(content_type) = token.strip_prefix("type:") {
+            return Some(match content_type {
+                "" => Err("type: needs a content type, e.g. type:application/json".to_string()),
+                content_type => Ok(Predicate::Type(content_type.to_string())),
+            });
+        }
+
+        let (field, rest) = ["size", "updated"]
+            .iter()
+            .find_map(|field| token.strip_prefix(field).map(|rest| (*field, rest)))?;
+        let (comparison, value) = match rest.as_bytes() {
+            [b'<', b'=', ..] => (Comparison::LessOrEqual, &rest[2..]),
+            [b'>', b'=', ..] => (Comparison::GreaterOrEqual, &rest[2..]),
+            [b'<', ..] => (Comparison::Less, &rest[1..]),
+            [b'>', ..] => (Comparison::Greater, &rest[1..]),
+            [b'=', ..] => (Comparison::Equal, &rest[1..]),
+            // e.g. a file called "sizes.csv"
+            _ => return None,
+        };
+
+        Some(match field {
+            "size" => parse_size(value)
+                .map(|size| Predicate::Size(comparison, size))
+                .ok_or(format!("Invalid size '{value}' in {token}, e.g. size>1GB or size<=500KiB")),
+            _ => parse_updated(value)
+                .map(|bound| Predicate::Updated(comparison, bound))
+                .ok_or(format!("Invalid time '{value}' in {token}, e.g. updated<7d or updated>2024-01-01")),
+        })
+    }
+
+    fn matches(&self, metadata: &ObjectMetadata, now: u64) -> bool {
+        match self {
+            Predicate::Size(comparison, size) => comparison.holds(metadata.size.cmp(size)),
+            Predicate::Updated(comparison, bound) => {
+                let Some(updated) = metadata.updated.as_deref().and_then(util::parse_timestamp) else {
+                    return false;
+                };
+                match bound {
+                    UpdatedBound::Age(age) => comparison.holds(now.saturating_sub(updated).cmp(age)),
+                    UpdatedBound::Date(date) => comparison.holds(updated.cmp(date)),
+                }
+            }
+            Predicate::Class(class) => metadata
+                .storage_class
+                .as_deref()
+                .is_some_and(|storage_class| storage_class.eq_ignore_ascii_case(class)),
+            Predicate::Type(wanted) => metadata.content_type.as_deref().is_some_and(|content_type| {
+                // "application/json; charset=utf-8" is still application/json
+                let content_type = content_type.split(';').next().unwrap_or_default().trim();
+                match wanted.strip_suffix('*') {
+                    Some(prefix) => content_type.starts_with(prefix),
+                    None if wanted.ends_with('/') => content_type.starts_with(wanted.as_str()),
+                    None => content_type.eq_ignore_ascii_case(wanted),
+                }
+            }),
+        }
+    }
+}
+
+/// `1234`, `1.5GB` (powers of 1000) or `500KiB` (powers of 1024)
+fn parse_size(value: &str) -> Option<u64> {
+    let split = value.find(|c: char| !c.is_ascii_digit() && c != '.').unwrap_or(value.len());
+    let (number, unit) = value.split_at(split);
+    let number = number.parse::<f64>().ok()?;
+    let multiplier: u64 = match unit.to_ascii_uppercase().as_str() {
+        "" | "B" => 1,
+        "K" | "KB" => 1_000,
+        "M" | "MB" => 1_000_000,
+        "G" | "GB" => 1_000_000_000,
+        "T" | "TB" => 1_000_000_000_000,
+        "KIB" => 1 << 10,
+        "MIB" => 1 << 20,
+        "GIB" => 1 << 30,
+        "TIB" => 1 << 40,
+        _ => return None,
+    };
+    Some((number * multiplier as f64) as u64)
+}
+
+/// `30m`, `12h`, `7d`, `2w` ago, or a `YYYY-MM-DD` date
+fn parse_updated(value: &str) -> Option<UpdatedBound> {
+    if value.len() == 10 && value.as_bytes().get(4) == Some(&b'-') {
+        return util::parse_timestamp(value).map(UpdatedBound::Date);
+    }
+    let (number, unit) = value.split_at(value.char_indices().next_back()?.0);
+    let number = number.parse::<u64>().ok()?;
+    let seconds = match unit {
+        "s" => 1,
+        "m" => 60,
+        "h" => 3_600,
+        "d" => 86_400,
+        "w" => 604_800,
+        _ => return None,
+    };
+    number.checked_mul(seconds).map(UpdatedBound::Age)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn conditions_are_split_from_the_words() {
+        let expression = FilterExpression::parse("logs size>1GB class:NEARLINE 2024 updated<7d sizes.csv type:image/");
+        assert_eq!(expression.as_ref().map(|e| e.text.as_str()), Ok("logs 2024 sizes.csv"));
+        assert_eq!(
+            expression.map(|e| e.predicates),
+            Ok(vec![
+                Predicate::Size(Comparison::Greater, 1_000_000_000),
+                Predicate::Class("NEARLINE".to_string()),
+                Predicate::Updated(Comparison::Less, UpdatedBound::Age(7 * 86_400)),
+                Predicate::Type("image/".to_string()),
+            ])
+        );
+    }
+
+    #[test]
+    fn malformed_conditions_are_errors() {
+        for input in ["size>lots", "updated<soon", "class:", "type:", "size>=1XB"] {
+            assert!(FilterExpression::parse(input).is_err(), "{input}");
+        }
+    }
+
+    #[test]
+    fn sizes_take_decimal_and_binary_units() {
+        assert_eq!(parse_size("1234"), Some(1234));
+        assert_eq!(parse_size("1.5GB"), Some(1_500_000_000));
+        assert_eq!(parse_size("500KiB"), Some(512_000));
+        assert_eq!(parse_size("2mb"), Some(2_000_000));
+        assert_eq!(parse_size("1TiB"), Some(1 << 40));
+        assert_eq!(parse_size("GB"), None);
+        assert_eq!(parse_size("12XB"), None);
+    }
+
+    #[test]
+    fn update_times_are_ages_or_dates() {
+        assert_eq!(parse_updated("30m"), Some(UpdatedBound::Age(1_800)));
+        assert_eq!(parse_updated("12h"), Some(UpdatedBound::Age(43_200)));
+        assert_eq!(parse_updated("2w"), Some(UpdatedBound::Age(1_209_600)));
+        assert_eq!(parse_updated("2024-01-01"), Some(UpdatedBound::Date(1_704_067_200)));
+        assert_eq!(parse_updated("7y"), None);
+        assert_eq!(parse_updated("d"), None);
+        assert_eq!(parse_updated(""), None);
+    }
+
+    #[test]
+    fn metadata_must_hold_every_condition() {
+        let metadata = ObjectMetadata {
+            size: 2_000,
+            storage_class: Some("nearline".to_string()),
+            content_type: Some("application/json; charset=utf-8".to_string()),
+            updated: Some("2024-01-02T00:00:00Z".to_string()),
+            ..ObjectMetadata::default()
+        };
+        let matches = |input: &str| FilterExpression::parse(input).is_ok_and(|expression| expression.matches(&metadata));
+        assert!(matches("size>1KB class:NEARLINE type:application/json updated>2024-01-01"));
+        assert!(matches("type:application/*"));
+        assert!(!matches("size>1KB size<2KB"));
+        assert!(!matches("type:image/"));
+        assert!(!matches("updated<2024-01-01"));
+    }
+}
@@ -0,0 +1,751 @@
+use std::io::{BufRead, BufReader};
+use std::path::Path;
+use std::process::{Child, Command, Stdio};
+use std::result::Result;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+use serde::Serialize;
+use tracing::info;
+
+use crate::action::Action;
+use crate::config::cloud_provider_config::cloud_provider_kind::CloudProviderKind;
+use crate::util;
+
+pub mod cloud_uri;
+pub mod expression;
+pub mod search;
+
+use cloud_uri::CloudUri;
+
+/// A single object returned by a recursive listing
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ObjectEntry {
+    pub uri: CloudUri,
+    pub size: u64,
+    pub updated: Option<String>,
+}
+
+/// Lists every object under `uri`, or the object itself when `uri` is not a prefix
+pub fn list_recursive(uri: &CloudUri) -> Result<Vec<ObjectEntry>, Action> {
+    let (program, args) = listing_command(uri)?;
+    let args: Vec<&str> = args.iter().map(String::as_str).collect();
+    let output = util::cli_command_checked(program, &args)?;
+    Ok(output
+        .lines()
+        .map_while(Result::ok)
+        .filter_map(|line| parse_listing_line(uri, &line))
+        .collect())
+}
+
+/// Like `list_recursive`, but hands each object to `on_entry` as soon as the CLI prints it.
+/// Stops early once `cancel` is set.
+pub fn stream_recursive(
+    uri: &CloudUri,
+    cancel: &AtomicBool,
+    on_entry: &mut dyn FnMut(ObjectEntry),
+) -> Result<(), Action> {
+    let (program, args) = listing_command(uri)?;
+    let mut child = spawn(program, &args, Stdio::null(), Stdio::piped())?;
+    let Some(stdout) = child.stdout.take() else {
+        return Err(Action::Error(format!("No output from {program}")));
+    };
+
+    for line in BufReader::new(stdout).lines().map_while(Result::ok) {
+        if cancel.load(Ordering::SeqCst) {
+            let _ = child.kill();
+            return Ok(());
+        }
+        if let Some(entry) = parse_listing_line(uri, &line) {
+            on_entry(entry);
+        }
+    }
+
+    match child.wait_with_output() {
+        Ok(output) if output.status.success() => Ok(()),
+        Ok(output) => {
+            let stderr = String::from_utf8_lossy(&output.stderr).trim().to_string();
+            Err(Action::Error(format!("{program} listing of {uri} failed: {stderr}")))
+        }
+        Err(e) => Err(Action::Error(format!("{program} listing of {uri} failed: {e}"))),
+    }
+}
+
+/// The CLI invocation that recursively lists `uri` with sizes and modification times
+fn listing_command(uri: &CloudUri) -> Result<(&'static str, Vec<String>), Action> {
+    match uri.kind {
+        CloudProviderKind::Gcs => {
+            let target = if uri.is_prefix() {
+                format!("{uri}**")
+            } else {
+                uri.to_string()
+            };
+            Ok(("gsutil", vec!["ls".into(), "-l".into(), target]))
+        }
+        CloudProviderKind::S3 => Ok((
+            "aws",
+            vec!["s3".into(), "ls".into(), "--recursive".into(), uri.to_string()],
+        )),
+        CloudProviderKind::Azure => {
+            let query = "[].[name, properties.contentLength, properties.lastModified]";
+            let mut args: Vec<String> = vec![
+                "storage".into(), "blob".into(), "list".into(),
+                "--account-name".into(), azure_account(uri)?.into(),
+                "--container-name".into(), uri.bucket.clone(),
+                "--auth-mode".into(), "login".into(),
+                "--num-results".into(), "*".into(),
+                "--query".into(), query.into(),
+                "--output".into(), "tsv".into(),
+            ];
+            if !uri.path.is_empty() {
+                args.extend(["--prefix".into(), uri.path.clone()]);
+            }
+            Ok(("az", args))
+        }
+    }
+}
+
+/// Parses one line printed by `listing_command`
+fn parse_listing_line(uri: &CloudUri, line: &str) -> Option<ObjectEntry> {
+    match uri.kind {
+        CloudProviderKind::Gcs => {
+            // "    1234  2024-01-01T00:00:00Z  gs://bucket/key"
+            let (size, rest) = line.trim_start().split_once(char::is_whitespace)?;
+            let (updated, object) = rest.trim_start().split_once(char::is_whitespace)?;
+            let size = size.parse::<u64>().ok()?;
+            let uri = CloudUri::parse(object.trim_start()).ok()?;
+            Some(ObjectEntry { uri, size, updated: Some(updated.to_string()) })
+        }
+        CloudProviderKind::S3 => {
+            // "2024-01-01 00:00:00       1234 key"
+            let (date, rest) = line.trim_start().split_once(' ')?;
+            let (time, rest) = rest.trim_start().split_once(' ')?;
+            let (size, key) = rest.trim_start().split_once(' ')?;
+            let size = size.parse::<u64>().ok()?;
+            if !uri.is_prefix() && key != uri.path {
+                return None;
+            }
+            Some(ObjectEntry {
+                uri: uri.root().join(key),
+                size,
+                updated: Some(format!("{date}T{time}")),
+            })
+        }
+        CloudProviderKind::Azure => {
+            let mut fields = line.split('\t');
+            let name = fields.next()?;
+            let size = fields.next()?.parse::<u64>().ok()?;
+            let updated = fields.next().map(|u| u.to_string());
+            if !uri.is_prefix() && name != uri.path {
+                return None;
+            }
+            Some(ObjectEntry { uri: uri.root().join(name), size, updated })
+        }
+    }
+}
+
+/// Provider metadata of a single object. Hashes are kept exactly as the provider reports them.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct ObjectMetadata {
+    pub size: u64,
+    /// Base64 encoded, big-endian CRC32C (GCS, S3 additional checksum)
+    pub crc32c: Option<String>,
+    /// Base64 encoded SHA-256 (S3 additional checksum)
+    pub sha256: Option<String>,
+    /// Base64 encoded MD5 (GCS, Azure)
+    pub md5: Option<String>,
+    /// ETag without quotes (S3)
+    pub etag: Option<String>,
+    pub content_type: Option<String>,
+    pub storage_class: Option<String>,
+    pub updated: Option<String>,
+    /// Server-side encryption that keeps the ETag from being the MD5 of the content (S3 SSE-KMS or SSE-C)
+    pub encryption: Option<String>,
+}
+
+/// Reads an object's metadata without downloading it
+pub fn object_metadata(uri: &CloudUri) -> Result<ObjectMetadata, Action> {
+    let target = uri.to_string();
+    match uri.kind {
+        CloudProviderKind::Gcs => {
+            let output = util::cli_command_checked("gsutil", &["stat", &target])?;
+            let mut metadata = ObjectMetadata::default();
+
+            output
+                .lines()
+                .map_while(Result::ok)
+                .for_each(|line| read_gsutil_field(&mut metadata, &line));
+            Ok(metadata)
+        }
+        CloudProviderKind::S3 => {
+            let output = util::cli_command_checked(
+                "aws",
+                &[
+                    "s3api", "head-object",
+                    "--bucket", &uri.bucket,
+                    "--key", &uri.path,
+                    "--checksum-mode", "ENABLED",
+                    "--query",
+                    "[ContentLength, ETag, ContentType, StorageClass, LastModified, ChecksumCRC32C, ChecksumSHA256, \
+                        ServerSideEncryption, SSECustomerAlgorithm]",
+                    "--output", "text",
+                ],
+            )?;
+            let line = String::from_utf8_lossy(&output).trim().to_string();
+            let fields: Vec<Option<String>> = line
+                .split('\t')
+                .map(|field| match field {
+                    "None" | "" => None,
+                    f => Some(f.to_string()),
+                })
+                .collect();
+            let field = |idx: usize| fields.get(idx).cloned().flatten();
+
+            Ok(ObjectMetadata {
+                size: field(0).and_then(|size| size.parse().ok()).unwrap_or_default(),
+                crc32c: field(5),
+                sha256: field(6),
+                md5: None,
+                etag: field(1).map(|etag| etag.trim_matches('"').to_string()),
+                content_type: field(2),
+                // S3 leaves the class out for STANDARD objects
+                storage_class: field(3).or(Some("STANDARD".to_string())),
+                updated: field(4),
+                // SSE-S3 (AES256) keeps the MD5 ETag, KMS and customer keys do not
+                encryption: match (field(7), field(8)) {
+                    (_, Some(algorithm)) => Some(format!("SSE-C {algorithm}")),
+                    (Some(kms), None) if kms.starts_with("aws:kms") => Some(kms),
+                    _ => None,
+                },
+            })
+        }
+        CloudProviderKind::Azure => {
+            let query = "[properties.contentLength, properties.contentSettings.contentMd5, \
+                properties.contentSettings.contentType, properties.blobTier, properties.lastModified]";
+            let output = util::cli_command_checked(
+                "az",
+                &[
+                    "storage", "blob", "show",
+                    "--account-name", azure_account(uri)?,
+                    "--container-name", &uri.bucket,
+                    "--name", &uri.path,
+                    "--auth-mode", "login",
+                    "--query", query,
+                    "--output", "tsv",
+                ],
+            )?;
+            // tsv puts a flat list on one line or one per line depending on the CLI version
+            let fields: Vec<Option<String>> = String::from_utf8_lossy(&output)
+                .trim_end()
+                .split(['\t', '\n'])
+                .map(|field| match field.trim() {
+                    "None" | "" => None,
+                    f => Some(f.to_string()),
+                })
+                .collect();
+            let field = |idx: usize| fields.get(idx).cloned().flatten();
+
+            Ok(ObjectMetadata {
+                size: field(0).and_then(|size| size.parse().ok()).unwrap_or_default(),
+                md5: field(1),
+                content_type: field(2),
+                storage_class: field(3),
+                updated: field(4),
+                ..ObjectMetadata::default()
+            })
+        }
+    }
+}
+
+/// Reads one `    Content-Length:         1234` line printed by `gsutil stat` or `gsutil ls -L`
+fn read_gsutil_field(metadata: &mut ObjectMetadata, line: &str) {
+    let Some((key, value)) = line.trim().split_once(':') else {
+        return;
+    };
+    let value = value.trim().to_string();
+    match key {
+        "Content-Length" => metadata.size = value.parse().unwrap_or_default(),
+        "Content-Type" => metadata.content_type = Some(value),
+        "Storage class" => metadata.storage_class = Some(value),
+        "Update time" => metadata.updated = Some(value),
+        "Hash (crc32c)" => metadata.crc32c = Some(value),
+        "Hash (md5)" => metadata.md5 = Some(value),
+        "ETag" => metadata.etag = Some(value),
+        _ => (),
+    }
+}
+
+/// Metadata of the objects directly under `prefix`, without descending into sub-prefixes.
+/// S3 listings carry no content type.
+pub fn list_metadata(prefix: &CloudUri) -> Result<Vec<(CloudUri, ObjectMetadata)>, Action> {
+    metadata_listing(prefix, false)
+}
+
+/// Metadata of every object under `prefix`, hashes included, in one listing rather than a call per object
+pub fn list_metadata_recursive(prefix: &CloudUri) -> Result<Vec<(CloudUri, ObjectMetadata)>, Action> {
+    metadata_listing(prefix, true)
+}
+
+fn metadata_listing(prefix: &CloudUri, recursive: bool) -> Result<Vec<(CloudUri, ObjectMetadata)>, Action> {
+    match prefix.kind {
+        CloudProviderKind::Gcs => {
+            let target = if recursive {
+                format!("{prefix}**")
+            } else {
+                prefix.to_string()
+            };
+            let output = util::cli_command_checked("gsutil", &["ls", "-L", &target])?;
+
+            // "gs://bucket/key:" starts an object, its fields follow indented
+            let mut objects: Vec<(CloudUri, ObjectMetadata)> = vec![];
+            for line in output.lines().map_while(Result::ok) {
+                if line.starts_with(char::is_whitespace) {
+                    if let Some((_, metadata)) = objects.last_mut() {
+                        read_gsutil_field(metadata, &line);
+                    }
+                } else if let Some(object) = line.strip_suffix(':') {
+                    if let Ok(uri) = CloudUri::parse(object) {
+                        objects.push((uri, ObjectMetadata::default()));
+                    }
+                }
+            }
+            Ok(objects)
+        }
+        CloudProviderKind::S3 => {
+            let mut args = vec![
+                "s3api", "list-objects-v2",
+                "--bucket", &prefix.bucket,
+                "--prefix", &prefix.path,
+                "--query", "Contents[].[Key, Size, LastModified, StorageClass, ETag]",
+                "--output", "text",
+            ];
+            if !recursive {
+                args.extend(["--delimiter", "/"]);
+            }
+            let output = util::cli_command_checked("aws", &args)?;
+            Ok(output
+                .lines()
+                .map_while(Result::ok)
+                .filter_map(|line| {
+                    let mut fields = line.split('\t');
+                    let key = fields.next()?;
+                    let size = fields.next()?.parse::<u64>().ok()?;
+                    let metadata = ObjectMetadata {
+                        size,
+                        updated: fields.next().map(|updated| updated.to_string()),
+                        storage_class: fields.next().map(|class| class.to_string()),
+                        etag: fields.next().map(|etag| etag.trim_matches('"').to_string()),
+                        ..ObjectMetadata::default()
+                    };
+                    Some((prefix.root().join(key), metadata))
+                })
+                .collect())
+        }
+        CloudProviderKind::Azure => {
+            let query = "[].[name, properties.contentLength, properties.lastModified, \
+                properties.contentSettings.contentType, properties.blobTier, properties.contentSettings.contentMd5]";
+            let mut args = vec![
+                "storage", "blob", "list",
+                "--account-name", azure_account(prefix)?,
+                "--container-name", &prefix.bucket,
+                "--prefix", &prefix.path,
+                "--auth-mode", "login",
+                "--num-results", "*",
+                "--query", query,
+                "--output", "tsv",
+            ];
+            if !recursive {
+                args.extend(["--delimiter", "/"]);
+            }
+            let output = util::cli_command_checked("az", &args)?;
+            let field = |field: Option<&str>| field.filter(|f| !f.is_empty() && *f != "None").map(|f| f.to_string());
+            Ok(output
+                .lines()
+                .map_while(Result::ok)
+                .filter_map(|line| {
+                    let mut fields = line.split('\t');
+                    let name = fields.next()?;
+                    // sub-prefixes come back without a length
+                    let size = fields.next()?.parse::<u64>().ok()?;
+                    let metadata = ObjectMetadata {
+                        size,
+                        updated: field(fields.next()),
+                        content_type: field(fields.next()),
+                        storage_class: field(fields.next()),
+                        md5: field(fields.next()),
+                        ..ObjectMetadata::default()
+                    };
+                    Some((prefix.root().join(name), metadata))
+                })
+                .collect())
+        }
+    }
+}
+
+/// Sub-prefixes directly under `prefix`, the "directories" that `list_metadata` leaves out
+pub fn list_prefixes(prefix: &CloudUri) -> Result<Vec<CloudUri>, Action> {
+    let names: Vec<String> = match prefix.kind {
+        CloudProviderKind::Gcs => util::cli_command_checked("gsutil", &["ls", &prefix.to_string()])?
+            .lines()
+            .map_while(Result::ok)
+            .filter_map(|line| CloudUri::parse(line.trim()).ok())
+            .map(|uri| uri.path)
+            .collect(),
+        CloudProviderKind::S3 => {
+            let output = util::cli_command_checked(
+                "aws",
+                &[
+                    "s3api", "list-objects-v2",
+                    "--bucket", &prefix.bucket,
+                    "--prefix", &prefix.path,
+                    "--delimiter", "/",
+                    "--query", "CommonPrefixes[].Prefix",
+                    "--output", "text",
+                ],
+            )?;
+            // text output puts every prefix on one tab separated line, or prints "None"
+            String::from_utf8_lossy(&output)
+                .split(['\t', '\n'])
+                .map(|name| name.trim().to_string())
+                .filter(|name| !name.is_empty() && name != "None")
+                .collect()
+        }
+        CloudProviderKind::Azure => util::cli_command_checked(
+            "az",
+            &[
+                "storage", "blob", "list",
+                "--account-name", azure_account(prefix)?,
+                "--container-name", &prefix.bucket,
+                "--prefix", &prefix.path,
+                "--delimiter", "/",
+                "--auth-mode", "login",
+                "--num-results", "*",
+                "--query", "[].name",
+                "--output", "tsv",
+            ],
+        )?
+        .lines()
+        .map_while(Result::ok)
+        .collect(),
+    };
+    Ok(names
+        .into_iter()
+        .filter(|name| name.ends_with('/') && *name != prefix.path)
+        .map(|name| prefix.root().join(&name))
+        .collect())
+}
+
+/// Size in bytes of a single object
+pub fn object_size(uri: &CloudUri) -> Result<u64, Action> {
+    match list_recursive(uri)?.first() {
+        Some(entry) => Ok(entry.size),
+        None => Err(Action::Error(format!("Could not find object {uri}"))),
+    }
+}
+
+/// Spawns a process streaming the object's bytes, starting at `offset`, to its stdout
+pub fn open_reader(uri: &CloudUri, offset: u64) -> Result<Child, Action> {
+    let target = uri.to_string();
+    let range = format!("{offset}-");
+    let (program, args): (&str, Vec<String>) = match uri.kind {
+        CloudProviderKind::Gcs if offset == 0 => ("gsutil", vec!["cat".into(), target]),
+        CloudProviderKind::Gcs => ("gsutil", vec!["cat".into(), "-r".into(), range, target]),
+        CloudProviderKind::S3 if offset == 0 => ("aws", vec!["s3".into(), "cp".into(), target, "-".into()]),
+        // get-object prints the response after the body, so ranged reads go to a file instead
+        CloudProviderKind::S3 => {
+            return Err(Action::Error(format!("Ranged reads of {uri} go through open_s3_range_reader")))
+        }
+        CloudProviderKind::Azure => (
+            "az",
+            vec![
+                "storage".into(), "blob".into(), "download".into(),
+                "--account-name".into(), azure_account(uri)?.to_string(),
+                "--container-name".into(), uri.bucket.clone(),
+                "--name".into(), uri.path.clone(),
+                "--start-range".into(), offset.to_string(),
+                "--file".into(), "/dev/stdout".into(),
+                "--auth-mode".into(), "login".into(),
+                "--no-progress".into(),
+                "--output".into(), "none".into(),
+            ],
+        ),
+    };
+    spawn(program, &args, Stdio::null(), Stdio::piped())
+}
+
+/// Spawns an S3 ranged read of everything from `offset` into `outfile`, which only succeeds while
+/// the object still has `etag`. Its stdout is the ETag of what was read and nothing else.
+pub fn open_s3_range_reader(uri: &CloudUri, offset: u64, etag: &str, outfile: &Path) -> Result<Child, Action> {
+    let args: Vec<String> = vec![
+        "s3api".into(), "get-object".into(),
+        "--bucket".into(), uri.bucket.clone(),
+        "--key".into(), uri.path.clone(),
+        "--range".into(), format!("bytes={offset}-"),
+        "--if-match".into(), format!("\"{etag}\""),
+        "--query".into(), "ETag".into(),
+        "--output".into(), "text".into(),
+        outfile.to_string_lossy().to_string(),
+    ];
+    spawn("aws", &args, Stdio::null(), Stdio::piped())
+}
+
+/// Spawns a process writing everything sent to its stdin to the object
+pub fn open_writer(uri: &CloudUri) -> Result<Child, Action> {
+    let target = uri.to_string();
+    let (program, args): (&str, Vec<String>) = match uri.kind {
+        CloudProviderKind::Gcs => ("gsutil", vec!["cp".into(), "-".into(), target]),
+        CloudProviderKind::S3 => ("aws", vec!["s3".into(), "cp".into(), "-".into(), target]),
+        CloudProviderKind::Azure => (
+            "az",
+            vec![
+                "storage".into(), "blob".into(), "upload".into(),
+                "--account-name".into(), azure_account(uri)?.to_string(),
+                "--container-name".into(), uri.bucket.clone(),
+                "--name".into(), uri.path.clone(),
+                "--file".into(), "/dev/stdin".into(),
+                "--overwrite".into(),
+                "--auth-mode".into(), "login".into(),
+                "--no-progress".into(),
+                "--output".into(), "none".into(),
+            ],
+        ),
+    };
+    spawn(program, &args, Stdio::piped(), Stdio::null())
+}
+
+/// Server-side copy between two locations of the same provider
+pub fn copy(source: &CloudUri, destination: &CloudUri) -> Result<(), Action> {
+    if source.kind != destination.kind {
+        return Err(Action::Error(format!(
+            "Cannot copy server-side from {} to {}",
+            source.kind, destination.kind
+        )));
+    }
+    let src = source.to_string();
+    let dst = destination.to_string();
+    match source.kind {
+        CloudProviderKind::Gcs => util::cli_command_checked("gsutil", &["cp", &src, &dst]).map(|_| ()),
+        CloudProviderKind::S3 => util::cli_command_checked("aws", &["s3", "cp", &src, &dst]).map(|_| ()),
+        CloudProviderKind::Azure => {
+            let source_url = azure_blob_url(source)?;
+            util::cli_command_checked(
+                "az",
+                &[
+                    "storage", "blob", "copy", "start",
+                    "--account-name", azure_account(destination)?,
+                    "--destination-container", &destination.bucket,
+                    "--destination-blob", &destination.path,
+                    "--source-uri", &source_url,
+                    "--auth-mode", "login",
+                    "--output", "none",
+                ],
+            )?;
+            wait_for_azure_copy(destination)
+        }
+    }
+}
+
+/// Azure copies run asynchronously, so poll the destination until the copy settles
+fn wait_for_azure_copy(destination: &CloudUri) -> Result<(), Action> {
+    loop {
+        let output = util::cli_command_checked(
+            "az",
+            &[
+                "storage", "blob", "show",
+                "--account-name", azure_account(destination)?,
+                "--container-name", &destination.bucket,
+                "--name", &destination.path,
+                "--auth-mode", "login",
+                "--query", "properties.copy.status",
+                "--output", "tsv",
+            ],
+        )?;
+        match String::from_utf8_lossy(&output).trim() {
+            "pending" => std::thread::sleep(std::time::Duration::from_secs(1)),
+            "success" | "" => return Ok(()),
+            status => return Err(Action::Error(format!("Copy to {destination} ended with status {status}"))),
+        }
+    }
+}
+
+/// Deletes a single object
+pub fn remove(uri: &CloudUri) -> Result<(), Action> {
+    let target = uri.to_string();
+    match uri.kind {
+        CloudProviderKind::Gcs => util::cli_command_checked("gsutil", &["rm", &target]).map(|_| ()),
+        CloudProviderKind::S3 => util::cli_command_checked("aws", &["s3", "rm", &target]).map(|_| ()),
+        CloudProviderKind::Azure => util::cli_command_checked(
+            "az",
+            &[
+                "storage", "blob", "delete",
+                "--account-name", azure_account(uri)?,
+                "--container-name", &uri.bucket,
+                "--name", &uri.path,
+                "--auth-mode", "login",
+                "--output", "none",
+            ],
+        )
+        .map(|_| ()),
+    }
+}
+
+/// Most sources a single GCS compose request accepts
+pub const MAX_COMPOSE_SOURCES: usize = 32;
+
+/// Concatenates GCS objects into `destination`, which may itself be one of the sources
+pub fn compose(sources: &[CloudUri], destination: &CloudUri) -> Result<(), Action> {
+    if destination.kind != CloudProviderKind::Gcs {
+        return Err(Action::Error(format!("Compose is not available for {}", destination.kind)));
+    }
+    let mut args: Vec<String> = vec!["compose".into()];
+    args.extend(sources.iter().map(|source| source.to_string()));
+    args.push(destination.to_string());
+    let args: Vec<&str> = args.iter().map(|arg| arg.as_str()).collect();
+    util::cli_command_checked("gsutil", &args).map(|_| ())
+}
+
+/// Starts an S3 multipart upload and returns its upload id
+pub fn create_multipart_upload(uri: &CloudUri) -> Result<String, Action> {
+    let output = util::cli_command_checked(
+        "aws",
+        &[
+            "s3api", "create-multipart-upload",
+            "--bucket", &uri.bucket,
+            "--key", &uri.path,
+            "--query", "UploadId",
+            "--output", "text",
+        ],
+    )?;
+    Ok(String::from_utf8_lossy(&output).trim().to_string())
+}
+
+/// Uploads `body` as one part of an S3 multipart upload and returns the part's ETag
+pub fn upload_part(uri: &CloudUri, upload_id: &str, part_number: usize, body: &Path) -> Result<String, Action> {
+    let part_number = part_number.to_string();
+    let body = body.to_string_lossy();
+    let output = util::cli_command_checked(
+        "aws",
+        &[
+            "s3api", "upload-part",
+            "--bucket", &uri.bucket,
+            "--key", &uri.path,
+            "--upload-id", upload_id,
+            "--part-number", &part_number,
+            "--body", &body,
+            "--query", "ETag",
+            "--output", "text",
+        ],
+    )?;
+    Ok(String::from_utf8_lossy(&output).trim().trim_matches('"').to_string())
+}
+
+/// Finishes an S3 multipart upload from the ETags of its parts, in part order
+pub fn complete_multipart_upload(uri: &CloudUri, upload_id: &str, etags: &[String]) -> Result<(), Action> {
+    let parts: Vec<String> = etags
+        .iter()
+        .enumerate()
+        .map(|(idx, etag)| format!(r#"{{"ETag":"\"{etag}\"","PartNumber":{}}}"#, idx + 1))
+        .collect();
+    let multipart = format!(r#"{{"Parts":[{}]}}"#, parts.join(","));
+    util::cli_command_checked(
+        "aws",
+        &[
+            "s3api", "complete-multipart-upload",
+            "--bucket", &uri.bucket,
+            "--key", &uri.path,
+            "--upload-id", upload_id,
+            "--multipart-upload", &multipart,
+            "--output", "text",
+        ],
+    )
+    .map(|_| ())
+}
+
+fn azure_account(uri: &CloudUri) -> Result<&str, Action> {
+    match &uri.account {
+        Some(account) => Ok(account),
+        None => Err(Action::Error(format!("No storage account in {uri}"))),
+    }
+}
+
+fn azure_blob_url(uri: &CloudUri) -> Result<String, Action> {
+    let account = azure_account(uri)?;
+    Ok(format!("https://{account}.blob.core.windows.net/{}/{}", uri.bucket, uri.path))
+}
+
+fn spawn(program: &str, args: &[String], stdin: Stdio, stdout: Stdio) -> Result<Child, Action> {
+    info!("Spawning {program} {args:?}");
+    match Command::new(program)
+        .args(args)
+        .stdin(stdin)
+        .stdout(stdout)
+        .stderr(Stdio::piped())
+        .spawn()
+    {
+        Ok(child) => Ok(child),
+        Err(_) => Err(Action::Error(format!("Could not start '{program}'"))),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn listed(uri: &str, line: &str) -> Result<Option<ObjectEntry>, Action> {
+        Ok(parse_listing_line(&CloudUri::parse(uri)?, line))
+    }
+
+    #[test]
+    fn parses_gsutil_listing() -> Result<(), Action> {
+        let entry = listed("gs://bucket/dir/", "      1234  2024-01-01T00:00:00Z  gs://bucket/dir/key")?;
+        assert_eq!(
+            entry,
+            Some(ObjectEntry {
+                uri: CloudUri::parse("gs://bucket/dir/key")?,
+                size: 1234,
+                updated: Some("2024-01-01T00:00:00Z".to_string()),
+            })
+        );
+        assert_eq!(listed("gs://bucket/dir/", "TOTAL: 1 objects, 1234 bytes (1.21 KiB)")?, None);
+        Ok(())
+    }
+
+    #[test]
+    fn parses_aws_listing() -> Result<(), Action> {
+        let entry = listed("s3://bucket/dir/", "2024-01-01 00:00:00       1234 dir/key with spaces")?;
+        assert_eq!(
+            entry,
+            Some(ObjectEntry {
+                uri: CloudUri::parse("s3://bucket/dir/key with spaces")?,
+                size: 1234,
+                updated: Some("2024-01-01T00:00:00".to_string()),
+            })
+        );
+        assert_eq!(listed("s3://bucket/dir/", "                           PRE sub/")?, None);
+        Ok(())
+    }
+
+    #[test]
+    fn object_listing_keeps_only_the_object() -> Result<(), Action> {
+        // `aws s3 ls` of a key also lists every key it is a prefix of
+        assert!(listed("s3://bucket/key", "2024-01-01 00:00:00 1 key")?.is_some());
+        assert_eq!(listed("s3://bucket/key", "2024-01-01 00:00:00 1 key2")?, None);
+        assert_eq!(listed("az://account/container/key", "key2\t1\t2024-01-01T00:00:00+00:00")?, None);
+        Ok(())
+    }
+
+    #[test]
+    fn parses_azure_listing() -> Result<(), Action> {
+        let entry = listed("az://account/container/dir/", "dir/blob\t42\t2024-01-01T00:00:00+00:00")?;
+        assert_eq!(
+            entry,
+            Some(ObjectEntry {
+                uri: CloudUri::parse("az://account/container/dir/blob")?,
+                size: 42,
+                updated: Some("2024-01-01T00:00:00+00:00".to_string()),
+            })
+        );
+        // sub-prefixes have no length
+        assert_eq!(listed("az://account/container/", "dir/\tNone\tNone")?, None);
+        Ok(())
+    }
+}
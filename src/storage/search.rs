@@ -0,0 +1,220 @@
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread;
+
+use regex_automata::meta::Regex;
+
+use crate::action::Action;
+
+use super::{cloud_uri::CloudUri, expression::FilterExpression, object_metadata, stream_recursive, ObjectEntry, ObjectMetadata};
+
+/// What a recursive search lists and which of the listed URIs it keeps
+#[derive(Debug, Clone)]
+pub struct SearchPattern {
+    /// The input as the user typed it
+    pub text: String,
+    /// Longest literal prefix of the pattern, the only part the provider is asked to list
+    pub prefix: CloudUri,
+    regex: Regex,
+    /// Metadata conditions typed after the pattern, e.g. `size>1GB`
+    expression: FilterExpression,
+}
+
+impl SearchPattern {
+    /// Either a glob over full URIs, `gs://b/**/*.parquet`, or a prefix followed by a regex,
+    /// `gs://b/logs/ re:\.gz$`, optionally followed by metadata conditions such as `size>1GB`
+    pub fn parse(input: &str) -> Result<Self, Action> {
+        let text = input.trim().to_string();
+        let expression = FilterExpression::parse(&text).map_err(Action::Error)?;
+        let pattern_text = expression.text.clone();
+
+        let (prefix, pattern) = match pattern_text.split_once(" re:") {
+            Some((prefix, regex)) => (CloudUri::parse(prefix.trim())?, regex.to_string()),
+            None => match pattern_text.find(['*', '?', '[']) {
+                Some(idx) => {
+                    let literal = &pattern_text[..idx];
+                    let prefix = &literal[..literal.rfind('/').map_or(0, |idx| idx + 1)];
+                    (CloudUri::parse(prefix)?, format!("^{}$", glob_to_regex(&pattern_text)))
+                }
+                // no wildcard: everything under the prefix
+                None => {
+                    let prefix = CloudUri::parse(&pattern_text)?;
+                    let pattern = format!("^{}", glob_to_regex(&prefix.to_string()));
+                    (prefix, pattern)
+                }
+            },
+        };
+        if !prefix.is_prefix() {
+            return Err(Action::Error(format!("{prefix} is not a prefix, end it with '/'")));
+        }
+
+        let regex = Regex::new(&pattern).map_err(|e| Action::Error(format!("Invalid search pattern '{text}': {e}")))?;
+        Ok(Self { text, prefix, regex, expression })
+    }
+
+    /// Checks the name first; metadata beyond size and update time is only fetched for objects whose name matches
+    pub fn matches(&self, entry: &ObjectEntry) -> bool {
+        if !self.regex.is_match(&entry.uri.to_string()) {
+            return false;
+        }
+        if self.expression.predicates.is_empty() {
+            return true;
+        }
+        let metadata = if self.expression.needs_full_metadata() {
+            match object_metadata(&entry.uri) {
+                Ok(metadata) => metadata,
+                Err(_) => return false,
+            }
+        } else {
+            ObjectMetadata { size: entry.size, updated: entry.updated.clone(), ..ObjectMetadata::default() }
+        };
+        self.expression.matches(&metadata)
+    }
+}
+
+/// `**` crosses `/`, `*` and `?` stay within one segment, `[...]` is a character class and
+/// `[!...]` (or `[^...]`) its negation
+fn glob_to_regex(glob: &str) -> String {
+    let mut regex = String::new();
+    let mut chars = glob.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '*' if chars.peek() == Some(&'*') => {
+                chars.next();
+                // `**/` also matches no directory at all
+                if chars.peek() == Some(&'/') {
+                    chars.next();
+                    regex.push_str("(?:.*/)?");
+                } else {
+                    regex.push_str(".*");
+                }
+            }
+            '*' => regex.push_str("[^/]*"),
+            '?' => regex.push_str("[^/]"),
+            '[' => {
+                regex.push('[');
+                // a negated class still stays within one segment
+                if chars.next_if(|c| matches!(c, '!' | '^')).is_some() {
+                    regex.push_str("^/");
+                }
+                for c in chars.by_ref() {
+                    // nested classes and set operations mean nothing in a glob
+                    if "\\[&~".contains(c) {
+                        regex.push('\\');
+                    }
+                    regex.push(c);
+                    if c == ']' {
+                        break;
+                    }
+                }
+            }
+            c if "\\.+()|{}^$#&-~".contains(c) => {
+                regex.push('\\');
+                regex.push(c);
+            }
+            c => regex.push(c),
+        }
+    }
+    regex
+}
+
+/// What a search has produced since it was last polled
+#[derive(Debug, Clone, Default)]
+pub struct SearchProgress {
+    /// New matches only, earlier ones were handed out by previous polls
+    pub matches: Vec<ObjectEntry>,
+    pub scanned: usize,
+    pub done: bool,
+    pub error: Option<String>,
+}
+
+/// A search running on a background thread; `poll` picks up the matches listed so far
+#[derive(Debug)]
+pub struct RunningSearch {
+    pub pattern: SearchPattern,
+    progress: Arc<Mutex<SearchProgress>>,
+    cancel: Arc<AtomicBool>,
+}
+
+impl RunningSearch {
+    pub fn start(pattern: SearchPattern) -> Self {
+        let progress = Arc::new(Mutex::new(SearchProgress::default()));
+        let cancel = Arc::new(AtomicBool::new(false));
+
+        let thread_pattern = pattern.clone();
+        let thread_progress = Arc::clone(&progress);
+        let thread_cancel = Arc::clone(&cancel);
+        thread::spawn(move || {
+            let result = stream_recursive(&thread_pattern.prefix, &thread_cancel, &mut |entry| {
+                let matched = thread_pattern.matches(&entry);
+                if let Ok(mut progress) = thread_progress.lock() {
+                    progress.scanned += 1;
+                    if matched {
+                        progress.matches.push(entry);
+                    }
+                }
+            });
+            if let Ok(mut progress) = thread_progress.lock() {
+                progress.done = true;
+                if let Err(Action::Error(e)) = result {
+                    progress.error = Some(e);
+                }
+            }
+        });
+
+        Self { pattern, progress, cancel }
+    }
+
+    pub fn poll(&self) -> SearchProgress {
+        match self.progress.lock() {
+            Ok(mut progress) => SearchProgress {
+                matches: std::mem::take(&mut progress.matches),
+                ..progress.clone()
+            },
+            Err(_) => SearchProgress::default(),
+        }
+    }
+
+    pub fn cancel(&self) {
+        self.cancel.store(true, Ordering::SeqCst);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn glob_matches(glob: &str, uri: &str) -> bool {
+        Regex::new(&format!("^{}$", glob_to_regex(glob))).is_ok_and(|regex| regex.is_match(uri))
+    }
+
+    #[test]
+    fn stars_stay_within_a_segment_unless_doubled() {
+        assert!(glob_matches("gs://b/*.csv", "gs://b/a.csv"));
+        assert!(!glob_matches("gs://b/*.csv", "gs://b/x/a.csv"));
+        assert!(glob_matches("gs://b/**/*.csv", "gs://b/x/y/a.csv"));
+        assert!(glob_matches("gs://b/**/*.csv", "gs://b/a.csv"));
+        assert!(glob_matches("gs://b/**", "gs://b/x/y"));
+        assert!(glob_matches("gs://b/?.csv", "gs://b/a.csv"));
+        assert!(!glob_matches("gs://b/?.csv", "gs://b/ab.csv"));
+        assert!(!glob_matches("gs://b/a?b", "gs://b/a/b"));
+    }
+
+    #[test]
+    fn regex_characters_are_literal() {
+        assert!(glob_matches("s3://b/a+b (1).{x}$", "s3://b/a+b (1).{x}$"));
+        assert!(!glob_matches("s3://b/a.csv", "s3://b/aXcsv"));
+        assert!(glob_matches("s3://b/a^b|c", "s3://b/a^b|c"));
+    }
+
+    #[test]
+    fn classes_and_their_negation() {
+        assert!(glob_matches("gs://b/2024-0[1-3].gz", "gs://b/2024-02.gz"));
+        assert!(!glob_matches("gs://b/2024-0[1-3].gz", "gs://b/2024-04.gz"));
+        assert!(glob_matches("gs://b/2024-0[!1-3].gz", "gs://b/2024-04.gz"));
+        assert!(!glob_matches("gs://b/2024-0[!1-3].gz", "gs://b/2024-02.gz"));
+        assert!(glob_matches("gs://b/a[^x]", "gs://b/ay"));
+        assert!(!glob_matches("gs://b/a[!x]b", "gs://b/a/b"));
+        assert!(glob_matches("gs://b/[[&]", "gs://b/&"));
+    }
+}
@@ -0,0 +1,404 @@
+use std::fmt;
+use std::fs::File;
+use std::io::Read;
+use std::path::Path;
+
+use base64::{engine::general_purpose::STANDARD, Engine};
+use sha2::{Digest, Sha256};
+
+use crate::config::cloud_provider_config::cloud_provider_kind::CloudProviderKind;
+use crate::storage::{self, cloud_uri::CloudUri, ObjectMetadata};
+
+use super::PART_SIZE;
+
+/// Part sizes tried when matching a multipart S3 ETag: ours, then the AWS CLI's usual ones
+const MULTIPART_PART_SIZES: [u64; 4] = [PART_SIZE, 8 * 1024 * 1024, 16 * 1024 * 1024, 64 * 1024 * 1024];
+
+const READ_SIZE: usize = 1024 * 1024;
+
+/// Outcome of comparing a local file with the provider's checksum of the remote object
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Verification {
+    Verified(String),
+    Mismatch(String),
+    Unverifiable(String),
+}
+
+impl fmt::Display for Verification {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Verification::Verified(method) => write!(f, "{method} ok"),
+            Verification::Mismatch(reason) => write!(f, "mismatch: {reason}"),
+            Verification::Unverifiable(reason) => write!(f, "n/a: {reason}"),
+        }
+    }
+}
+
+/// Checksums of a byte stream, in every form a provider might report
+#[derive(Debug)]
+pub struct Digests {
+    pub size: u64,
+    pub crc32c: u32,
+    pub md5: md5::Digest,
+    /// Only computed when asked for, see `Digester::with_sha256`
+    pub sha256: Option<[u8; 32]>,
+    /// Multipart ETag computed for each candidate part size, e.g. "<hex>-12"
+    pub multipart_etags: Vec<String>,
+}
+
+/// Hashes a stream of known length as it passes through, in a single pass
+pub struct Digester {
+    size: u64,
+    part_sizes: Vec<u64>,
+    /// per candidate part size: md5 of the current part, and the md5s of finished parts
+    part_contexts: Vec<(md5::Context, Vec<u8>)>,
+    crc: u32,
+    whole: md5::Context,
+    sha256: Option<Sha256>,
+    offset: u64,
+}
+
+impl Digester {
+    /// `part_sizes` are the multipart ETag part sizes worth computing, if any
+    pub fn new(size: u64, part_sizes: Vec<u64>) -> Self {
+        let part_contexts = part_sizes.iter().map(|_| (md5::Context::new(), vec![])).collect();
+        Self {
+            size,
+            part_sizes,
+            part_contexts,
+            crc: 0,
+            whole: md5::Context::new(),
+            sha256: None,
+            offset: 0,
+        }
+    }
+
+    /// Also computes the SHA-256, for S3 objects that store one
+    pub fn with_sha256(mut self) -> Self {
+        self.sha256 = Some(Sha256::new());
+        self
+    }
+
+    /// Digester that can match a multipart ETag made with any of the usual part sizes
+    pub fn any_multipart(size: u64) -> Self {
+        Self::new(size, MULTIPART_PART_SIZES.to_vec())
+    }
+
+    pub fn update(&mut self, chunk: &[u8]) {
+        self.crc = crc32c::crc32c_append(self.crc, chunk);
+        self.whole.consume(chunk);
+        if let Some(sha256) = &mut self.sha256 {
+            sha256.update(chunk);
+        }
+
+        for ((context, finished), part_size) in self.part_contexts.iter_mut().zip(&self.part_sizes) {
+            // a read can straddle a part boundary
+            let mut remaining = chunk;
+            let mut position = self.offset;
+            while !remaining.is_empty() {
+                let left_in_part = (part_size - position % part_size) as usize;
+                let (head, tail) = remaining.split_at(left_in_part.min(remaining.len()));
+                context.consume(head);
+                position += head.len() as u64;
+                if position.is_multiple_of(*part_size) {
+                    let done = std::mem::replace(context, md5::Context::new()).compute();
+                    finished.extend_from_slice(&done.0);
+                }
+                remaining = tail;
+            }
+        }
+        self.offset += chunk.len() as u64;
+    }
+
+    pub fn finish(self) -> Digests {
+        let size = self.size;
+        let multipart_etags = self
+            .part_contexts
+            .into_iter()
+            .zip(&self.part_sizes)
+            .map(|((context, mut finished), part_size)| {
+                if !size.is_multiple_of(*part_size) {
+                    finished.extend_from_slice(&context.compute().0);
+                }
+                format!("{:x}-{}", md5::compute(&finished), size.div_ceil(*part_size))
+            })
+            .collect();
+
+        Digests {
+            size: self.offset,
+            crc32c: self.crc,
+            md5: self.whole.compute(),
+            sha256: self.sha256.map(|sha256| sha256.finalize().into()),
+            multipart_etags,
+        }
+    }
+}
+
+/// Compares `local` with `remote`, calling `progress` with the number of bytes hashed
+pub fn verify(local: &Path, remote: &CloudUri, progress: &mut dyn FnMut(u64)) -> Result<Verification, String> {
+    let metadata = storage::object_metadata(remote).map_err(super::action_message)?;
+    verify_against(local, remote.kind, &metadata, progress)
+}
+
+pub fn verify_against(
+    local: &Path,
+    kind: CloudProviderKind,
+    metadata: &ObjectMetadata,
+    progress: &mut dyn FnMut(u64),
+) -> Result<Verification, String> {
+    let local_size = std::fs::metadata(local).map_err(|e| e.to_string())?.len();
+    if local_size != metadata.size {
+        return Ok(Verification::Mismatch(format!(
+            "size {local_size} != {}",
+            metadata.size
+        )));
+    }
+    if metadata.crc32c.is_none() && metadata.sha256.is_none() && metadata.md5.is_none() && metadata.etag.is_none() {
+        return Ok(Verification::Unverifiable("no checksum stored".to_string()));
+    }
+
+    // only the part sizes that give the ETag's part count are worth hashing
+    let part_sizes = match metadata.etag.as_ref().and_then(|etag| etag.split_once('-')) {
+        Some((_, parts)) if kind == CloudProviderKind::S3 => {
+            let parts: u64 = parts.parse().unwrap_or_default();
+            MULTIPART_PART_SIZES
+                .iter()
+                .copied()
+                .filter(|part_size| local_size.div_ceil(*part_size) == parts)
+                .collect()
+        }
+        _ => vec![],
+    };
+    let mut digester = Digester::new(local_size, part_sizes);
+    if whole_object(&metadata.sha256).is_some() {
+        digester = digester.with_sha256();
+    }
+
+    let mut file = File::open(local).map_err(|e| e.to_string())?;
+    let mut buffer = vec![0; READ_SIZE];
+    loop {
+        let read = file.read(&mut buffer).map_err(|e| e.to_string())?;
+        if read == 0 {
+            break;
+        }
+        digester.update(&buffer[..read]);
+        progress(read as u64);
+    }
+
+    Ok(compare_digests(kind, metadata, &digester.finish()))
+}
+
+/// Checks bytes that were hashed on the way through against what the provider stored
+pub fn compare_digests(kind: CloudProviderKind, metadata: &ObjectMetadata, digests: &Digests) -> Verification {
+    if digests.size != metadata.size {
+        return Verification::Mismatch(format!("size {} != {}", digests.size, metadata.size));
+    }
+
+    match kind {
+        CloudProviderKind::Gcs | CloudProviderKind::Azure => {
+            if let Some(remote_crc) = &metadata.crc32c {
+                let local_crc = STANDARD.encode(digests.crc32c.to_be_bytes());
+                compare("crc32c", &local_crc, remote_crc)
+            } else if let Some(remote_md5) = &metadata.md5 {
+                compare("md5", &STANDARD.encode(digests.md5.0), remote_md5)
+            } else {
+                Verification::Unverifiable("no checksum stored".to_string())
+            }
+        }
+        CloudProviderKind::S3 => {
+            // an additional checksum covers the content whatever the encryption
+            if let Some(remote_crc) = whole_object(&metadata.crc32c) {
+                return compare("crc32c", &STANDARD.encode(digests.crc32c.to_be_bytes()), remote_crc);
+            }
+            if let (Some(remote_sha), Some(local_sha)) = (whole_object(&metadata.sha256), &digests.sha256) {
+                return compare("sha256", &STANDARD.encode(local_sha), remote_sha);
+            }
+            if let Some(encryption) = &metadata.encryption {
+                return Verification::Unverifiable(format!("the ETag of a {encryption} object is not an MD5"));
+            }
+            let Some(etag) = &metadata.etag else {
+                return Verification::Unverifiable("no ETag".to_string());
+            };
+            match etag.split_once('-') {
+                None if !is_md5(etag) => Verification::Unverifiable("the ETag is not an MD5".to_string()),
+                None => compare("etag", &format!("{:x}", digests.md5), &etag.to_lowercase()),
+                Some((_, parts)) => {
+                    let candidates: Vec<&String> = digests
+                        .multipart_etags
+                        .iter()
+                        .filter(|local| local.rsplit_once('-').map(|(_, n)| n) == Some(parts))
+                        .collect();
+                    if candidates.is_empty() {
+                        Verification::Unverifiable("unknown multipart part size".to_string())
+                    } else if candidates.contains(&etag) {
+                        Verification::Verified("multipart etag".to_string())
+                    } else {
+                        Verification::Mismatch("multipart etag".to_string())
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// An S3 additional checksum of the whole object; those of multipart uploads are usually a checksum
+/// of the part checksums, ending in `-<parts>`, and cannot be compared with a hash of the content
+fn whole_object(checksum: &Option<String>) -> Option<&String> {
+    checksum.as_ref().filter(|checksum| !checksum.contains('-'))
+}
+
+/// Whether a single-part ETag can be the hex MD5 of the content
+fn is_md5(etag: &str) -> bool {
+    etag.len() == 32 && etag.chars().all(|c| c.is_ascii_hexdigit())
+}
+
+/// Result for a copy checked at both ends: the streamed bytes must match the source and the destination
+pub fn combine(source: Verification, destination: Verification) -> Verification {
+    match (source, destination) {
+        (Verification::Mismatch(reason), _) => Verification::Mismatch(format!("source {reason}")),
+        (_, Verification::Mismatch(reason)) => Verification::Mismatch(format!("destination {reason}")),
+        (Verification::Verified(source), Verification::Verified(destination)) => {
+            Verification::Verified(format!("{source} -> {destination}"))
+        }
+        (Verification::Unverifiable(_), Verification::Verified(destination)) => {
+            Verification::Verified(format!("{destination} (source n/a)"))
+        }
+        (_, unverifiable) => unverifiable,
+    }
+}
+
+fn compare(method: &str, local: &str, remote: &str) -> Verification {
+    if local == remote {
+        Verification::Verified(method.to_string())
+    } else {
+        Verification::Mismatch(format!("{method} {local} != {remote}"))
+    }
+}
+
+/// The checksums in `metadata` in a form that can be compared across providers,
+/// e.g. a base64 GCS MD5 and a hex S3 ETag both become ("md5", hex)
+pub fn comparable_hashes(kind: CloudProviderKind, metadata: &ObjectMetadata) -> Vec<(&'static str, String)> {
+    let mut hashes = vec![];
+    if let Some(crc) = &metadata.crc32c {
+        hashes.push(("crc32c", crc.clone()));
+    }
+    if let Some(md5) = metadata.md5.as_ref().and_then(|md5| STANDARD.decode(md5).ok()) {
+        hashes.push(("md5", md5.iter().map(|byte| format!("{byte:02x}")).collect()));
+    }
+    if let Some(sha256) = whole_object(&metadata.sha256) {
+        hashes.push(("sha256", sha256.clone()));
+    }
+    if let (CloudProviderKind::S3, Some(etag)) = (kind, &metadata.etag) {
+        // a plain ETag is usually the MD5, a multipart one only matches the same multipart upload
+        if etag.contains('-') {
+            hashes.push(("etag", etag.to_lowercase()));
+        } else if is_md5(etag) {
+            hashes.push(("md5", etag.to_lowercase()));
+        }
+    }
+    hashes
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn digest(data: &[u8], part_sizes: Vec<u64>, chunk: usize) -> Digests {
+        let mut digester = Digester::new(data.len() as u64, part_sizes);
+        for piece in data.chunks(chunk) {
+            digester.update(piece);
+        }
+        digester.finish()
+    }
+
+    #[test]
+    fn digests_the_whole_stream() {
+        let digests = digest(b"hello world", vec![], 3);
+        assert_eq!(digests.size, 11);
+        assert_eq!(format!("{:x}", digests.md5), "5eb63bbbe01eeed093cb22bb8f5acdc3");
+        assert_eq!(digests.crc32c, crc32c::crc32c(b"hello world"));
+        assert!(digests.multipart_etags.is_empty());
+    }
+
+    #[test]
+    fn multipart_etag_is_the_md5_of_the_part_md5s() {
+        let data: Vec<u8> = (0..10u8).collect();
+        let mut part_md5s = vec![];
+        for part in data.chunks(4) {
+            part_md5s.extend_from_slice(&md5::compute(part).0);
+        }
+        let expected = format!("{:x}-3", md5::compute(&part_md5s));
+
+        // reads that straddle part boundaries give the same result as reads that line up with them
+        for chunk in [1, 3, 4, 10] {
+            assert_eq!(digest(&data, vec![4], chunk).multipart_etags, vec![expected.clone()]);
+        }
+    }
+
+    #[test]
+    fn last_part_is_not_repeated_when_the_size_is_a_multiple() {
+        let data = [7u8; 8];
+        let part_md5 = md5::compute([7u8; 4]).0;
+        let expected = format!("{:x}-2", md5::compute([part_md5, part_md5].concat()));
+        assert_eq!(digest(&data, vec![4], 8).multipart_etags, vec![expected]);
+    }
+
+    #[test]
+    fn digests_verify_against_the_providers_checksums() {
+        let digests = digest(b"hello world", vec![], 11);
+        let metadata = ObjectMetadata {
+            size: 11,
+            md5: Some(STANDARD.encode(digests.md5.0)),
+            etag: Some(format!("{:x}", digests.md5)),
+            ..ObjectMetadata::default()
+        };
+        assert!(matches!(compare_digests(CloudProviderKind::Gcs, &metadata, &digests), Verification::Verified(_)));
+        assert!(matches!(compare_digests(CloudProviderKind::S3, &metadata, &digests), Verification::Verified(_)));
+
+        let changed = ObjectMetadata { etag: Some("0".repeat(32)), ..metadata };
+        assert!(matches!(compare_digests(CloudProviderKind::S3, &changed, &digests), Verification::Mismatch(_)));
+    }
+
+    #[test]
+    fn s3_prefers_the_additional_checksums() {
+        let mut digester = Digester::new(11, vec![]).with_sha256();
+        digester.update(b"hello world");
+        let digests = digester.finish();
+        let sha256 = STANDARD.encode(digests.sha256.unwrap_or_default());
+        // a KMS ETag says nothing about the content, the checksums still do
+        let metadata = ObjectMetadata {
+            size: 11,
+            etag: Some("0".repeat(32)),
+            encryption: Some("aws:kms".to_string()),
+            ..ObjectMetadata::default()
+        };
+        let with_crc = ObjectMetadata { crc32c: Some(STANDARD.encode(digests.crc32c.to_be_bytes())), ..metadata.clone() };
+        let with_sha = ObjectMetadata { sha256: Some(sha256.clone()), ..metadata.clone() };
+        assert_eq!(compare_digests(CloudProviderKind::S3, &with_crc, &digests), Verification::Verified("crc32c".to_string()));
+        assert_eq!(compare_digests(CloudProviderKind::S3, &with_sha, &digests), Verification::Verified("sha256".to_string()));
+
+        let wrong = ObjectMetadata { sha256: Some(STANDARD.encode([0u8; 32])), ..metadata.clone() };
+        assert!(matches!(compare_digests(CloudProviderKind::S3, &wrong, &digests), Verification::Mismatch(_)));
+
+        // a checksum of part checksums is not comparable, and neither is the ETag
+        let composite = ObjectMetadata { sha256: Some(format!("{sha256}-3")), ..metadata };
+        assert!(matches!(compare_digests(CloudProviderKind::S3, &composite, &digests), Verification::Unverifiable(_)));
+    }
+
+    #[test]
+    fn s3_etags_that_are_not_md5s_are_unverifiable() {
+        let digests = digest(b"hello world", vec![], 11);
+        for (etag, encryption) in [("0".repeat(32), Some("SSE-C AES256")), ("not-an-md5".to_string(), None), ("abc".to_string(), None)] {
+            let metadata = ObjectMetadata {
+                size: 11,
+                etag: Some(etag),
+                encryption: encryption.map(str::to_string),
+                ..ObjectMetadata::default()
+            };
+            assert!(
+                !matches!(compare_digests(CloudProviderKind::S3, &metadata, &digests), Verification::Mismatch(_)),
+                "{metadata:?}"
+            );
+        }
+    }
+}
@@ -0,0 +1,183 @@
+use std::collections::BTreeMap;
+use std::fmt;
+
+use crate::action::Action;
+use crate::config::cloud_provider_config::cloud_provider_kind::CloudProviderKind;
+use crate::storage::{self, cloud_uri::CloudUri, ObjectMetadata};
+
+use super::checksum;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Difference {
+    LeftOnly,
+    RightOnly,
+    /// On both sides, with the reason they differ
+    Differs(String),
+}
+
+/// An object that is not the same on both sides
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DiffEntry {
+    /// Path relative to the compared prefixes
+    pub relative: String,
+    pub difference: Difference,
+}
+
+impl fmt::Display for DiffEntry {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match &self.difference {
+            Difference::LeftOnly => write!(f, "left only   {}", self.relative),
+            Difference::RightOnly => write!(f, "right only  {}", self.relative),
+            Difference::Differs(reason) => write!(f, "differs     {} ({reason})", self.relative),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Diff {
+    pub entries: Vec<DiffEntry>,
+    /// Objects found on both sides with matching size and checksum
+    pub identical: usize,
+    /// Objects with matching size but no checksum both providers report
+    pub unverified: usize,
+}
+
+/// Compares every object under `left` with the one at the same relative path under `right`.
+/// Objects with the same size are compared by the checksums both listings carry, even across providers.
+pub fn compare(left: &CloudUri, right: &CloudUri) -> Result<Diff, Action> {
+    let listing = |prefix: &CloudUri| -> Result<BTreeMap<String, ObjectMetadata>, Action> {
+        Ok(storage::list_metadata_recursive(prefix)?
+            .into_iter()
+            .filter_map(|(uri, metadata)| {
+                let relative = uri.relative_to(prefix)?;
+                // placeholder objects for "directories" are not worth reporting
+                if relative.is_empty() || relative.ends_with('/') {
+                    return None;
+                }
+                Some((relative, metadata))
+            })
+            .collect())
+    };
+    Ok(compare_listings((left.kind, &listing(left)?), (right.kind, &listing(right)?)))
+}
+
+/// Compares two listings keyed by relative path, each with the provider it came from
+fn compare_listings(
+    (left_kind, left_objects): (CloudProviderKind, &BTreeMap<String, ObjectMetadata>),
+    (right_kind, right_objects): (CloudProviderKind, &BTreeMap<String, ObjectMetadata>),
+) -> Diff {
+    let mut diff = Diff::default();
+    for (relative, left_metadata) in left_objects {
+        let outcome = match right_objects.get(relative) {
+            None => Outcome::Different(Difference::LeftOnly),
+            Some(right_metadata) if right_metadata.size != left_metadata.size => Outcome::Different(
+                Difference::Differs(format!("size {} != {}", left_metadata.size, right_metadata.size)),
+            ),
+            Some(right_metadata) => compare_checksums(
+                &checksum::comparable_hashes(left_kind, left_metadata),
+                &checksum::comparable_hashes(right_kind, right_metadata),
+            ),
+        };
+        match outcome {
+            Outcome::Different(difference) => diff.entries.push(DiffEntry { relative: relative.clone(), difference }),
+            Outcome::Identical => diff.identical += 1,
+            Outcome::Unverified => diff.unverified += 1,
+        }
+    }
+    diff.entries.extend(
+        right_objects
+            .keys()
+            .filter(|relative| !left_objects.contains_key(*relative))
+            .map(|relative| DiffEntry { relative: relative.clone(), difference: Difference::RightOnly }),
+    );
+    diff.entries.sort_by(|a, b| a.relative.cmp(&b.relative));
+    diff
+}
+
+enum Outcome {
+    Identical,
+    Unverified,
+    Different(Difference),
+}
+
+/// Goes through the checksums both sides have. A multipart ETag depends on the part size the
+/// uploader used, so equal ones show the objects match but unequal ones show nothing.
+fn compare_checksums(left_hashes: &[(&'static str, String)], right_hashes: &[(&'static str, String)]) -> Outcome {
+    for (method, left_hash) in left_hashes {
+        let Some((_, right_hash)) = right_hashes.iter().find(|(right_method, _)| right_method == method) else {
+            continue;
+        };
+        if left_hash == right_hash {
+            return Outcome::Identical;
+        }
+        if *method != "etag" {
+            return Outcome::Different(Difference::Differs(format!("{method} differs")));
+        }
+    }
+    Outcome::Unverified
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn object(size: u64, etag: &str) -> ObjectMetadata {
+        ObjectMetadata { size, etag: Some(etag.to_string()), ..ObjectMetadata::default() }
+    }
+
+    fn listing(objects: &[(&str, ObjectMetadata)]) -> BTreeMap<String, ObjectMetadata> {
+        objects.iter().map(|(relative, metadata)| (relative.to_string(), metadata.clone())).collect()
+    }
+
+    fn s3_compare(left: &[(&str, ObjectMetadata)], right: &[(&str, ObjectMetadata)]) -> Diff {
+        compare_listings((CloudProviderKind::S3, &listing(left)), (CloudProviderKind::S3, &listing(right)))
+    }
+
+    #[test]
+    fn reports_each_side_and_what_differs() {
+        let md5 = "5eb63bbbe01eeed093cb22bb8f5acdc3";
+        let other = "0123456789abcdef0123456789abcdef";
+        let diff = s3_compare(
+            &[("same", object(1, md5)), ("changed", object(1, md5)), ("grown", object(1, md5)), ("left", object(1, md5))],
+            &[("same", object(1, md5)), ("changed", object(1, other)), ("grown", object(2, md5)), ("right", object(1, md5))],
+        );
+        assert_eq!(diff.identical, 1);
+        assert_eq!(diff.unverified, 0);
+        assert_eq!(
+            diff.entries,
+            vec![
+                DiffEntry { relative: "changed".to_string(), difference: Difference::Differs("md5 differs".to_string()) },
+                DiffEntry { relative: "grown".to_string(), difference: Difference::Differs("size 1 != 2".to_string()) },
+                DiffEntry { relative: "left".to_string(), difference: Difference::LeftOnly },
+                DiffEntry { relative: "right".to_string(), difference: Difference::RightOnly },
+            ]
+        );
+    }
+
+    #[test]
+    fn unequal_multipart_etags_are_unverified() {
+        let etag = "0123456789abcdef0123456789abcdef";
+        let diff = s3_compare(
+            &[
+                ("same parts", object(9, &format!("{etag}-2"))),
+                ("other parts", object(9, &format!("{etag}-2"))),
+                ("other part size", object(9, &format!("{etag}-2"))),
+            ],
+            &[
+                ("same parts", object(9, &format!("{etag}-2"))),
+                ("other parts", object(9, "fedcba9876543210fedcba9876543210-2")),
+                ("other part size", object(9, &format!("{etag}-3"))),
+            ],
+        );
+        assert!(diff.entries.is_empty(), "{:?}", diff.entries);
+        assert_eq!((diff.identical, diff.unverified), (1, 2));
+    }
+
+    #[test]
+    fn another_checksum_settles_what_the_etags_cannot() {
+        let crc = |value: &str| ObjectMetadata { crc32c: Some(value.to_string()), ..object(9, "0123456789abcdef0123456789abcdef-2") };
+        let diff = s3_compare(&[("a", crc("AAAAAA=="))], &[("a", ObjectMetadata { etag: Some("x-3".to_string()), ..crc("BBBBBB==") })]);
+        assert_eq!(diff.entries.len(), 1);
+        assert_eq!(diff.entries[0].difference, Difference::Differs("crc32c differs".to_string()));
+    }
+}
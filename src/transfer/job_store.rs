@@ -0,0 +1,176 @@
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+use tracing::{error, info};
+
+use crate::logging::get_data_dir;
+use crate::storage::cloud_uri::CloudUri;
+
+use super::{Endpoint, TransferJob, TransferKind, TransferState, UploadSession};
+
+const JOB_STORE_FILE: &str = "transfers.toml";
+/// Failed and cancelled jobs kept for retrying; older ones are dropped
+const MAX_KEPT_STOPPED: usize = 50;
+
+/// On-disk form of a transfer job that has not finished yet
+#[derive(Debug, Serialize, Deserialize)]
+pub struct JobRecord {
+    pub id: usize,
+    pub kind: TransferKind,
+    pub source: String,
+    pub destination: String,
+    pub total_bytes: Option<u64>,
+    /// `None` while queued or running; failed and cancelled jobs keep their reason
+    pub failure: Option<String>,
+    pub cancelled: bool,
+    pub session: Option<UploadSession>,
+    /// ETag of the S3 object a download started from
+    #[serde(default)]
+    pub etag: Option<String>,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct JobStore {
+    jobs: Vec<JobRecord>,
+}
+
+pub fn job_store_path() -> PathBuf {
+    get_data_dir().join(JOB_STORE_FILE)
+}
+
+/// Scratch space for partial upload chunks
+pub fn chunk_dir() -> PathBuf {
+    get_data_dir().join("transfers")
+}
+
+impl From<&TransferJob> for JobRecord {
+    fn from(job: &TransferJob) -> Self {
+        Self {
+            id: job.id,
+            kind: job.kind,
+            source: job.source.to_string(),
+            destination: job.destination.to_string(),
+            total_bytes: job.total_bytes,
+            failure: match &job.state {
+                TransferState::Failed(reason) => Some(reason.clone()),
+                _ => None,
+            },
+            cancelled: job.state == TransferState::Cancelled,
+            session: job.session.clone(),
+            etag: job.etag.clone(),
+        }
+    }
+}
+
+impl JobRecord {
+    /// Jobs that were running or paused when the app stopped go back in the queue
+    pub fn state(&self) -> TransferState {
+        match (&self.failure, self.cancelled) {
+            (_, true) => TransferState::Cancelled,
+            (Some(reason), _) => TransferState::Failed(reason.clone()),
+            (None, false) => TransferState::Queued,
+        }
+    }
+}
+
+impl From<&str> for Endpoint {
+    fn from(value: &str) -> Self {
+        match CloudUri::parse(value) {
+            Ok(uri) => Endpoint::Remote(uri),
+            Err(_) => Endpoint::Local(PathBuf::from(value)),
+        }
+    }
+}
+
+/// Writes every unfinished job to the data directory, with only the newest
+/// `MAX_KEPT_STOPPED` failed or cancelled ones. Callers hold the queue lock, so saves never overlap.
+pub fn save(jobs: &[TransferJob]) {
+    let is_stopped = |job: &TransferJob| matches!(job.state, TransferState::Failed(_) | TransferState::Cancelled);
+    let stopped = jobs.iter().filter(|job| is_stopped(job)).count();
+    let mut to_drop = stopped.saturating_sub(MAX_KEPT_STOPPED);
+    let store = JobStore {
+        jobs: jobs
+            .iter()
+            .filter(|job| job.state != TransferState::Done)
+            .filter(|job| {
+                // jobs are oldest first, so the first stopped ones go
+                if to_drop > 0 && is_stopped(job) {
+                    to_drop -= 1;
+                    false
+                } else {
+                    true
+                }
+            })
+            .map(JobRecord::from)
+            .collect(),
+    };
+    let path = job_store_path();
+    match toml::to_string(&store) {
+        Ok(contents) => {
+            if let Err(e) = write_atomically(&path, &contents) {
+                error!("Could not persist transfers to {}: {e}", path.display());
+            }
+        }
+        Err(e) => error!("Could not serialize transfers: {e}"),
+    }
+}
+
+/// Writes `contents` next to `path` and renames it over `path`, so a crash or another instance
+/// saving at the same time leaves either the old store or the new one, never a truncated file
+fn write_atomically(path: &Path, contents: &str) -> io::Result<()> {
+    let mut temporary = path.as_os_str().to_owned();
+    temporary.push(format!(".{}.tmp", std::process::id()));
+    let temporary = PathBuf::from(temporary);
+    fs::write(&temporary, contents)?;
+    fs::rename(&temporary, path).inspect_err(|_| {
+        let _ = fs::remove_file(&temporary);
+    })
+}
+
+/// Reads the jobs left behind by a previous run
+pub fn load() -> Vec<JobRecord> {
+    let path = job_store_path();
+    let contents = match fs::read_to_string(&path) {
+        Ok(contents) => contents,
+        Err(_) => return Vec::new(),
+    };
+    match toml::from_str::<JobStore>(&contents) {
+        Ok(store) => {
+            info!("Restoring {} transfers from {}", store.jobs.len(), path.display());
+            store.jobs
+        }
+        Err(e) => {
+            error!("Ignoring unreadable transfer state {}: {e}", path.display());
+            Vec::new()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::action::Action;
+
+    #[test]
+    fn writes_replace_the_store_without_leftovers() -> Result<(), Action> {
+        let dir = std::env::temp_dir().join(format!("job-store-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).map_err(|e| Action::Error(e.to_string()))?;
+        let path = dir.join(JOB_STORE_FILE);
+
+        for contents in ["jobs = []\n", "[[jobs]]\n"] {
+            write_atomically(&path, contents).map_err(|e| Action::Error(e.to_string()))?;
+            assert_eq!(fs::read_to_string(&path).unwrap_or_default(), contents);
+        }
+        let names: Vec<_> = fs::read_dir(&dir)
+            .map_err(|e| Action::Error(e.to_string()))?
+            .map_while(Result::ok)
+            .map(|entry| entry.file_name())
+            .collect();
+        assert_eq!(names, vec![JOB_STORE_FILE]);
+        let _ = fs::remove_dir_all(&dir);
+        Ok(())
+    }
+}
@@ -0,0 +1,946 @@
+use std::fmt;
+use std::fs::File;
+use std::io::{Read, Seek, SeekFrom, Write};
+use std::path::{Component, Path, PathBuf};
+use std::process::Child;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use serde::{Deserialize, Serialize};
+use tracing::{error, info};
+
+use crate::action::Action;
+use crate::config::cloud_provider_config::cloud_provider_kind::CloudProviderKind;
+use crate::storage::{self, cloud_uri::CloudUri};
+
+pub mod checksum;
+pub mod diff;
+pub mod job_store;
+pub mod sync;
+
+use checksum::Verification;
+
+/// How many jobs may run at the same time, the rest wait in the queue
+pub const MAX_RUNNING_TRANSFERS: usize = 2;
+
+const CHUNK_SIZE: usize = 256 * 1024;
+
+/// Uploads larger than this are split into parts that are tracked individually,
+/// so an interrupted upload only repeats the part it was working on
+pub const PART_SIZE: u64 = 32 * 1024 * 1024;
+
+/// Suffix of a download that has not finished yet
+const PARTIAL_SUFFIX: &str = ".part";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum TransferKind {
+    Download,
+    Upload,
+    /// Compares a local file with a remote object without transferring anything
+    Verify,
+    Copy,
+    /// Copy followed by deleting the source
+    Move,
+    /// Removes the source, e.g. a file a sync found to be extraneous
+    Delete,
+}
+
+impl fmt::Display for TransferKind {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            TransferKind::Download => write!(f, "Download"),
+            TransferKind::Upload => write!(f, "Upload"),
+            TransferKind::Verify => write!(f, "Verify"),
+            TransferKind::Copy => write!(f, "Copy"),
+            TransferKind::Move => write!(f, "Move"),
+            TransferKind::Delete => write!(f, "Delete"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TransferState {
+    Queued,
+    Running,
+    Done,
+    Failed(String),
+    Cancelled,
+    /// A move or delete interrupted by the last quit, waiting to be resumed by hand
+    Paused,
+}
+
+impl fmt::Display for TransferState {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            TransferState::Queued => write!(f, "queued"),
+            TransferState::Running => write!(f, "running"),
+            TransferState::Done => write!(f, "done"),
+            TransferState::Failed(reason) => write!(f, "failed: {reason}"),
+            TransferState::Cancelled => write!(f, "cancelled"),
+            TransferState::Paused => write!(f, "paused"),
+        }
+    }
+}
+
+/// Provider-side state of a multi-part upload, persisted so it can continue after a restart
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum UploadSession {
+    /// GCS: parts are uploaded as temporary objects and composed at the end
+    Composite { parts_done: usize },
+    /// S3: a multipart upload and the ETags of the parts uploaded so far
+    Multipart { upload_id: String, etags: Vec<String> },
+}
+
+/// Where a job reads from or writes to
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Endpoint {
+    Local(PathBuf),
+    Remote(CloudUri),
+}
+
+impl fmt::Display for Endpoint {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Endpoint::Local(path) => write!(f, "{}", path.display()),
+            Endpoint::Remote(uri) => write!(f, "{uri}"),
+        }
+    }
+}
+
+/// What to queue: the kind of job, its source and destination, and its size when already known
+pub type JobRequest = (TransferKind, Endpoint, Endpoint, Option<u64>);
+
+#[derive(Debug, Clone)]
+pub struct TransferJob {
+    pub id: usize,
+    pub kind: TransferKind,
+    pub source: Endpoint,
+    pub destination: Endpoint,
+    pub total_bytes: Option<u64>,
+    pub bytes_transferred: u64,
+    /// Bytes that were already in place when the job (re)started
+    pub resumed_bytes: u64,
+    pub state: TransferState,
+    pub started_at: Option<Instant>,
+    pub finished_at: Option<Instant>,
+    pub session: Option<UploadSession>,
+    /// ETag of the S3 object a download started from; a partial download only resumes while it still matches
+    pub etag: Option<String>,
+    pub verification: Option<Verification>,
+    cancel: Arc<AtomicBool>,
+}
+
+impl TransferJob {
+    /// Average throughput in bytes per second since the job started, not counting resumed bytes
+    pub fn rate(&self) -> Option<f64> {
+        let started = self.started_at?;
+        let elapsed = self.finished_at.unwrap_or_else(Instant::now).duration_since(started);
+        if elapsed.as_secs_f64() <= 0.0 {
+            return None;
+        }
+        let transferred = self.bytes_transferred.saturating_sub(self.resumed_bytes);
+        Some(transferred as f64 / elapsed.as_secs_f64())
+    }
+
+    pub fn eta(&self) -> Option<Duration> {
+        if self.state != TransferState::Running {
+            return None;
+        }
+        let rate = self.rate().filter(|r| *r > 0.0)?;
+        let remaining = self.total_bytes?.saturating_sub(self.bytes_transferred);
+        Some(Duration::from_secs_f64(remaining as f64 / rate))
+    }
+
+    pub fn is_running(&self) -> bool {
+        self.state == TransferState::Running
+    }
+}
+
+/// Objects yanked in the Viewer, waiting to be pasted somewhere else
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Clipboard {
+    /// `Copy` or `Move`
+    pub kind: TransferKind,
+    pub sources: Vec<CloudUri>,
+}
+
+/// Shared queue of transfer jobs. Cloning it hands out another handle to the same queue.
+#[derive(Debug, Clone, Default)]
+pub struct TransferQueue {
+    jobs: Arc<Mutex<Vec<TransferJob>>>,
+    /// Prefixes whose listing changed because a copy, move or delete finished
+    changed: Arc<Mutex<Vec<CloudUri>>>,
+    /// Never written to the job store, for one-off transfers outside the TUI
+    ephemeral: bool,
+}
+
+impl TransferQueue {
+    pub fn ephemeral() -> Self {
+        Self { ephemeral: true, ..Self::default() }
+    }
+
+    pub fn enqueue(&self, kind: TransferKind, source: Endpoint, destination: Endpoint, total_bytes: Option<u64>) {
+        self.enqueue_all(vec![(kind, source, destination, total_bytes)]);
+    }
+
+    /// Queues every job of one operation and saves the store once
+    pub fn enqueue_all(&self, requests: Vec<JobRequest>) {
+        if requests.is_empty() {
+            return;
+        }
+        if let Ok(mut jobs) = self.jobs.lock() {
+            let first = jobs.last().map(|job| job.id + 1).unwrap_or_default();
+            for (offset, (kind, source, destination, total_bytes)) in requests.into_iter().enumerate() {
+                let id = first + offset;
+                info!("Queueing transfer {id}: {kind} {source} -> {destination}");
+                jobs.push(TransferJob {
+                    id,
+                    kind,
+                    source,
+                    destination,
+                    total_bytes,
+                    bytes_transferred: 0,
+                    resumed_bytes: 0,
+                    state: TransferState::Queued,
+                    started_at: None,
+                    finished_at: None,
+                    session: None,
+                    etag: None,
+                    verification: None,
+                    cancel: Arc::new(AtomicBool::new(false)),
+                });
+            }
+            self.save(&jobs);
+        }
+    }
+
+    /// Brings back the jobs a previous run did not finish. Interrupted transfers are queued again,
+    /// interrupted moves and deletes are paused until someone retries them.
+    pub fn restore(&self) {
+        let records = job_store::load();
+        if let Ok(mut jobs) = self.jobs.lock() {
+            jobs.extend(records.into_iter().map(|record| TransferJob {
+                id: record.id,
+                kind: record.kind,
+                source: record.source.as_str().into(),
+                destination: record.destination.as_str().into(),
+                total_bytes: record.total_bytes,
+                bytes_transferred: 0,
+                resumed_bytes: 0,
+                state: match record.state() {
+                    TransferState::Queued if matches!(record.kind, TransferKind::Move | TransferKind::Delete) => {
+                        TransferState::Paused
+                    }
+                    state => state,
+                },
+                started_at: None,
+                finished_at: None,
+                session: record.session,
+                etag: record.etag,
+                verification: None,
+                cancel: Arc::new(AtomicBool::new(false)),
+            }));
+            jobs.sort_by_key(|job| job.id);
+        }
+    }
+
+    fn persist(&self) {
+        if let Ok(jobs) = self.jobs.lock() {
+            self.save(&jobs);
+        }
+    }
+
+    /// Takes the guard rather than the jobs, so every save happens while holding the queue lock
+    fn save(&self, jobs: &MutexGuard<'_, Vec<TransferJob>>) {
+        if !self.ephemeral {
+            job_store::save(jobs);
+        }
+    }
+
+    /// Copy of every job, oldest first
+    pub fn snapshot(&self) -> Vec<TransferJob> {
+        match self.jobs.lock() {
+            Ok(jobs) => jobs.clone(),
+            Err(_) => Vec::new(),
+        }
+    }
+
+    /// Prefixes changed by finished copies and moves since the last call, each listed once
+    pub fn take_changed_prefixes(&self) -> Vec<CloudUri> {
+        match self.changed.lock() {
+            Ok(mut changed) => {
+                let mut prefixes: Vec<CloudUri> = vec![];
+                for prefix in changed.drain(..) {
+                    if !prefixes.contains(&prefix) {
+                        prefixes.push(prefix);
+                    }
+                }
+                prefixes
+            }
+            Err(_) => Vec::new(),
+        }
+    }
+
+    pub fn running_count(&self) -> usize {
+        self.snapshot().iter().filter(|job| job.is_running()).count()
+    }
+
+    /// Nothing is queued or running any more
+    pub fn is_idle(&self) -> bool {
+        self.snapshot()
+            .iter()
+            .all(|job| !matches!(job.state, TransferState::Queued | TransferState::Running))
+    }
+
+    /// Starts queued jobs until `MAX_RUNNING_TRANSFERS` are running
+    pub fn start_queued(&self) {
+        let to_start: Vec<TransferJob> = match self.jobs.lock() {
+            Err(_) => return,
+            Ok(mut jobs) => {
+                let running = jobs.iter().filter(|job| job.is_running()).count();
+                let free = MAX_RUNNING_TRANSFERS.saturating_sub(running);
+                jobs.iter_mut()
+                    .filter(|job| job.state == TransferState::Queued)
+                    .take(free)
+                    .map(|job| {
+                        job.state = TransferState::Running;
+                        job.started_at = Some(Instant::now());
+                        job.finished_at = None;
+                        job.bytes_transferred = 0;
+                        job.resumed_bytes = 0;
+                        job.verification = None;
+                        job.clone()
+                    })
+                    .collect()
+            }
+        };
+
+        for job in to_start {
+            let queue = self.clone();
+            thread::spawn(move || {
+                let id = job.id;
+                let result = run_job(&queue, &job);
+                queue.finish(id, result);
+            });
+        }
+    }
+
+    /// Puts a failed, cancelled or paused job back in the queue, it resumes where it stopped
+    pub fn retry(&self, id: usize) -> Result<(), Action> {
+        self.update(id, |job| match job.state {
+            TransferState::Failed(_) | TransferState::Cancelled | TransferState::Paused => {
+                job.state = TransferState::Queued;
+                job.cancel.store(false, Ordering::SeqCst);
+                Ok(())
+            }
+            _ => Err(Action::Error("Only failed, cancelled or paused transfers can be retried".to_string())),
+        })?;
+        self.persist();
+        Ok(())
+    }
+
+    pub fn cancel(&self, id: usize) -> Result<(), Action> {
+        let result = self.update(id, |job| match job.state {
+            TransferState::Queued => {
+                job.state = TransferState::Cancelled;
+                Ok(())
+            }
+            TransferState::Running => {
+                job.cancel.store(true, Ordering::SeqCst);
+                Ok(())
+            }
+            _ => Err(Action::Error("Transfer is not running".to_string())),
+        });
+        self.persist();
+        result
+    }
+
+    fn update<T>(&self, id: usize, f: impl FnOnce(&mut TransferJob) -> Result<T, Action>) -> Result<T, Action> {
+        match self.jobs.lock() {
+            Err(_) => Err(Action::Error("Transfer queue is unavailable".to_string())),
+            Ok(mut jobs) => match jobs.iter_mut().find(|job| job.id == id) {
+                Some(job) => f(job),
+                None => Err(Action::Error(format!("No transfer with id {id}"))),
+            },
+        }
+    }
+
+    fn add_progress(&self, id: usize, bytes: u64) {
+        let _ = self.update(id, |job| {
+            job.bytes_transferred += bytes;
+            Ok(())
+        });
+    }
+
+    /// Records bytes that were already transferred by an earlier attempt
+    fn set_resumed(&self, id: usize, bytes: u64) {
+        let _ = self.update(id, |job| {
+            job.bytes_transferred = bytes;
+            job.resumed_bytes = bytes;
+            Ok(())
+        });
+    }
+
+    fn set_session(&self, id: usize, session: Option<UploadSession>) {
+        let _ = self.update(id, |job| {
+            job.session = session;
+            Ok(())
+        });
+        self.persist();
+    }
+
+    fn set_etag(&self, id: usize, etag: Option<String>) {
+        let _ = self.update(id, |job| {
+            job.etag = etag;
+            Ok(())
+        });
+        self.persist();
+    }
+
+    fn set_verification(&self, id: usize, verification: Verification) {
+        let _ = self.update(id, |job| {
+            job.verification = Some(verification);
+            Ok(())
+        });
+    }
+
+    fn set_total(&self, id: usize, total: u64) {
+        let _ = self.update(id, |job| {
+            job.total_bytes = Some(total);
+            Ok(())
+        });
+    }
+
+    fn finish(&self, id: usize, result: Result<(), String>) {
+        let mut changed = vec![];
+        let _ = self.update(id, |job| {
+            if let (TransferKind::Copy | TransferKind::Move, Endpoint::Remote(destination)) = (job.kind, &job.destination) {
+                changed.push(destination.parent());
+            }
+            if let (TransferKind::Move | TransferKind::Delete, Endpoint::Remote(source)) = (job.kind, &job.source) {
+                changed.push(source.parent());
+            }
+            job.finished_at = Some(Instant::now());
+            job.state = match result {
+                Ok(()) => {
+                    info!("Transfer {id} done");
+                    TransferState::Done
+                }
+                Err(_) if job.cancel.load(Ordering::SeqCst) => TransferState::Cancelled,
+                Err(reason) => {
+                    error!("Transfer {id} failed: {reason}");
+                    TransferState::Failed(reason)
+                }
+            };
+            Ok(())
+        });
+        if let Ok(mut prefixes) = self.changed.lock() {
+            prefixes.extend(changed);
+        }
+        self.persist();
+    }
+}
+
+fn run_job(queue: &TransferQueue, job: &TransferJob) -> Result<(), String> {
+    match (job.kind, &job.source, &job.destination) {
+        (TransferKind::Verify, Endpoint::Local(local), Endpoint::Remote(remote)) => {
+            let total = std::fs::metadata(local).map_err(|e| e.to_string())?.len();
+            queue.set_total(job.id, total);
+            let verification = checksum::verify(local, remote, &mut |bytes| queue.add_progress(job.id, bytes))?;
+            queue.set_verification(job.id, verification);
+            Ok(())
+        }
+        (_, Endpoint::Remote(source), Endpoint::Local(destination)) => {
+            download(queue, job, source, destination)?;
+            verify_transfer(queue, job, destination, source)
+        }
+        (_, Endpoint::Local(source), Endpoint::Remote(destination)) => {
+            upload(queue, job, source, destination)?;
+            verify_transfer(queue, job, source, destination)
+        }
+        (TransferKind::Delete, Endpoint::Remote(target), _) => storage::remove(target).map_err(action_message),
+        (TransferKind::Delete, Endpoint::Local(target), _) => std::fs::remove_file(target).map_err(|e| e.to_string()),
+        (TransferKind::Move, Endpoint::Remote(source), Endpoint::Remote(destination)) => {
+            copy(queue, job, source, destination)?;
+            storage::remove(source).map_err(action_message)
+        }
+        (_, Endpoint::Remote(source), Endpoint::Remote(destination)) => copy(queue, job, source, destination),
+        (_, Endpoint::Local(_), Endpoint::Local(_)) => Err("Local to local transfers are not supported".to_string()),
+    }
+}
+
+/// Checks a finished transfer against the provider's checksum; a mismatch fails the job
+fn verify_transfer(queue: &TransferQueue, job: &TransferJob, local: &Path, remote: &CloudUri) -> Result<(), String> {
+    let verification = checksum::verify(local, remote, &mut |_| ())?;
+    let mismatch = matches!(verification, Verification::Mismatch(_));
+    queue.set_verification(job.id, verification);
+    if mismatch {
+        Err("checksum mismatch".to_string())
+    } else {
+        Ok(())
+    }
+}
+
+fn download(queue: &TransferQueue, job: &TransferJob, source: &CloudUri, destination: &Path) -> Result<(), String> {
+    let total = match job.total_bytes {
+        Some(total) => total,
+        None => {
+            let total = storage::object_size(source).map_err(action_message)?;
+            queue.set_total(job.id, total);
+            total
+        }
+    };
+    if let Some(parent) = destination.parent() {
+        std::fs::create_dir_all(parent).map_err(|e| e.to_string())?;
+    }
+
+    // whatever made it to disk last time is kept and the rest is requested as a ranged read
+    let partial = partial_path(destination);
+    let mut offset = std::fs::metadata(&partial).map(|m| m.len()).unwrap_or_default();
+    if offset > total {
+        offset = 0;
+    }
+
+    // an S3 object replaced since the partial download started has to be downloaded again
+    let etag = match source.kind {
+        CloudProviderKind::S3 => storage::object_metadata(source).map_err(action_message)?.etag,
+        _ => None,
+    };
+    if offset > 0 && source.kind == CloudProviderKind::S3 && (job.etag.is_none() || job.etag != etag) {
+        info!("{source} changed since its download started, starting over");
+        offset = 0;
+    }
+    if offset == 0 && source.kind == CloudProviderKind::S3 {
+        queue.set_etag(job.id, etag.clone());
+    }
+    let mut file = std::fs::OpenOptions::new()
+        .create(true)
+        .write(true)
+        .truncate(offset == 0)
+        .open(&partial)
+        .map_err(|e| e.to_string())?;
+    file.seek(SeekFrom::Start(offset)).map_err(|e| e.to_string())?;
+    if offset > 0 {
+        info!("Resuming download of {source} at byte {offset}");
+        queue.set_resumed(job.id, offset);
+    }
+
+    if offset < total && offset > 0 && source.kind == CloudProviderKind::S3 {
+        download_s3_range(queue, job, source, etag.as_deref().unwrap_or_default(), offset, &mut file)?;
+    } else if offset < total {
+        let mut child = storage::open_reader(source, offset).map_err(action_message)?;
+        let stderr = drain_stderr(&mut child);
+        let mut stdout = match child.stdout.take() {
+            Some(stdout) => stdout,
+            None => return Err("No output from download process".to_string()),
+        };
+
+        let mut buffer = vec![0; CHUNK_SIZE];
+        loop {
+            if job.cancel.load(Ordering::SeqCst) {
+                let _ = child.kill();
+                return Err("cancelled".to_string());
+            }
+            let read = stdout.read(&mut buffer).map_err(|e| e.to_string())?;
+            if read == 0 {
+                break;
+            }
+            file.write_all(&buffer[..read]).map_err(|e| e.to_string())?;
+            queue.add_progress(job.id, read as u64);
+        }
+
+        wait_for(child, stderr)?;
+    }
+
+    file.flush().map_err(|e| e.to_string())?;
+    std::fs::rename(&partial, destination).map_err(|e| e.to_string())
+}
+
+/// Reads the rest of an S3 object from `offset` into a scratch file, counting progress as it grows,
+/// and appends it to the partial download once the read succeeded for the expected `etag`
+fn download_s3_range(queue: &TransferQueue, job: &TransferJob, source: &CloudUri, etag: &str, offset: u64, file: &mut File) -> Result<(), String> {
+    let chunk_dir = job_store::chunk_dir();
+    std::fs::create_dir_all(&chunk_dir).map_err(|e| e.to_string())?;
+    let range_path = chunk_dir.join(format!("{}.range", job.id));
+
+    let mut child = storage::open_s3_range_reader(source, offset, etag, &range_path).map_err(action_message)?;
+    let stderr = drain_stderr(&mut child);
+    let mut reported = 0;
+    loop {
+        if job.cancel.load(Ordering::SeqCst) {
+            let _ = child.kill();
+            let _ = std::fs::remove_file(&range_path);
+            return Err("cancelled".to_string());
+        }
+        let written = std::fs::metadata(&range_path).map(|m| m.len()).unwrap_or_default();
+        queue.add_progress(job.id, written.saturating_sub(reported));
+        reported = reported.max(written);
+        match child.try_wait() {
+            Ok(Some(_)) => break,
+            Ok(None) => thread::sleep(Duration::from_millis(200)),
+            Err(e) => return Err(e.to_string()),
+        }
+    }
+
+    let mut returned = String::new();
+    if let Some(mut stdout) = child.stdout.take() {
+        let _ = stdout.read_to_string(&mut returned);
+    }
+    let result = wait_for(child, stderr).and_then(|_| match returned.trim().trim_matches('"') {
+        returned if returned == etag => Ok(()),
+        returned => Err(format!("{source} changed while resuming (ETag {returned}, expected {etag})")),
+    });
+    let appended = result.and_then(|_| {
+        let mut range = File::open(&range_path).map_err(|e| e.to_string())?;
+        std::io::copy(&mut range, file).map_err(|e| e.to_string())
+    });
+    let _ = std::fs::remove_file(&range_path);
+    queue.add_progress(job.id, appended?.saturating_sub(reported));
+    Ok(())
+}
+
+fn upload(queue: &TransferQueue, job: &TransferJob, source: &Path, destination: &CloudUri) -> Result<(), String> {
+    let total = std::fs::metadata(source).map_err(|e| e.to_string())?.len();
+    queue.set_total(job.id, total);
+
+    match destination.kind {
+        CloudProviderKind::Gcs if total > PART_SIZE => upload_composite(queue, job, source, destination, total),
+        CloudProviderKind::S3 if total > PART_SIZE => upload_multipart(queue, job, source, destination, total),
+        // the az CLI cannot stage blocks, so Azure uploads always start over
+        _ => {
+            let mut file = File::open(source).map_err(|e| e.to_string())?;
+            stream_to_object(queue, job, &mut file, total, destination)
+        }
+    }
+}
+
+/// Uploads each part as a temporary object, then composes them into the destination
+fn upload_composite(queue: &TransferQueue, job: &TransferJob, source: &Path, destination: &CloudUri, total: u64) -> Result<(), String> {
+    let num_parts = total.div_ceil(PART_SIZE) as usize;
+    let part_uri = |idx: usize| {
+        let mut uri = destination.clone();
+        uri.path = format!("{}.cstv-part-{idx:05}", destination.path);
+        uri
+    };
+
+    let parts_done = match &job.session {
+        Some(UploadSession::Composite { parts_done }) => *parts_done,
+        _ => 0,
+    };
+    if parts_done > 0 {
+        info!("Resuming upload of {destination} at part {parts_done} of {num_parts}");
+        queue.set_resumed(job.id, (parts_done as u64 * PART_SIZE).min(total));
+    }
+
+    let mut file = File::open(source).map_err(|e| e.to_string())?;
+    for idx in parts_done..num_parts {
+        let start = idx as u64 * PART_SIZE;
+        file.seek(SeekFrom::Start(start)).map_err(|e| e.to_string())?;
+        let mut part = (&mut file).take(PART_SIZE);
+        stream_to_object(queue, job, &mut part, PART_SIZE.min(total - start), &part_uri(idx))?;
+        queue.set_session(job.id, Some(UploadSession::Composite { parts_done: idx + 1 }));
+    }
+
+    // compose in batches, folding the destination back in as the first source each round
+    let parts: Vec<CloudUri> = (0..num_parts).map(part_uri).collect();
+    let mut remaining = parts.as_slice();
+    let mut first = true;
+    while !remaining.is_empty() {
+        let take = if first {
+            storage::MAX_COMPOSE_SOURCES
+        } else {
+            storage::MAX_COMPOSE_SOURCES - 1
+        };
+        let (batch, rest) = remaining.split_at(take.min(remaining.len()));
+        let mut sources = vec![];
+        if !first {
+            sources.push(destination.clone());
+        }
+        sources.extend_from_slice(batch);
+        storage::compose(&sources, destination).map_err(action_message)?;
+        remaining = rest;
+        first = false;
+    }
+
+    for part in &parts {
+        if let Err(e) = storage::remove(part) {
+            error!("Could not remove temporary part {part}: {}", action_message(e));
+        }
+    }
+    queue.set_session(job.id, None);
+    Ok(())
+}
+
+/// Uploads parts through an S3 multipart upload, whose id survives restarts
+fn upload_multipart(queue: &TransferQueue, job: &TransferJob, source: &Path, destination: &CloudUri, total: u64) -> Result<(), String> {
+    let num_parts = total.div_ceil(PART_SIZE) as usize;
+    let (upload_id, mut etags) = match &job.session {
+        Some(UploadSession::Multipart { upload_id, etags }) => (upload_id.clone(), etags.clone()),
+        _ => {
+            let upload_id = storage::create_multipart_upload(destination).map_err(action_message)?;
+            queue.set_session(job.id, Some(UploadSession::Multipart { upload_id: upload_id.clone(), etags: vec![] }));
+            (upload_id, vec![])
+        }
+    };
+    if !etags.is_empty() {
+        info!("Resuming multipart upload of {destination} at part {} of {num_parts}", etags.len());
+        queue.set_resumed(job.id, (etags.len() as u64 * PART_SIZE).min(total));
+    }
+
+    let chunk_dir = job_store::chunk_dir();
+    std::fs::create_dir_all(&chunk_dir).map_err(|e| e.to_string())?;
+    let chunk_path = chunk_dir.join(format!("{}.chunk", job.id));
+
+    let mut file = File::open(source).map_err(|e| e.to_string())?;
+    for idx in etags.len()..num_parts {
+        if job.cancel.load(Ordering::SeqCst) {
+            return Err("cancelled".to_string());
+        }
+        let start = idx as u64 * PART_SIZE;
+        file.seek(SeekFrom::Start(start)).map_err(|e| e.to_string())?;
+        let mut chunk = File::create(&chunk_path).map_err(|e| e.to_string())?;
+        let written = std::io::copy(&mut (&mut file).take(PART_SIZE), &mut chunk).map_err(|e| e.to_string())?;
+        drop(chunk);
+
+        let etag = storage::upload_part(destination, &upload_id, idx + 1, &chunk_path).map_err(action_message)?;
+        etags.push(etag);
+        queue.add_progress(job.id, written);
+        queue.set_session(job.id, Some(UploadSession::Multipart { upload_id: upload_id.clone(), etags: etags.clone() }));
+    }
+    let _ = std::fs::remove_file(&chunk_path);
+
+    storage::complete_multipart_upload(destination, &upload_id, &etags).map_err(action_message)?;
+    queue.set_session(job.id, None);
+    Ok(())
+}
+
+/// Pipes `reader` into a writer process for `destination`, counting progress as it goes
+fn stream_to_object(queue: &TransferQueue, job: &TransferJob, reader: &mut impl Read, len: u64, destination: &CloudUri) -> Result<(), String> {
+    let mut child = storage::open_writer(destination).map_err(action_message)?;
+    let stderr = drain_stderr(&mut child);
+    let mut stdin = match child.stdin.take() {
+        Some(stdin) => stdin,
+        None => return Err("No input for upload process".to_string()),
+    };
+
+    let mut buffer = vec![0; CHUNK_SIZE];
+    let mut sent = 0;
+    while sent < len {
+        if job.cancel.load(Ordering::SeqCst) {
+            let _ = child.kill();
+            return Err("cancelled".to_string());
+        }
+        let read = reader.read(&mut buffer).map_err(|e| e.to_string())?;
+        if read == 0 {
+            break;
+        }
+        stdin.write_all(&buffer[..read]).map_err(|e| e.to_string())?;
+        sent += read as u64;
+        queue.add_progress(job.id, read as u64);
+    }
+    drop(stdin);
+
+    wait_for(child, stderr)
+}
+
+fn copy(queue: &TransferQueue, job: &TransferJob, source: &CloudUri, destination: &CloudUri) -> Result<(), String> {
+    if source.kind != destination.kind {
+        return stream_copy(queue, job, source, destination);
+    }
+    let total = match job.total_bytes {
+        Some(total) => total,
+        None => {
+            let total = storage::object_size(source).map_err(action_message)?;
+            queue.set_total(job.id, total);
+            total
+        }
+    };
+    storage::copy(source, destination).map_err(action_message)?;
+    queue.add_progress(job.id, total);
+    Ok(())
+}
+
+/// Copies between providers by piping one CLI's download into another's upload.
+/// The bytes are hashed on the way through and checked against both ends.
+fn stream_copy(queue: &TransferQueue, job: &TransferJob, source: &CloudUri, destination: &CloudUri) -> Result<(), String> {
+    let source_metadata = storage::object_metadata(source).map_err(action_message)?;
+    queue.set_total(job.id, source_metadata.size);
+
+    let mut reader = storage::open_reader(source, 0).map_err(action_message)?;
+    let reader_stderr = drain_stderr(&mut reader);
+    let mut stdout = match reader.stdout.take() {
+        Some(stdout) => stdout,
+        None => return Err("No output from download process".to_string()),
+    };
+    let mut writer = storage::open_writer(destination).map_err(action_message)?;
+    let writer_stderr = drain_stderr(&mut writer);
+    let mut stdin = match writer.stdin.take() {
+        Some(stdin) => stdin,
+        None => return Err("No input for upload process".to_string()),
+    };
+
+    let mut digester = checksum::Digester::any_multipart(source_metadata.size);
+    // either end may store an additional SHA-256 checksum on S3
+    if source.kind == CloudProviderKind::S3 || destination.kind == CloudProviderKind::S3 {
+        digester = digester.with_sha256();
+    }
+    let mut buffer = vec![0; CHUNK_SIZE];
+    loop {
+        if job.cancel.load(Ordering::SeqCst) {
+            let _ = reader.kill();
+            let _ = writer.kill();
+            return Err("cancelled".to_string());
+        }
+        let read = stdout.read(&mut buffer).map_err(|e| e.to_string())?;
+        if read == 0 {
+            break;
+        }
+        digester.update(&buffer[..read]);
+        stdin.write_all(&buffer[..read]).map_err(|e| e.to_string())?;
+        queue.add_progress(job.id, read as u64);
+    }
+    drop(stdin);
+
+    wait_for(reader, reader_stderr)?;
+    wait_for(writer, writer_stderr)?;
+
+    let digests = digester.finish();
+    let destination_metadata = storage::object_metadata(destination).map_err(action_message)?;
+    let verification = checksum::combine(
+        checksum::compare_digests(source.kind, &source_metadata, &digests),
+        checksum::compare_digests(destination.kind, &destination_metadata, &digests),
+    );
+    let mismatch = matches!(verification, Verification::Mismatch(_));
+    queue.set_verification(job.id, verification);
+    if mismatch {
+        Err("checksum mismatch".to_string())
+    } else {
+        Ok(())
+    }
+}
+
+/// Streams a whole object into `out`. A reader that goes away early, like `head`, is not an error.
+pub fn read_object(uri: &CloudUri, out: &mut impl Write) -> Result<(), String> {
+    let mut child = storage::open_reader(uri, 0).map_err(action_message)?;
+    let stderr = drain_stderr(&mut child);
+    let mut stdout = match child.stdout.take() {
+        Some(stdout) => stdout,
+        None => return Err("No output from download process".to_string()),
+    };
+    match std::io::copy(&mut stdout, out) {
+        Ok(_) => wait_for(child, stderr),
+        Err(e) if e.kind() == std::io::ErrorKind::BrokenPipe => {
+            let _ = child.kill();
+            Ok(())
+        }
+        Err(e) => {
+            let _ = child.kill();
+            Err(e.to_string())
+        }
+    }
+}
+
+/// Reads the child's stderr on its own thread so a chatty CLI can never fill the pipe and stall
+fn drain_stderr(child: &mut Child) -> Option<thread::JoinHandle<String>> {
+    child.stderr.take().map(|mut stderr| {
+        thread::spawn(move || {
+            let mut message = String::new();
+            let _ = stderr.read_to_string(&mut message);
+            message
+        })
+    })
+}
+
+fn wait_for(mut child: Child, stderr: Option<thread::JoinHandle<String>>) -> Result<(), String> {
+    let status = child.wait().map_err(|e| e.to_string())?;
+    let message = stderr.and_then(|handle| handle.join().ok()).unwrap_or_default();
+    if status.success() {
+        Ok(())
+    } else {
+        Err(message.trim().lines().next_back().unwrap_or("transfer process failed").to_string())
+    }
+}
+
+pub fn action_message(action: Action) -> String {
+    match action {
+        Action::Error(message) => message,
+        other => format!("{other:?}"),
+    }
+}
+
+fn partial_path(destination: &Path) -> PathBuf {
+    let mut partial = destination.as_os_str().to_owned();
+    partial.push(PARTIAL_SUFFIX);
+    PathBuf::from(partial)
+}
+
+/// Where downloads land unless told otherwise
+pub fn default_download_dir() -> PathBuf {
+    dirs::download_dir()
+        .or_else(dirs::home_dir)
+        .unwrap_or_else(|| PathBuf::from("."))
+}
+
+/// Where the object `key`, '/' separated and relative to what is being downloaded, lands under
+/// `base`. `None` when a segment is empty, `.`, `..` or not a plain file name here, since the file
+/// would end up outside `base`.
+pub fn local_destination(base: &Path, key: &str) -> Option<PathBuf> {
+    let mut path = base.to_path_buf();
+    for segment in key.split('/') {
+        let mut components = Path::new(segment).components();
+        match (components.next(), components.next()) {
+            (Some(Component::Normal(name)), None) if name == segment => path.push(name),
+            _ => return None,
+        }
+    }
+    Some(path)
+}
+
+/// Expands a leading `~/` to the user's home directory
+pub fn expand_local_path(input: &str) -> PathBuf {
+    match (input.strip_prefix("~/"), dirs::home_dir()) {
+        (Some(rest), Some(home)) => home.join(rest),
+        _ => PathBuf::from(input),
+    }
+}
+
+/// Every file below `directory`, recursively
+pub fn local_files(directory: &Path) -> Result<Vec<PathBuf>, Action> {
+    let mut files = vec![];
+    let entries = match std::fs::read_dir(directory) {
+        Ok(entries) => entries,
+        Err(e) => return Err(Action::Error(format!("Cannot read {}: {e}", directory.display()))),
+    };
+    for entry in entries.map_while(Result::ok) {
+        let path = entry.path();
+        if path.is_dir() {
+            files.extend(local_files(&path)?);
+        } else {
+            files.push(path);
+        }
+    }
+    Ok(files)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn keys_stay_below_the_base() {
+        let base = Path::new("downloads");
+        assert_eq!(local_destination(base, "a/b.txt"), Some(base.join("a").join("b.txt")));
+        assert_eq!(local_destination(base, "..."), Some(base.join("...")));
+        assert_eq!(local_destination(base, ".hidden"), Some(base.join(".hidden")));
+    }
+
+    #[test]
+    fn keys_that_would_escape_are_refused() {
+        let base = Path::new("downloads");
+        for key in ["../x", "a/../../x", "..", "/etc/passwd", "a//b", "./a", "a/.", "", "a/"] {
+            assert_eq!(local_destination(base, key), None, "{key}");
+        }
+    }
+}
@@ -0,0 +1,325 @@
+use std::collections::HashMap;
+use std::fmt;
+use std::path::{Path, PathBuf};
+use std::time::UNIX_EPOCH;
+
+use tracing::info;
+
+use crate::action::Action;
+use crate::storage::{self, cloud_uri::CloudUri, ObjectEntry, ObjectMetadata};
+use crate::util;
+
+use super::{checksum, local_destination, local_files, Endpoint, JobRequest, TransferKind};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SyncDirection {
+    /// Local directory -> remote prefix
+    Up,
+    /// Remote prefix -> local directory
+    Down,
+}
+
+impl fmt::Display for SyncDirection {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            SyncDirection::Up => write!(f, "local -> remote"),
+            SyncDirection::Down => write!(f, "remote -> local"),
+        }
+    }
+}
+
+/// How two files with the same name are judged to differ
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SyncCompare {
+    /// Different size, or the source is newer than the target
+    SizeMtime,
+    /// Different size, or the provider's checksum does not match the local file
+    Checksum,
+}
+
+impl fmt::Display for SyncCompare {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            SyncCompare::SizeMtime => write!(f, "size/mtime"),
+            SyncCompare::Checksum => write!(f, "checksum"),
+        }
+    }
+}
+
+/// Everything needed to plan a sync; the review screen edits it and asks for a new plan
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SyncRequest {
+    pub local: PathBuf,
+    pub remote: CloudUri,
+    pub direction: SyncDirection,
+    pub compare: SyncCompare,
+    pub delete_extraneous: bool,
+}
+
+impl SyncRequest {
+    pub fn new(local: PathBuf, remote: CloudUri) -> Self {
+        Self {
+            local,
+            remote,
+            direction: SyncDirection::Down,
+            compare: SyncCompare::SizeMtime,
+            delete_extraneous: false,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SyncAction {
+    Add,
+    Update,
+    Delete,
+}
+
+impl fmt::Display for SyncAction {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            SyncAction::Add => write!(f, "add"),
+            SyncAction::Update => write!(f, "update"),
+            SyncAction::Delete => write!(f, "delete"),
+        }
+    }
+}
+
+/// One line of the preview
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SyncEntry {
+    pub action: SyncAction,
+    /// Path relative to the synced directory, using `/`
+    pub relative: String,
+    pub size: u64,
+    pub excluded: bool,
+}
+
+/// A file on either side, keyed by its relative path
+#[derive(Debug, Clone)]
+struct SideEntry {
+    size: u64,
+    /// Seconds since the epoch
+    modified: Option<u64>,
+    /// What the remote listing reported, only listed when comparing checksums
+    metadata: Option<ObjectMetadata>,
+}
+
+/// Works out what has to be added, updated and deleted to make the target match the source
+pub fn plan(request: &SyncRequest) -> Result<Vec<SyncEntry>, Action> {
+    let local = local_entries(&request.local)?;
+    let remote = remote_entries(&request.remote, request.compare)?;
+    plan_sides(request, &local, remote)
+}
+
+/// The plan for both sides' files. Remote keys without a path inside the local directory, such as
+/// ones with `..` segments, are left out, so nothing is written or deleted outside of it.
+fn plan_sides(
+    request: &SyncRequest,
+    local: &HashMap<String, SideEntry>,
+    mut remote: HashMap<String, SideEntry>,
+) -> Result<Vec<SyncEntry>, Action> {
+    remote.retain(|relative, _| {
+        let inside = local_destination(&request.local, relative).is_some();
+        if !inside {
+            info!("Leaving {relative} out of the sync, it would land outside {}", request.local.display());
+        }
+        inside
+    });
+    let (source, target) = match request.direction {
+        SyncDirection::Up => (local, &remote),
+        SyncDirection::Down => (&remote, local),
+    };
+
+    let mut entries = vec![];
+    for (relative, source_entry) in source {
+        let action = match target.get(relative) {
+            None => Some(SyncAction::Add),
+            Some(target_entry) if differs(request, relative, source_entry, target_entry)? => Some(SyncAction::Update),
+            Some(_) => None,
+        };
+        if let Some(action) = action {
+            entries.push(SyncEntry { action, relative: relative.clone(), size: source_entry.size, excluded: false });
+        }
+    }
+    if request.delete_extraneous {
+        for (relative, target_entry) in target {
+            if !source.contains_key(relative) {
+                entries.push(SyncEntry {
+                    action: SyncAction::Delete,
+                    relative: relative.clone(),
+                    size: target_entry.size,
+                    excluded: false,
+                });
+            }
+        }
+    }
+
+    entries.sort_by(|a, b| a.relative.cmp(&b.relative));
+    Ok(entries)
+}
+
+/// The transfer each included entry turns into: kind, source, destination
+pub fn jobs(request: &SyncRequest, entries: &[SyncEntry]) -> Vec<JobRequest> {
+    entries
+        .iter()
+        .filter(|entry| !entry.excluded)
+        .filter_map(|entry| {
+            let local = Endpoint::Local(local_destination(&request.local, &entry.relative)?);
+            let remote = Endpoint::Remote(request.remote.join(&entry.relative));
+            Some(match (entry.action, request.direction) {
+                (SyncAction::Delete, SyncDirection::Up) => (TransferKind::Delete, remote.clone(), remote, None),
+                (SyncAction::Delete, SyncDirection::Down) => (TransferKind::Delete, local.clone(), local, None),
+                (_, SyncDirection::Up) => (TransferKind::Upload, local, remote, None),
+                (_, SyncDirection::Down) => (TransferKind::Download, remote, local, None),
+            })
+        })
+        .collect()
+}
+
+fn differs(request: &SyncRequest, relative: &str, source: &SideEntry, target: &SideEntry) -> Result<bool, Action> {
+    if source.size != target.size {
+        return Ok(true);
+    }
+    match request.compare {
+        SyncCompare::SizeMtime => Ok(match (source.modified, target.modified) {
+            (Some(source), Some(target)) => source > target,
+            _ => false,
+        }),
+        SyncCompare::Checksum => {
+            let remote = match request.direction {
+                SyncDirection::Up => target,
+                SyncDirection::Down => source,
+            };
+            let Some(metadata) = &remote.metadata else {
+                return Ok(false);
+            };
+            let Some(local) = local_destination(&request.local, relative) else {
+                return Ok(true);
+            };
+            match checksum::verify_against(&local, request.remote.kind, metadata, &mut |_| ()) {
+                Ok(checksum::Verification::Mismatch(_)) => Ok(true),
+                Ok(_) => Ok(false),
+                Err(e) => Err(Action::Error(format!("Could not compare {relative}: {e}"))),
+            }
+        }
+    }
+}
+
+fn local_entries(directory: &Path) -> Result<HashMap<String, SideEntry>, Action> {
+    // a directory that does not exist yet is simply empty, it is created by the first download
+    if !directory.exists() {
+        return Ok(HashMap::new());
+    }
+    let mut entries = HashMap::new();
+    for file in local_files(directory)? {
+        let Ok(relative) = file.strip_prefix(directory) else {
+            continue;
+        };
+        let relative = relative
+            .components()
+            .map(|component| component.as_os_str().to_string_lossy().to_string())
+            .collect::<Vec<String>>()
+            .join("/");
+        let metadata = std::fs::metadata(&file).map_err(|e| Action::Error(format!("Cannot read {}: {e}", file.display())))?;
+        let modified = metadata
+            .modified()
+            .ok()
+            .and_then(|modified| modified.duration_since(UNIX_EPOCH).ok())
+            .map(|since| since.as_secs());
+        entries.insert(relative, SideEntry { size: metadata.len(), modified, metadata: None });
+    }
+    Ok(entries)
+}
+
+fn remote_entries(prefix: &CloudUri, compare: SyncCompare) -> Result<HashMap<String, SideEntry>, Action> {
+    let relative_to_prefix = |uri: &CloudUri| {
+        let relative = uri.relative_to(prefix)?;
+        // placeholder objects for "directories" have nothing to sync
+        (!relative.is_empty() && !relative.ends_with('/')).then_some(relative)
+    };
+    Ok(match compare {
+        SyncCompare::SizeMtime => storage::list_recursive(prefix)?
+            .into_iter()
+            .filter_map(|ObjectEntry { uri, size, updated }| {
+                let modified = updated.as_deref().and_then(util::parse_timestamp);
+                Some((relative_to_prefix(&uri)?, SideEntry { size, modified, metadata: None }))
+            })
+            .collect(),
+        // the hashes come with the listing, so no object needs a metadata call of its own
+        SyncCompare::Checksum => storage::list_metadata_recursive(prefix)?
+            .into_iter()
+            .filter_map(|(uri, metadata)| {
+                let modified = metadata.updated.as_deref().and_then(util::parse_timestamp);
+                let entry = SideEntry { size: metadata.size, modified, metadata: Some(metadata) };
+                Some((relative_to_prefix(&uri)?, entry))
+            })
+            .collect(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn side(files: &[(&str, u64, u64)]) -> HashMap<String, SideEntry> {
+        files
+            .iter()
+            .map(|(relative, size, modified)| {
+                (relative.to_string(), SideEntry { size: *size, modified: Some(*modified), metadata: None })
+            })
+            .collect()
+    }
+
+    fn request(direction: SyncDirection) -> Result<SyncRequest, Action> {
+        let mut request = SyncRequest::new(PathBuf::from("/sync/local"), CloudUri::parse("gs://bucket/remote/")?);
+        request.direction = direction;
+        request.delete_extraneous = true;
+        Ok(request)
+    }
+
+    fn summary(entries: &[SyncEntry]) -> Vec<(SyncAction, &str)> {
+        entries.iter().map(|entry| (entry.action, entry.relative.as_str())).collect()
+    }
+
+    #[test]
+    fn up_adds_updates_and_deletes() -> Result<(), Action> {
+        let local = side(&[("new.txt", 1, 10), ("grown.txt", 2, 10), ("newer.txt", 3, 20), ("same.txt", 4, 10)]);
+        let remote = side(&[("grown.txt", 1, 10), ("newer.txt", 3, 10), ("same.txt", 4, 10), ("gone.txt", 5, 10)]);
+        let entries = plan_sides(&request(SyncDirection::Up)?, &local, remote)?;
+        assert_eq!(
+            summary(&entries),
+            vec![
+                (SyncAction::Delete, "gone.txt"),
+                (SyncAction::Update, "grown.txt"),
+                (SyncAction::Add, "new.txt"),
+                (SyncAction::Update, "newer.txt"),
+            ]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn excluded_entries_are_not_transferred() -> Result<(), Action> {
+        let request = request(SyncDirection::Down)?;
+        let mut entries = plan_sides(&request, &side(&[]), side(&[("a.txt", 1, 10), ("b/c.txt", 2, 10)]))?;
+        entries[0].excluded = true;
+        let jobs = jobs(&request, &entries);
+        assert_eq!(jobs.len(), 1);
+        assert!(matches!(&jobs[0], (TransferKind::Download, Endpoint::Remote(remote), Endpoint::Local(local), None)
+            if remote.to_string() == "gs://bucket/remote/b/c.txt" && local == &Path::new("/sync/local").join("b").join("c.txt")));
+        Ok(())
+    }
+
+    #[test]
+    fn keys_leaving_the_directory_are_left_out() -> Result<(), Action> {
+        let request = request(SyncDirection::Down)?;
+        let remote = side(&[("ok.txt", 1, 10), ("../evil.txt", 1, 10), ("a/../../evil.txt", 1, 10), ("/etc/passwd", 1, 10)]);
+        let entries = plan_sides(&request, &side(&[("stale.txt", 1, 10)]), remote)?;
+        assert_eq!(summary(&entries), vec![(SyncAction::Add, "ok.txt"), (SyncAction::Delete, "stale.txt")]);
+        for job in jobs(&request, &entries) {
+            assert!(matches!(&job.2, Endpoint::Local(local) if local.starts_with(&request.local)), "{job:?}");
+        }
+        Ok(())
+    }
+}
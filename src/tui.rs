@@ -1,35 +1,64 @@
 use std::io::{stdout, Stdout};
 use std::result::Result;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::sync::Arc;
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, Instant};
 
 use crossterm::{
-    event::{EnableMouseCapture, KeyEvent, MouseEvent},
+    event::{
+        DisableBracketedPaste, DisableFocusChange, EnableBracketedPaste, EnableFocusChange, EnableMouseCapture,
+        Event as CrosstermEvent, KeyEvent, MouseEvent,
+    },
     terminal::EnterAlternateScreen,
 };
 use ratatui::backend::CrosstermBackend as Backend;
+use tracing::info;
 
+/// Housekeeping rounds per second: starting queued transfers, expiring key sequences
+pub const DEFAULT_TICK_RATE: f64 = 4.0;
+/// At most this many frames are drawn per second
+pub const DEFAULT_FRAME_RATE: f64 = 30.0;
+/// Slowest and fastest tick or frame rate the config file may ask for
+pub const RATES: std::ops::RangeInclusive<f64> = 0.1..=1000.0;
+
+/// What the input thread sends to the app
 #[derive(Clone, Debug)]
-pub enum _Event {
-    Init,
-    Quit,
-    Error,
-    Closed,
+pub enum Event {
     Tick,
     Render,
     Key(KeyEvent),
     Mouse(MouseEvent),
     Resize(u16, u16),
+    FocusGained,
+    Paste(String),
+    Error(String),
 }
 
 pub struct Tui {
     pub terminal: ratatui::Terminal<Backend<Stdout>>,
+    pub tick_rate: Duration,
+    pub frame_rate: Duration,
+    events: Option<Receiver<Event>>,
+    stop: Arc<AtomicBool>,
+    input: Option<JoinHandle<()>>,
 }
 
 impl Tui {
-    pub fn new() -> Result<Self, String> {
+    /// Rates are per second and expected within `RATES`
+    pub fn new(tick_rate: f64, frame_rate: f64) -> Result<Self, String> {
         let terminal = ratatui::Terminal::new(Backend::new(stdout()));
 
         match terminal {
-            Ok(term) => Ok(Self { terminal: term }),
+            Ok(term) => Ok(Self {
+                terminal: term,
+                tick_rate: Duration::from_secs_f64(1.0 / tick_rate.clamp(*RATES.start(), *RATES.end())),
+                frame_rate: Duration::from_secs_f64(1.0 / frame_rate.clamp(*RATES.start(), *RATES.end())),
+                events: None,
+                stop: Arc::new(AtomicBool::new(false)),
+                input: None,
+            }),
             Err(_) => {
                 let message = "Error attaching a new Ratatui terminal".to_string();
                 Err(message)
@@ -40,8 +69,17 @@ impl Tui {
     pub fn enter(&mut self) -> Result<(), String> {
         match crossterm::terminal::enable_raw_mode() {
             Ok(_) => {
-                match crossterm::execute!(stdout(), EnterAlternateScreen, EnableMouseCapture) {
-                    Ok(e) => Ok(e),
+                match crossterm::execute!(
+                    stdout(),
+                    EnterAlternateScreen,
+                    EnableMouseCapture,
+                    EnableFocusChange,
+                    EnableBracketedPaste
+                ) {
+                    Ok(_) => {
+                        self.start();
+                        Ok(())
+                    }
                     Err(_) => Err("error executing the terminal".to_string()),
                 }
             }
@@ -49,11 +87,40 @@ impl Tui {
         }
     }
 
+    /// Starts the input thread, which reads the terminal and keeps the tick and render beat
+    fn start(&mut self) {
+        let (sender, receiver) = mpsc::channel();
+        self.stop.store(false, Ordering::Relaxed);
+        let stop = self.stop.clone();
+        let (tick_rate, frame_rate) = (self.tick_rate, self.frame_rate);
+        self.input = Some(thread::spawn(move || read_events(sender, stop, tick_rate, frame_rate)));
+        self.events = Some(receiver);
+    }
+
+    /// Stops the input thread; it notices within one tick or frame
+    fn stop(&mut self) {
+        self.stop.store(true, Ordering::Relaxed);
+        if let Some(input) = self.input.take() {
+            if input.join().is_err() {
+                info!("The input thread panicked");
+            }
+        }
+        self.events = None;
+    }
+
+    /// Waits for the next event; `None` once the input thread is gone
+    pub fn next_event(&self) -> Option<Event> {
+        self.events.as_ref()?.recv().ok()
+    }
+
     pub fn exit(&mut self) -> Result<(), String> {
+        self.stop();
         match crossterm::terminal::disable_raw_mode() {
             Ok(()) => {
                 match crossterm::execute!(
                     self.terminal.backend_mut(),
+                    DisableBracketedPaste,
+                    DisableFocusChange,
                     crossterm::terminal::LeaveAlternateScreen,
                     crossterm::event::DisableMouseCapture
                 ) {
@@ -87,3 +154,43 @@ impl Tui {
         }
     }
 }
+
+/// Sends every terminal event, a `Tick` every `tick_rate` and a `Render` every `frame_rate`
+/// until `stop` is set or the app stops listening
+fn read_events(sender: Sender<Event>, stop: Arc<AtomicBool>, tick_rate: Duration, frame_rate: Duration) {
+    let mut next_tick = Instant::now() + tick_rate;
+    let mut next_render = Instant::now();
+
+    while !stop.load(Ordering::Relaxed) {
+        let now = Instant::now();
+        let mut due = vec![];
+        if now >= next_tick {
+            due.push(Event::Tick);
+            next_tick = now + tick_rate;
+        }
+        if now >= next_render {
+            due.push(Event::Render);
+            next_render = now + frame_rate;
+        }
+
+        let timeout = next_tick.min(next_render).saturating_duration_since(now);
+        let event = match crossterm::event::poll(timeout) {
+            Ok(false) => None,
+            Ok(true) => match crossterm::event::read() {
+                Ok(CrosstermEvent::Key(key)) => Some(Event::Key(key)),
+                Ok(CrosstermEvent::Mouse(mouse)) => Some(Event::Mouse(mouse)),
+                Ok(CrosstermEvent::Resize(columns, rows)) => Some(Event::Resize(columns, rows)),
+                Ok(CrosstermEvent::FocusGained) => Some(Event::FocusGained),
+                Ok(CrosstermEvent::Paste(text)) => Some(Event::Paste(text)),
+                Ok(CrosstermEvent::FocusLost) => None,
+                Err(e) => Some(Event::Error(format!("Error reading events: {e}"))),
+            },
+            Err(e) => Some(Event::Error(format!("Error polling for events: {e}"))),
+        };
+        for event in due.into_iter().chain(event) {
+            if sender.send(event).is_err() {
+                return;
+            }
+        }
+    }
+}
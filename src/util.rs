@@ -1,10 +1,13 @@
+use std::collections::{HashMap, HashSet};
 use std::process::Command;
 
 use ego_tree::{iter::Nodes, NodeRef};
+use ratatui::layout::{Constraint, Layout, Rect};
+use ratatui::text::{Line, Span};
 use tracing::info;
 use tui_tree_widget::TreeItem;
 
-use crate::{action::Action, app::Focus, components::results_pager::ResultsPager};
+use crate::{action::Action, app::Focus, components::results_pager::ResultsPager, config::theme::Theme};
 
 pub fn cli_command(program: &str, args: &Vec<&str>) -> Result<Vec<u8>, Action> {
     info!("CLI Command {program} {args:?}");
@@ -17,11 +20,76 @@ pub fn cli_command(program: &str, args: &Vec<&str>) -> Result<Vec<u8>, Action> {
     }
 }
 
+pub fn cli_command_checked(program: &str, args: &[&str]) -> Result<Vec<u8>, Action> {
+    info!("CLI Command {program} {args:?}");
+    match Command::new(program).args(args).output() {
+        Ok(output) if output.status.success() => Ok(output.stdout),
+        Ok(output) => {
+            let stderr = String::from_utf8_lossy(&output.stderr).trim().to_string();
+            let message = format!("{program} {} failed: {stderr}", args.join(" "));
+            Err(Action::Error(message))
+        }
+        Err(_) => {
+            let message = [program.to_string(), args.join(" ")].join(" ");
+            Err(Action::Error(message))
+        }
+    }
+}
+
+/// Seconds since the epoch of a provider timestamp: `YYYY-MM-DDTHH:MM:SS` (anything after the seconds
+/// is ignored), a bare `YYYY-MM-DD`, or the `Tue, 02 Jan 2024 10:11:12 GMT` form `gsutil stat` prints
+pub fn parse_timestamp(timestamp: &str) -> Option<u64> {
+    let number = |text: &str, range: std::ops::Range<usize>| text.get(range)?.parse::<i64>().ok();
+    let (year, month, day, time) = match number(timestamp, 0..4) {
+        Some(year) => (year, number(timestamp, 5..7)?, number(timestamp, 8..10)?, timestamp.get(11..)),
+        None => {
+            let fields: Vec<&str> = timestamp.split_whitespace().collect();
+            let months = ["Jan", "Feb", "Mar", "Apr", "May", "Jun", "Jul", "Aug", "Sep", "Oct", "Nov", "Dec"];
+            let month = months.iter().position(|name| Some(name) == fields.get(2))?;
+            let year = fields.get(3)?.parse::<i64>().ok()?;
+            let day = fields.get(1)?.parse::<i64>().ok()?;
+            (year, month as i64 + 1, day, fields.get(4).copied())
+        }
+    };
+    let (hour, minute, second) = match time.filter(|time| !time.is_empty()) {
+        Some(time) => (number(time, 0..2)?, number(time, 3..5)?, number(time, 6..8)?),
+        None => (0, 0, 0),
+    };
+
+    // days from civil, http://howardhinnant.github.io/date_algorithms.html
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year.div_euclid(400);
+    let year_of_era = year - era * 400;
+    let day_of_year = (153 * (month + if month > 2 { -3 } else { 9 }) + 2) / 5 + day - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+    let days = era * 146_097 + day_of_era - 719_468;
+
+    u64::try_from(days * 86_400 + hour * 3_600 + minute * 60 + second).ok()
+}
+
+pub fn human_bytes(bytes: u64) -> String {
+    let units = ["B", "KiB", "MiB", "GiB", "TiB"];
+    let mut value = bytes as f64;
+    let mut unit = 0;
+    while value >= 1024.0 && unit < units.len() - 1 {
+        value /= 1024.0;
+        unit += 1;
+    }
+    if unit == 0 {
+        format!("{bytes} {}", units[unit])
+    } else {
+        format!("{value:.1} {}", units[unit])
+    }
+}
+
 
 pub fn make_tree_items(
     nodes: Nodes<String>,
     results_pager: &mut ResultsPager,
     focus: Focus,
+    marked: &HashSet<String>,
+    details: &HashMap<String, String>,
+    theme: &Theme,
 ) -> Vec<TreeItem<'static, String>> {
     let mut root_vec = vec![];
 
@@ -32,7 +100,7 @@ pub fn make_tree_items(
             let mut ti = TreeItem::new(identifier.clone(), identifier.clone(), vec![])
                 .expect("error creating nodes under parent");
 
-            add_children(node, &mut ti, &mut results_pager.clone(), focus);
+            add_children(node, &mut ti, &mut results_pager.clone(), focus, marked, details, theme);
             root_vec.push(ti);
         });
 
@@ -44,6 +112,9 @@ pub fn add_children(
     tree_item: &mut TreeItem<String>,
     results_pager: &mut ResultsPager,
     focus: Focus,
+    marked: &HashSet<String>,
+    details: &HashMap<String, String>,
+    theme: &Theme,
 ) {
     if node.has_children() {
         let num_node_children = node.children().count();
@@ -91,10 +162,10 @@ pub fn add_children(
                         };
 
                         let mut child_ti =
-                            TreeItem::new(child_val.clone(), clean_text.clone(), vec![])
+                            TreeItem::new(child_val.clone(), tree_item_text(&child_val, clean_text, marked, details, theme), vec![])
                                 .expect("error creating child node");
 
-                        add_children(*n, &mut child_ti, &mut results_pager.clone(), focus);
+                        add_children(*n, &mut child_ti, &mut results_pager.clone(), focus, marked, details, theme);
                         tree_item
                             .add_child(child_ti)
                             .expect("error adding child to the tree item");
@@ -121,9 +192,9 @@ pub fn add_children(
                     _ => child_val.clone(),
                 };
 
-                let mut child_ti = TreeItem::new(child_val.clone(), clean_text.clone(), vec![])
+                let mut child_ti = TreeItem::new(child_val.clone(), tree_item_text(&child_val, clean_text, marked, details, theme), vec![])
                     .expect("error creating child node");
-                add_children(n, &mut child_ti, &mut results_pager.clone(), focus);
+                add_children(n, &mut child_ti, &mut results_pager.clone(), focus, marked, details, theme);
                 tree_item
                     .add_child(child_ti)
                     .expect("error adding child to the tree item");
@@ -131,3 +202,39 @@ pub fn add_children(
         }
     }
 }
+
+/// Marked items get a bullet and stand out from the rest of the listing, shown columns follow the name
+fn tree_item_text(
+    value: &str,
+    text: String,
+    marked: &HashSet<String>,
+    details: &HashMap<String, String>,
+    theme: &Theme,
+) -> Line<'static> {
+    let mut line = if marked.contains(value) {
+        Line::styled(format!("● {text}"), theme.marked)
+    } else {
+        Line::raw(text)
+    };
+    if let Some(detail) = details.get(value) {
+        line.push_span(Span::styled(format!("  {detail}"), theme.muted));
+    }
+    line
+}
+
+/// A rectangle centered within `area`, sized as a percentage of it
+pub fn popup_area(area: Rect, percent_x: u16, percent_y: u16) -> Rect {
+    let [_, vertical, _] = Layout::vertical([
+        Constraint::Percentage((100 - percent_y) / 2),
+        Constraint::Percentage(percent_y),
+        Constraint::Percentage((100 - percent_y) / 2),
+    ])
+    .areas(area);
+    let [_, centered, _] = Layout::horizontal([
+        Constraint::Percentage((100 - percent_x) / 2),
+        Constraint::Percentage(percent_x),
+        Constraint::Percentage((100 - percent_x) / 2),
+    ])
+    .areas(vertical);
+    centered
+}